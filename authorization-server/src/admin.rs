@@ -0,0 +1,341 @@
+/// The `admin` module handles the administrative endpoints of the Authorization Server.
+/// These endpoints require the bearer token configured with `ADMIN_TOKEN` and are disabled
+/// when no token is configured.
+use crate::{
+    SharedAppState,
+    keys::{self, SigningKey, StoredSigningKey},
+    metadata::GRANT_TYPES_SUPPORTED,
+    now,
+    register::{InitialAccessToken, RegistrationLimits},
+    scope, secret,
+    user::User,
+};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode, header},
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Represents the response of a key rotation.
+#[derive(Serialize)]
+pub struct RotateKeysResponse {
+    /// The key ID of the new active signing key.
+    pub kid: String,
+    /// The key IDs currently published at `/jwks.json`, the active key first.
+    pub published: Vec<String>,
+}
+
+/// Represents the request body for the `/admin/users` endpoint.
+#[derive(Deserialize)]
+pub struct CreateUserRequest {
+    /// The name the user logs in with.
+    pub username: String,
+    /// The password of the user.
+    pub password: String,
+}
+
+/// Represents the response of a user creation.
+#[derive(Serialize)]
+pub struct CreateUserResponse {
+    /// The ID of the new user, the subject of the tokens issued on their behalf.
+    pub user_id: String,
+}
+
+/// Represents the request body for the `/admin/initial-access-tokens` endpoint.
+#[derive(Deserialize)]
+pub struct CreateInitialAccessTokenRequest {
+    /// The space-delimited scopes clients registered with the token may request (optional,
+    /// every supported scope if omitted).
+    pub scope: Option<String>,
+    /// The grant types clients registered with the token may use (optional, every supported
+    /// grant type if omitted).
+    pub grant_types: Option<Vec<String>>,
+    /// How many clients may be registered with the token (optional, unlimited if omitted).
+    pub max_uses: Option<u32>,
+}
+
+/// Represents a newly issued initial access token.
+#[derive(Serialize)]
+pub struct CreateInitialAccessTokenResponse {
+    /// The token to send as bearer token to `/register`.
+    pub initial_access_token: String,
+    /// The scopes clients registered with the token may request, if limited.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    /// The grant types clients registered with the token may use, if limited.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grant_types: Option<Vec<String>>,
+    /// How many clients may be registered with the token, if limited.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_uses: Option<u32>,
+    /// The lifetime of the token in seconds.
+    pub expires_in: u64,
+}
+
+/// Represents the request body for the `/admin/initial-access-tokens/revoke` endpoint.
+#[derive(Deserialize)]
+pub struct RevokeInitialAccessTokenRequest {
+    /// The initial access token to revoke.
+    pub initial_access_token: String,
+}
+
+/// Builds the error response for an unexpected server failure.
+fn server_error(err: impl std::fmt::Display) -> (StatusCode, String) {
+    tracing::error!("Admin request failed: {}", err);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "server_error".to_string(),
+    )
+}
+
+/// Checks that the request carries the configured admin token.
+fn authorize_admin(
+    app_state: &SharedAppState,
+    headers: &HeaderMap,
+) -> Result<(), (StatusCode, String)> {
+    let Some(admin_token) = app_state.lock().unwrap().config.admin_token.clone() else {
+        return Err((StatusCode::NOT_FOUND, "not_found".to_string()));
+    };
+
+    let presented = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if !presented.is_some_and(|presented| secret::constant_time_eq(presented, &admin_token)) {
+        tracing::warn!("Rejected admin request with missing or invalid token");
+        return Err((StatusCode::UNAUTHORIZED, "unauthorized".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Handles the `/admin/keys/rotate` endpoint.
+///
+/// Generates a new signing key and makes it the active key. The previous key stays published
+/// at `/jwks.json` until the access tokens it signed have expired.
+///
+/// The new key and the retirement of the previous one are written to the storage, so that the
+/// rotation survives restarts and reaches every replica sharing the storage.
+///
+/// # Returns
+/// - `Json<RotateKeysResponse>`: The new key ID and the published key IDs.
+/// - `(StatusCode, String)`: An error response if the request is not authorized.
+pub async fn rotate_keys(
+    State(app_state): State<SharedAppState>,
+    headers: HeaderMap,
+) -> Result<Json<RotateKeysResponse>, (StatusCode, String)> {
+    authorize_admin(&app_state, &headers)?;
+
+    // Key generation is CPU-bound, so keep it off the async runtime and outside of the lock
+    let key = tokio::task::spawn_blocking(SigningKey::generate)
+        .await
+        .map_err(|err| err.to_string())
+        .and_then(|key| key.map_err(|err| err.to_string()))
+        .map_err(server_error)?;
+
+    let private_key_pem = key.private_key_pem().map_err(server_error)?;
+
+    let mut state = app_state.lock().unwrap();
+    // Retire the key that is active across all replicas, not just the one this replica knows
+    keys::sync(&mut state).map_err(server_error)?;
+
+    let now = now();
+    let stored = state.storage.signing_keys().map_err(server_error)?;
+    for (kid, mut record) in stored {
+        if record.retired_at.is_none() {
+            record.retired_at = Some(now);
+        }
+        // Forget keys whose tokens have all expired
+        if record
+            .retired_at
+            .is_some_and(|retired_at| !state.keys.is_retained(retired_at))
+        {
+            state
+                .storage
+                .remove_signing_key(&kid)
+                .map_err(server_error)?;
+        } else {
+            state
+                .storage
+                .put_signing_key(&kid, record)
+                .map_err(server_error)?;
+        }
+    }
+
+    let kid = key.kid.clone();
+    state
+        .storage
+        .put_signing_key(
+            &kid,
+            StoredSigningKey {
+                private_key_pem,
+                created_at: now,
+                retired_at: None,
+            },
+        )
+        .map_err(server_error)?;
+    state.keys.rotate(key);
+    tracing::info!("Rotated signing key, new key ID: {}", kid);
+
+    Ok(Json(RotateKeysResponse {
+        kid,
+        published: state.keys.published().map(|key| key.kid.clone()).collect(),
+    }))
+}
+
+/// Handles the `/admin/users` endpoint.
+///
+/// Creates a user who can log in at `/authorize`. Usernames are unique.
+///
+/// # Returns
+/// - `Json<CreateUserResponse>`: The ID of the new user.
+/// - `(StatusCode, String)`: An error response if the request is not authorized or invalid.
+pub async fn create_user(
+    State(app_state): State<SharedAppState>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateUserRequest>,
+) -> Result<Json<CreateUserResponse>, (StatusCode, String)> {
+    authorize_admin(&app_state, &headers)?;
+
+    if payload.username.is_empty() || payload.password.is_empty() {
+        tracing::warn!("Rejected user without username or password");
+        return Err((StatusCode::BAD_REQUEST, "invalid_request".to_string()));
+    }
+
+    // Password hashing is CPU-bound, so keep it off the async runtime and outside of the lock
+    let user = tokio::task::spawn_blocking(move || User::new(&payload.username, &payload.password))
+        .await
+        .map_err(server_error)?
+        .map_err(server_error)?;
+
+    let mut state = app_state.lock().unwrap();
+    if state
+        .storage
+        .get_user_by_username(&user.username)
+        .map_err(server_error)?
+        .is_some()
+    {
+        tracing::warn!("Username already taken: {}", user.username);
+        return Err((StatusCode::CONFLICT, "username_taken".to_string()));
+    }
+
+    let user_id = Uuid::new_v4().to_string();
+    tracing::info!("Created user {} with ID: {}", user.username, user_id);
+    state
+        .storage
+        .put_user(&user_id, user)
+        .map_err(server_error)?;
+
+    Ok(Json(CreateUserResponse { user_id }))
+}
+
+/// Handles the `/admin/initial-access-tokens` endpoint.
+///
+/// Issues an initial access token (RFC 7591, Section 3) that authorizes client registrations
+/// under the `initial_access_token` registration policy. The scopes and grant types of the
+/// clients registered with it can be limited, as can the number of clients. The token expires
+/// after `INITIAL_ACCESS_TOKEN_LIFETIME`.
+///
+/// # Returns
+/// - `Json<CreateInitialAccessTokenResponse>`: The token and its limits.
+/// - `(StatusCode, String)`: An error response if the request is not authorized or invalid.
+pub async fn create_initial_access_token(
+    State(app_state): State<SharedAppState>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateInitialAccessTokenRequest>,
+) -> Result<Json<CreateInitialAccessTokenResponse>, (StatusCode, String)> {
+    authorize_admin(&app_state, &headers)?;
+
+    let mut state = app_state.lock().unwrap();
+
+    let scopes = payload.scope.as_deref().map(scope::parse);
+    if let Some(scopes) = &scopes
+        && !scope::is_subset(scopes, &scope::parse(&state.config.supported_scopes))
+    {
+        tracing::warn!(
+            "Rejected initial access token for unsupported scope: {:?}",
+            scopes
+        );
+        return Err((StatusCode::BAD_REQUEST, "invalid_scope".to_string()));
+    }
+    if let Some(grant_types) = &payload.grant_types
+        && !grant_types
+            .iter()
+            .all(|grant_type| GRANT_TYPES_SUPPORTED.contains(&grant_type.as_str()))
+    {
+        tracing::warn!(
+            "Rejected initial access token for unsupported grant types: {:?}",
+            grant_types
+        );
+        return Err((StatusCode::BAD_REQUEST, "invalid_request".to_string()));
+    }
+    if payload.max_uses == Some(0) {
+        tracing::warn!("Rejected initial access token without any use");
+        return Err((StatusCode::BAD_REQUEST, "invalid_request".to_string()));
+    }
+
+    let initial_access_token = Uuid::new_v4().to_string();
+    let expires_in = state.config.initial_access_token_lifetime;
+    state
+        .storage
+        .put_initial_access_token(
+            &secret::lookup_hash(&initial_access_token),
+            InitialAccessToken {
+                limits: RegistrationLimits {
+                    scopes: scopes.clone(),
+                    grant_types: payload.grant_types.clone(),
+                },
+                expires_at: now() + expires_in,
+                remaining_uses: payload.max_uses,
+            },
+        )
+        .map_err(server_error)?;
+    tracing::info!("Issued initial access token");
+
+    Ok(Json(CreateInitialAccessTokenResponse {
+        initial_access_token,
+        scope: scopes.as_deref().map(scope::join),
+        grant_types: payload.grant_types,
+        max_uses: payload.max_uses,
+        expires_in,
+    }))
+}
+
+/// Handles the `/admin/initial-access-tokens/revoke` endpoint.
+///
+/// Revokes an initial access token before it expires or runs out of uses. Clients already
+/// registered with it are not affected.
+///
+/// # Returns
+/// - `StatusCode`: `204 No Content` once the token is revoked.
+/// - `(StatusCode, String)`: An error response if the request is not authorized or the token
+///   does not exist.
+pub async fn revoke_initial_access_token(
+    State(app_state): State<SharedAppState>,
+    headers: HeaderMap,
+    Json(payload): Json<RevokeInitialAccessTokenRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    authorize_admin(&app_state, &headers)?;
+
+    let mut state = app_state.lock().unwrap();
+    let token_hash = secret::lookup_hash(&payload.initial_access_token);
+    if state
+        .storage
+        .get_initial_access_token(&token_hash)
+        .map_err(server_error)?
+        .is_none()
+    {
+        tracing::warn!("Rejected revocation of an unknown initial access token");
+        return Err((StatusCode::NOT_FOUND, "not_found".to_string()));
+    }
+
+    state
+        .storage
+        .remove_initial_access_token(&token_hash)
+        .map_err(server_error)?;
+    tracing::info!("Revoked initial access token");
+
+    Ok(StatusCode::NO_CONTENT)
+}
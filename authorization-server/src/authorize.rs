@@ -1,13 +1,21 @@
 /// The `authorize` module handles the `/authorize` endpoint of the Authorization Server.
-/// This endpoint is responsible for generating authorization codes for clients.
-use crate::SharedAppState;
+/// This endpoint authenticates the user and generates authorization codes for clients.
+use crate::{
+    AppState, SharedAppState,
+    config::Config,
+    consent, login, now,
+    pkce::{self, CodeChallenge, CodeChallengeMethod},
+    redirect_uri, resource, scope,
+    session::{self, Session},
+    storage::StorageError,
+    user,
+};
 use axum::{
-    extract::{Query, State},
-    http::StatusCode,
-    response::{Json, Redirect},
+    extract::{Form, Query, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Json, Redirect, Response},
 };
 use serde::{Deserialize, Serialize};
-use tracing;
 use uuid::Uuid;
 
 /// Represents the query parameters for the `/authorize` endpoint.
@@ -16,13 +24,108 @@ pub struct AuthorizationRequest {
     /// The client ID of the requesting client.
     pub client_id: String,
     /// The response type (e.g., "code").
-    pub response_type: String,
+    pub response_type: Option<String>,
     /// The redirect URI to which the authorization code will be sent.
     pub redirect_uri: Option<String>,
     /// The requested scope (optional).
     pub scope: Option<String>,
     /// The state parameter to prevent CSRF attacks.
     pub state: Option<String>,
+    /// The PKCE code challenge derived from the client's code verifier (optional).
+    pub code_challenge: Option<String>,
+    /// The method used to derive the code challenge ("plain" or "S256", defaults to "plain").
+    pub code_challenge_method: Option<String>,
+    /// The resource the client requests access to (RFC 8707, optional).
+    pub resource: Option<String>,
+    /// The value the client expects in the ID token, to mitigate replay attacks (OpenID Connect).
+    pub nonce: Option<String>,
+    /// The space-delimited prompts the client asks for ("none" or "consent", OpenID Connect).
+    pub prompt: Option<String>,
+}
+
+impl AuthorizationRequest {
+    /// Returns the name and value of every parameter sent with the request.
+    pub fn params(&self) -> Vec<(&'static str, &str)> {
+        let optional = [
+            ("response_type", &self.response_type),
+            ("redirect_uri", &self.redirect_uri),
+            ("scope", &self.scope),
+            ("state", &self.state),
+            ("code_challenge", &self.code_challenge),
+            ("code_challenge_method", &self.code_challenge_method),
+            ("resource", &self.resource),
+            ("nonce", &self.nonce),
+            ("prompt", &self.prompt),
+        ];
+        let mut params = vec![("client_id", self.client_id.as_str())];
+        params.extend(
+            optional
+                .into_iter()
+                .filter_map(|(name, value)| Some((name, value.as_deref()?))),
+        );
+        params
+    }
+}
+
+/// Represents the login and consent forms posted to the `/authorize` endpoint.
+///
+/// The forms repeat the parameters of the authorization request next to the user's input.
+#[derive(Deserialize)]
+pub struct AuthorizationForm {
+    /// The authorization request the form was shown for.
+    #[serde(flatten)]
+    pub request: AuthorizationRequest,
+    /// The username of the user, sent by the login form.
+    pub username: Option<String>,
+    /// The password of the user, sent by the login form.
+    pub password: Option<String>,
+    /// The decision of the user on the consent screen ("allow" or "deny").
+    pub consent: Option<String>,
+    /// The anti-CSRF token of the form, which must match the one in the browser's CSRF cookie.
+    pub csrf_token: Option<String>,
+}
+
+/// Represents an issued authorization code awaiting redemption at the `/token` endpoint.
+///
+/// Redeemed codes are kept until they expire, so that a replayed code can be detected and the
+/// tokens issued from it revoked.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AuthorizationCode {
+    /// The client ID the code was issued to.
+    pub client_id: String,
+    /// The ID of the user who authorized the client, the subject of the issued tokens.
+    pub sub: String,
+    /// The time the user logged in (UNIX timestamp).
+    pub auth_time: u64,
+    /// The time the code was issued (UNIX timestamp).
+    pub issued_at: u64,
+    /// The redirect URI sent to `/authorize`, which must be repeated at `/token`.
+    pub redirect_uri: Option<String>,
+    /// The scope requested by the client, if any.
+    pub requested_scope: Option<String>,
+    /// The scopes granted to the client.
+    pub scopes: Vec<String>,
+    /// The state parameter sent to `/authorize`.
+    pub state: Option<String>,
+    /// The PKCE code challenge the code is bound to, if any.
+    pub code_challenge: Option<CodeChallenge>,
+    /// Whether the code has already been exchanged for tokens.
+    pub redeemed: bool,
+    /// The family of the refresh tokens issued from the code, if any.
+    pub refresh_token_family: Option<String>,
+    /// The resource the tokens issued from the code are intended for.
+    #[serde(default)]
+    pub resource: Option<String>,
+    /// The `nonce` sent to `/authorize`, which is echoed in the ID token.
+    #[serde(default)]
+    pub nonce: Option<String>,
+}
+
+impl AuthorizationCode {
+    /// Checks whether the code is older than `lifetime` seconds.
+    pub fn is_expired(&self, lifetime: u64) -> bool {
+        now() > self.issued_at + lifetime
+    }
 }
 
 /// Represents a successful authorization response.
@@ -34,99 +137,653 @@ pub struct AuthorizationResponse {
     pub state: Option<String>,
 }
 
-/// Represents an error response for the `/authorize` endpoint.
+/// Represents an error response for the `/authorize` endpoint (RFC 6749, Section 4.1.2.1).
 #[derive(Serialize)]
 pub struct AuthorizationErrorResponse {
-    /// The error message.
+    /// The error code.
     pub error: String,
+    /// A human-readable explanation of the error, for the client developer.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_description: Option<String>,
+    /// A URI identifying a web page with information about the error.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_uri: Option<String>,
     /// The state parameter returned to the client.
     pub state: Option<String>,
 }
 
-/// Handles the `/authorize` endpoint.
-///
-/// This function validates the client request, generates an authorization code,
-/// and redirects the client to the specified redirect URI with the code and state.
-///
-/// # Arguments
-/// - `State(app_state)`: Shared application state.
-/// - `Query(params)`: Query parameters from the client request.
-///
-/// # Returns
-/// - `Redirect`: Redirects the client to the specified redirect URI with the authorization code.
-/// - `(StatusCode, Json<AuthorizationErrorResponse>)`: Returns an error response if validation fails.
-#[axum_macros::debug_handler]
-pub async fn authorize(
-    State(app_state): State<SharedAppState>,
-    Query(params): Query<AuthorizationRequest>,
-) -> Result<Redirect, (StatusCode, Json<AuthorizationErrorResponse>)> {
-    tracing::info!("Received authorization request: {:?}", params);
+/// The error type of the `/authorize` endpoint.
+pub enum AuthorizationError {
+    /// An error found before the client and its redirect URI were validated. It is shown to the
+    /// user, since redirecting to an unverified URI would make the server an open redirector.
+    Local(StatusCode, AuthorizationErrorResponse),
+    /// Any other error, which is sent back to the client at the given redirect URI.
+    Redirect(String, AuthorizationErrorResponse),
+}
 
-    let mut state = app_state.lock().unwrap();
+impl AuthorizationError {
+    /// Builds an error to be shown to the user.
+    fn local(
+        config: &Config,
+        status: StatusCode,
+        params: &AuthorizationRequest,
+        error: &str,
+        description: &str,
+    ) -> Self {
+        Self::Local(status, error_body(config, params, error, description))
+    }
+
+    /// Builds an error to be sent back to the client at `redirect_uri`.
+    fn redirect(
+        config: &Config,
+        redirect_uri: &str,
+        params: &AuthorizationRequest,
+        error: &str,
+        description: &str,
+    ) -> Self {
+        Self::Redirect(
+            redirect_uri.to_string(),
+            error_body(config, params, error, description),
+        )
+    }
+}
+
+impl IntoResponse for AuthorizationError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Local(status, body) => (status, Json(body)).into_response(),
+            Self::Redirect(redirect_uri, body) => {
+                let mut query = vec![("error", body.error.as_str())];
+                let optional = [
+                    ("error_description", &body.error_description),
+                    ("error_uri", &body.error_uri),
+                    ("state", &body.state),
+                ];
+                query.extend(
+                    optional
+                        .into_iter()
+                        .filter_map(|(name, value)| Some((name, value.as_deref()?))),
+                );
+                redirect(&redirect_uri, &query).into_response()
+            }
+        }
+    }
+}
+
+/// Builds the body of an error response, pointing `error_uri` at the configured documentation.
+fn error_body(
+    config: &Config,
+    params: &AuthorizationRequest,
+    error: &str,
+    description: &str,
+) -> AuthorizationErrorResponse {
+    AuthorizationErrorResponse {
+        error: error.to_string(),
+        error_description: Some(description.to_string()),
+        error_uri: config
+            .error_uri
+            .as_ref()
+            .map(|uri| format!("{uri}#{error}")),
+        state: params.state.clone(),
+    }
+}
+
+/// Builds the error for a failure of the storage backend, sent to `redirect_uri` if it is known.
+fn server_error(
+    config: &Config,
+    redirect_uri: Option<&str>,
+    params: &AuthorizationRequest,
+    err: StorageError,
+) -> AuthorizationError {
+    tracing::error!("Storage failure: {}", err);
+    let description = "The authorization server encountered an unexpected error";
+    match redirect_uri {
+        Some(redirect_uri) => {
+            AuthorizationError::redirect(config, redirect_uri, params, "server_error", description)
+        }
+        None => AuthorizationError::local(
+            config,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            params,
+            "server_error",
+            description,
+        ),
+    }
+}
+
+/// Represents an authorization request that passed validation.
+pub struct ValidatedRequest {
+    /// The name of the client, shown to the user.
+    pub client_name: String,
+    /// The redirect URI the authorization response is sent to.
+    pub redirect_uri: String,
+    /// The scopes that may be granted to the client.
+    pub scopes: Vec<String>,
+    /// The PKCE code challenge the code will be bound to, if any.
+    pub code_challenge: Option<CodeChallenge>,
+    /// The audience of the tokens issued from the code.
+    pub audience: String,
+    /// Whether the user must not be shown any page (`prompt=none`).
+    pub prompt_none: bool,
+    /// Whether the user must be asked for consent even if they already gave it (`prompt=consent`).
+    pub prompt_consent: bool,
+}
+
+/// Validates the parameters of an authorization request against the registered client.
+///
+/// Errors found before the redirect URI is validated are shown to the user; all later errors
+/// are sent back to the client at its redirect URI.
+fn validate_request(
+    state: &AppState,
+    params: &AuthorizationRequest,
+) -> Result<ValidatedRequest, AuthorizationError> {
+    let config = &state.config;
 
     // 1. Validate `client_id`
-    let client_data = state.client_registry.get(&params.client_id);
-    if client_data.is_none() {
+    let client_data = state
+        .storage
+        .get_client(&params.client_id)
+        .map_err(|err| server_error(config, None, params, err))?;
+    let Some(client) = client_data else {
         tracing::warn!("Invalid client_id: {}", params.client_id);
-        return Err((
+        return Err(AuthorizationError::local(
+            config,
             StatusCode::UNAUTHORIZED,
-            Json(AuthorizationErrorResponse {
-                error: "invalid_client".to_string(),
-                state: params.state.clone(),
-            }),
+            params,
+            "invalid_client",
+            "The client_id is unknown",
+        ));
+    };
+    let is_public_client = client.is_public();
+    let registered_redirect_uris = &client.redirect_uris;
+
+    // 2. Validate `redirect_uri`
+    //    Clients that registered several redirect URIs must name the one to use
+    let redirect_uri = match (&params.redirect_uri, registered_redirect_uris.as_slice()) {
+        (Some(uri), registered)
+            if registered
+                .iter()
+                .any(|registered| redirect_uri::matches(registered, uri)) =>
+        {
+            uri.clone()
+        }
+        (None, [registered]) => registered.clone(),
+        _ => {
+            tracing::warn!("Invalid redirect_uri: {:?}", params.redirect_uri);
+            return Err(AuthorizationError::local(
+                config,
+                StatusCode::BAD_REQUEST,
+                params,
+                "invalid_request",
+                "The redirect_uri is missing or not registered for the client",
+            ));
+        }
+    };
+    let fail = |error: &str, description: &str| {
+        AuthorizationError::redirect(config, &redirect_uri, params, error, description)
+    };
+
+    // 3. Validate `response_type`
+    match params.response_type.as_deref() {
+        Some("code") => {}
+        Some(response_type) => {
+            tracing::warn!("Unsupported response_type: {}", response_type);
+            return Err(fail(
+                "unsupported_response_type",
+                "Only the code response type is supported",
+            ));
+        }
+        None => {
+            tracing::warn!("Missing response_type");
+            return Err(fail("invalid_request", "The response_type is missing"));
+        }
+    }
+    if !client.allows_response_type("code") {
+        tracing::warn!(
+            "Client is not registered for response_type code: {}",
+            params.client_id
+        );
+        return Err(fail(
+            "unauthorized_client",
+            "The client is not registered for the code response type",
         ));
     }
-    let (_, registered_redirect_uris) = client_data.unwrap();
 
-    // 2. Validate `response_type`
-    if params.response_type != "code" {
-        tracing::warn!("Unsupported response_type: {}", params.response_type);
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(AuthorizationErrorResponse {
-                error: "unsupported_response_type".to_string(),
-                state: params.state.clone(),
-            }),
+    // 4. Validate `scope`: requested scopes the client is not allowed are dropped, and a
+    //    request without a scope is granted every scope the client registered for
+    let scopes = match &params.scope {
+        Some(requested) => scope::intersection(&scope::parse(requested), &client.scopes),
+        None => client.scopes.clone(),
+    };
+    if scopes.is_empty() {
+        tracing::warn!("Invalid scope: {:?}", params.scope);
+        return Err(fail(
+            "invalid_scope",
+            "None of the requested scopes is allowed for the client",
         ));
     }
+    tracing::info!("Granted scope: {}", scope::join(&scopes));
 
-    // 3. Validate `redirect_uri`
-    let redirect_uri = match &params.redirect_uri {
-        Some(uri) if registered_redirect_uris.contains(uri) => uri.clone(),
-        None if !registered_redirect_uris.is_empty() => registered_redirect_uris[0].clone(),
-        _ => {
-            tracing::warn!("Invalid redirect_uri: {:?}", params.redirect_uri);
-            return Err((
-                StatusCode::FORBIDDEN,
-                Json(AuthorizationErrorResponse {
-                    error: "invalid_redirect_uri".to_string(),
-                    state: params.state.clone(),
-                }),
+    // 5. Validate the PKCE parameters
+    let code_challenge = match (&params.code_challenge, &params.code_challenge_method) {
+        (Some(challenge), method) => {
+            let method = match method.as_deref().map(str::parse) {
+                None => CodeChallengeMethod::Plain,
+                Some(Ok(method)) => method,
+                Some(Err(())) => {
+                    tracing::warn!("Unsupported code_challenge_method: {:?}", method);
+                    return Err(fail(
+                        "invalid_request",
+                        "The code_challenge_method is not supported",
+                    ));
+                }
+            };
+            if !pkce::is_valid_code_verifier(challenge) {
+                tracing::warn!("Malformed code_challenge: {}", challenge);
+                return Err(fail("invalid_request", "The code_challenge is malformed"));
+            }
+            Some(CodeChallenge {
+                challenge: challenge.clone(),
+                method,
+            })
+        }
+        (None, Some(_)) => {
+            tracing::warn!("code_challenge_method sent without code_challenge");
+            return Err(fail(
+                "invalid_request",
+                "The code_challenge_method was sent without a code_challenge",
             ));
         }
+        (None, None) => None,
     };
 
-    // 4. Validate `scope` (optional, for now we assume all scopes are valid)
-    if let Some(scope) = &params.scope {
-        tracing::info!("Requested scope: {}", scope);
+    if code_challenge.is_none() && is_public_client && config.require_pkce_for_public_clients {
+        tracing::warn!("PKCE required for public client_id: {}", params.client_id);
+        return Err(fail(
+            "invalid_request",
+            "Public clients must send a code_challenge",
+        ));
+    }
+
+    // 6. Validate the requested `resource` (RFC 8707)
+    let Some(audience) = resource::audience(config, params.resource.as_deref()) else {
+        tracing::warn!("Unknown resource: {:?}", params.resource);
+        return Err(fail("invalid_target", "The resource is unknown"));
+    };
+
+    // 7. Validate `prompt`: `none` cannot be combined with prompts that show a page
+    let prompts = params
+        .prompt
+        .as_deref()
+        .map(scope::parse)
+        .unwrap_or_default();
+    let prompt_none = prompts.iter().any(|prompt| prompt == "none");
+    let prompt_consent = prompts.iter().any(|prompt| prompt == "consent");
+    if prompt_none && prompts.len() > 1 {
+        tracing::warn!(
+            "prompt=none combined with other prompts: {:?}",
+            params.prompt
+        );
+        return Err(fail(
+            "invalid_request",
+            "prompt=none cannot be combined with other prompts",
+        ));
+    }
+
+    Ok(ValidatedRequest {
+        client_name: client
+            .client_name
+            .unwrap_or_else(|| params.client_id.clone()),
+        redirect_uri,
+        scopes,
+        code_challenge,
+        audience,
+        prompt_none,
+        prompt_consent,
+    })
+}
+
+/// Builds a redirect to `redirect_uri` with `params` added to its query.
+fn redirect(redirect_uri: &str, params: &[(&str, &str)]) -> Redirect {
+    let query = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(params)
+        .finish();
+    let separator = if redirect_uri.contains('?') { '&' } else { '?' };
+
+    // The query carries the authorization code, which must not end up in the logs
+    tracing::info!("Redirecting to: {}", redirect_uri);
+    Redirect::to(&format!("{redirect_uri}{separator}{query}"))
+}
+
+/// Adds the `Set-Cookie` headers carrying `cookies` to `response`.
+fn with_cookies(mut response: Response, cookies: impl IntoIterator<Item = String>) -> Response {
+    for cookie in cookies {
+        if let Ok(cookie) = HeaderValue::from_str(&cookie) {
+            response.headers_mut().append(header::SET_COOKIE, cookie);
+        }
     }
+    response
+}
+
+/// Completes the authorization request of a logged in user.
+///
+/// A code is issued right away if the user already granted the client every requested scope,
+/// unless the client asks for consent again with `prompt=consent`. Otherwise the consent screen
+/// is shown, or the request fails with `consent_required` under `prompt=none`.
+fn authorize_user(
+    state: &mut AppState,
+    params: &AuthorizationRequest,
+    validated: ValidatedRequest,
+    session: &Session,
+    csrf_token: &str,
+) -> Result<Response, AuthorizationError> {
+    let granted = state
+        .storage
+        .get_consent(&session.user_id, &params.client_id)
+        .map_err(|err| server_error(&state.config, Some(&validated.redirect_uri), params, err))?
+        .unwrap_or_default();
 
-    // 5. Generate and store the authorization code
+    if scope::is_subset(&validated.scopes, &granted) && !validated.prompt_consent {
+        tracing::info!(
+            "User {} already consented to client_id: {}",
+            session.user_id,
+            params.client_id
+        );
+        return Ok(issue_code(state, params, validated, session)?.into_response());
+    }
+
+    if validated.prompt_none {
+        tracing::warn!("Consent required for client_id: {}", params.client_id);
+        return Err(AuthorizationError::redirect(
+            &state.config,
+            &validated.redirect_uri,
+            params,
+            "consent_required",
+            "The user has not consented to the requested scopes",
+        ));
+    }
+
+    Ok(consent::consent_page(
+        params,
+        &validated.client_name,
+        &validated.scopes,
+        csrf_token,
+    ))
+}
+
+/// Issues an authorization code to the user of `session` and redirects back to the client.
+fn issue_code(
+    state: &mut AppState,
+    params: &AuthorizationRequest,
+    validated: ValidatedRequest,
+    session: &Session,
+) -> Result<Redirect, AuthorizationError> {
+    // 1. Generate and store the authorization code
     let code = Uuid::new_v4().to_string();
+    let lifetime = state.config.authorization_code_lifetime;
+    state
+        .storage
+        .remove_expired_codes(lifetime)
+        .map_err(|err| server_error(&state.config, Some(&validated.redirect_uri), params, err))?;
     state
-        .authorization_state
-        .insert(code.clone(), params.client_id.clone());
+        .storage
+        .put_code(
+            &code,
+            AuthorizationCode {
+                client_id: params.client_id.clone(),
+                sub: session.user_id.clone(),
+                auth_time: session.auth_time,
+                issued_at: now(),
+                redirect_uri: params.redirect_uri.clone(),
+                requested_scope: params.scope.clone(),
+                scopes: validated.scopes,
+                state: params.state.clone(),
+                code_challenge: validated.code_challenge,
+                redeemed: false,
+                refresh_token_family: None,
+                resource: Some(validated.audience),
+                nonce: params.nonce.clone(),
+            },
+        )
+        .map_err(|err| server_error(&state.config, Some(&validated.redirect_uri), params, err))?;
 
-    tracing::info!("Generated authorization code: {}", code);
+    tracing::info!(
+        "Issued authorization code to client_id {} for user {}",
+        params.client_id,
+        session.user_id
+    );
 
-    // 6. Redirect to the `redirect_uri` with the authorization code and `state`
-    let mut redirect_url = format!("{redirect_uri}?code={code}");
+    // 2. Redirect to the `redirect_uri` with the authorization code and `state`
+    let mut query = vec![("code", code.as_str())];
     if let Some(state_param) = &params.state {
-        redirect_url.push_str(&format!("&state={}", state_param));
+        query.push(("state", state_param));
     }
+    Ok(redirect(&validated.redirect_uri, &query))
+}
+
+/// Handles `GET` requests to the `/authorize` endpoint.
+///
+/// This function validates the client request. If the user is logged in and has consented to
+/// the requested scopes, it generates an authorization code and redirects the client to the
+/// specified redirect URI with the code and state. Otherwise, it shows the login form or the
+/// consent screen, or redirects with an error under `prompt=none`. Browsers without a CSRF cookie
+/// receive one, whose token the forms repeat.
+///
+/// # Arguments
+/// - `State(app_state)`: Shared application state.
+/// - `headers`: The request headers, carrying the session and CSRF cookies if the user has them.
+/// - `Query(params)`: Query parameters from the client request.
+///
+/// # Returns
+/// - `Response`: A redirect to the redirect URI, the login form or the consent screen.
+/// - `AuthorizationError`: An error shown to the user if the client or redirect URI is invalid,
+///   and redirected to the client otherwise.
+#[axum_macros::debug_handler]
+pub async fn authorize(
+    State(app_state): State<SharedAppState>,
+    headers: HeaderMap,
+    Query(params): Query<AuthorizationRequest>,
+) -> Result<Response, AuthorizationError> {
+    tracing::info!("Received authorization request: {:?}", params);
+
+    let mut state = app_state.lock().unwrap();
+    let validated = validate_request(&state, &params)?;
+
+    let session = session::current_session(&state, &headers)
+        .map_err(|err| server_error(&state.config, Some(&validated.redirect_uri), &params, err))?;
+    let (csrf_token, csrf_cookie) = session::csrf_token(&state, &headers);
+    let response = match session {
+        Some(session) => {
+            tracing::info!("User already logged in: {}", session.user_id);
+            authorize_user(&mut state, &params, validated, &session, &csrf_token)?
+        }
+        None if validated.prompt_none => {
+            tracing::warn!("Login required for client_id: {}", params.client_id);
+            return Err(AuthorizationError::redirect(
+                &state.config,
+                &validated.redirect_uri,
+                &params,
+                "login_required",
+                "The user is not logged in",
+            ));
+        }
+        None => login::login_page(
+            StatusCode::OK,
+            &params,
+            &validated.client_name,
+            &csrf_token,
+            None,
+        ),
+    };
+    Ok(with_cookies(response, csrf_cookie))
+}
 
-    tracing::info!("Redirecting to: {}", redirect_url);
+/// Handles `POST` requests to the `/authorize` endpoint, i.e. the submitted login form and
+/// consent screen.
+///
+/// This function validates the client request, checks the anti-CSRF token of the form against
+/// the CSRF cookie, and authenticates the user, either with the
+/// submitted credentials, in which case it starts a session and sets the session cookie, or with
+/// the session cookie. It then records the user's decision on the consent screen: allowing
+/// remembers the granted scopes and redirects with a code, denying redirects with an
+/// `access_denied` error. Without a decision, it continues like [`authorize`]. Failed logins show
+/// the login form again.
+///
+/// Other sites can make the browser post the forms, but cannot read the CSRF cookie to repeat
+/// its token, so they can neither log the user in to an account of theirs nor consent on the
+/// user's behalf.
+///
+/// # Arguments
+/// - `State(app_state)`: Shared application state.
+/// - `headers`: The request headers, carrying the CSRF cookie, and the session cookie if the
+///   user is logged in.
+/// - `Form(form)`: The authorization request parameters and the user's input.
+///
+/// # Returns
+/// - `Response`: A redirect to the redirect URI, the login form or the consent screen.
+/// - `AuthorizationError`: An error shown to the user if the client, the redirect URI or the
+///   anti-CSRF token is invalid, and redirected to the client otherwise.
+#[axum_macros::debug_handler]
+pub async fn submit_form(
+    State(app_state): State<SharedAppState>,
+    headers: HeaderMap,
+    Form(form): Form<AuthorizationForm>,
+) -> Result<Response, AuthorizationError> {
+    let AuthorizationForm {
+        request: params,
+        username,
+        password,
+        consent,
+        csrf_token,
+    } = form;
+    tracing::info!("Received authorization form for request: {:?}", params);
+
+    // 1. Validate the request and the anti-CSRF token, and look up the session and the user
+    //    logging in
+    let (validated, csrf_token, session, user) = {
+        let state = app_state.lock().unwrap();
+        let validated = validate_request(&state, &params)?;
+        let csrf_token = csrf_token.filter(|token| session::verify_csrf_token(&headers, token));
+        let Some(csrf_token) = csrf_token else {
+            tracing::warn!(
+                "Missing or invalid CSRF token for client_id: {}",
+                params.client_id
+            );
+            return Err(AuthorizationError::local(
+                &state.config,
+                StatusCode::FORBIDDEN,
+                &params,
+                "access_denied",
+                "The form was not submitted from the authorization server",
+            ));
+        };
+        let session = session::current_session(&state, &headers).map_err(|err| {
+            server_error(&state.config, Some(&validated.redirect_uri), &params, err)
+        })?;
+        let user = match &username {
+            Some(username) => state
+                .storage
+                .get_user_by_username(username)
+                .map_err(|err| {
+                    server_error(&state.config, Some(&validated.redirect_uri), &params, err)
+                })?,
+            None => None,
+        };
+        (validated, csrf_token, session, user)
+    };
+
+    // 2. Authenticate the user with the submitted credentials, or else with the session cookie
+    let mut cookie = None;
+    let session = match (username, password) {
+        (Some(username), Some(password)) => {
+            // Password hashing is CPU-bound, so keep it off the async runtime and outside of
+            // the lock
+            let (user_id, user) = user.unzip();
+            let verified = tokio::task::spawn_blocking(move || {
+                user::verify_password(user.as_ref(), &password)
+            })
+            .await
+            .unwrap_or(false);
+            let Some(user_id) = user_id.filter(|_| verified) else {
+                tracing::warn!("Failed login for username: {}", username);
+                return Ok(login::login_page(
+                    StatusCode::UNAUTHORIZED,
+                    &params,
+                    &validated.client_name,
+                    &csrf_token,
+                    Some("Invalid username or password."),
+                ));
+            };
+            tracing::info!("User logged in: {}", user_id);
+
+            let mut state = app_state.lock().unwrap();
+            let (session_id, session) =
+                session::create_session(&mut state, &user_id).map_err(|err| {
+                    server_error(&state.config, Some(&validated.redirect_uri), &params, err)
+                })?;
+            cookie = Some(session::session_cookie(&state, &session_id));
+            session
+        }
+        _ => match session {
+            Some(session) => session,
+            None => {
+                return Ok(login::login_page(
+                    StatusCode::OK,
+                    &params,
+                    &validated.client_name,
+                    &csrf_token,
+                    None,
+                ));
+            }
+        },
+    };
+
+    // 3. Record the user's decision on the consent screen, if any
+    let mut state = app_state.lock().unwrap();
+    let response = match consent.as_deref() {
+        Some("allow") => {
+            let granted = state
+                .storage
+                .get_consent(&session.user_id, &params.client_id)
+                .map_err(|err| {
+                    server_error(&state.config, Some(&validated.redirect_uri), &params, err)
+                })?
+                .unwrap_or_default();
+            state
+                .storage
+                .put_consent(
+                    &session.user_id,
+                    &params.client_id,
+                    scope::union(&granted, &validated.scopes),
+                )
+                .map_err(|err| {
+                    server_error(&state.config, Some(&validated.redirect_uri), &params, err)
+                })?;
+            tracing::info!(
+                "User {} granted client_id {}: {}",
+                session.user_id,
+                params.client_id,
+                scope::join(&validated.scopes)
+            );
+            issue_code(&mut state, &params, validated, &session)?.into_response()
+        }
+        Some(_) => {
+            tracing::warn!(
+                "User {} denied client_id: {}",
+                session.user_id,
+                params.client_id
+            );
+            AuthorizationError::redirect(
+                &state.config,
+                &validated.redirect_uri,
+                &params,
+                "access_denied",
+                "The user denied the request",
+            )
+            .into_response()
+        }
+        None => authorize_user(&mut state, &params, validated, &session, &csrf_token)?,
+    };
 
-    // Ensure the redirect is returned correctly
-    Ok(Redirect::to(&redirect_url))
+    Ok(with_cookies(response, cookie))
 }
@@ -0,0 +1,202 @@
+/// The `config` module holds the server-wide policy settings of the Authorization Server.
+/// Settings are read from environment variables at startup and fall back to safe defaults.
+use std::{env, str::FromStr};
+
+/// Represents the policy settings of the Authorization Server.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// The issuer identifier of the Authorization Server, the base URL of its endpoints.
+    pub issuer: String,
+    /// Whether public clients (registered without a `client_secret`) must use PKCE.
+    pub require_pkce_for_public_clients: bool,
+    /// The path to the PEM-encoded RSA private key used to sign access tokens.
+    pub signing_key_path: String,
+    /// The space-delimited paths to previously used signing keys that are still published.
+    pub retired_signing_key_paths: String,
+    /// How long clients may cache the JWK set, in seconds.
+    pub jwks_max_age: u64,
+    /// How often signing keys rotated by other replicas are loaded from the storage, in seconds.
+    pub key_sync_interval: u64,
+    /// How often expired access tokens are removed from the storage, in seconds.
+    pub access_token_purge_interval: u64,
+    /// The bearer token required by the admin endpoints, or `None` to disable them.
+    pub admin_token: Option<String>,
+    /// The lifetime of issued authorization codes in seconds.
+    pub authorization_code_lifetime: u64,
+    /// The lifetime of issued refresh tokens in seconds.
+    pub refresh_token_lifetime: u64,
+    /// The path to the SQLite database, or `None` to keep all data in memory.
+    pub sqlite_path: Option<String>,
+    /// The space-delimited list of scopes clients may register for.
+    pub supported_scopes: String,
+    /// The space-delimited scope granted to clients that register without a `scope`.
+    pub default_scope: String,
+    /// Whether access tokens follow the JWT profile of RFC 9068 (`typ: at+jwt`).
+    pub jwt_access_token_profile: bool,
+    /// The space-delimited resource indicators (RFC 8707) clients may request tokens for.
+    pub resources: String,
+    /// The resource, and thus audience, of tokens requested without a `resource`.
+    pub default_resource: String,
+    /// How long users stay logged in, in seconds.
+    pub session_lifetime: u64,
+    /// The URL of a page documenting errors, sent as `error_uri` with the error code as fragment.
+    pub error_uri: Option<String>,
+    /// Who may register clients at `/register`.
+    pub registration_policy: RegistrationPolicy,
+    /// The lifetime of initial access tokens issued by admins, in seconds.
+    pub initial_access_token_lifetime: u64,
+    /// The space-delimited client IDs of the resource servers that may introspect any token and
+    /// poll `/revoked`. Other confidential clients may only introspect the tokens issued to them.
+    pub introspection_clients: String,
+}
+
+/// Decides who may register clients at the `/register` endpoint.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RegistrationPolicy {
+    /// Anyone who can reach the server may register clients.
+    #[default]
+    Open,
+    /// Registration requires an initial access token (RFC 7591, Section 3) issued by an admin.
+    InitialAccessToken,
+    /// Only requests carrying the admin token may register clients.
+    Admin,
+}
+
+impl FromStr for RegistrationPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "open" => Ok(Self::Open),
+            "initial_access_token" => Ok(Self::InitialAccessToken),
+            "admin" => Ok(Self::Admin),
+            _ => Err(format!("unknown registration policy: {value}")),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            issuer: "http://localhost:3033".to_string(),
+            require_pkce_for_public_clients: true,
+            signing_key_path: "unsafe-private.pem".to_string(),
+            retired_signing_key_paths: String::new(),
+            jwks_max_age: 300,
+            key_sync_interval: 30,
+            access_token_purge_interval: 300,
+            admin_token: None,
+            authorization_code_lifetime: 600,
+            refresh_token_lifetime: 30 * 24 * 3600,
+            sqlite_path: None,
+            supported_scopes: "openid read write".to_string(),
+            default_scope: "read".to_string(),
+            jwt_access_token_profile: false,
+            resources: "http://localhost:3034".to_string(),
+            default_resource: "http://localhost:3034".to_string(),
+            session_lifetime: 8 * 3600,
+            error_uri: None,
+            registration_policy: RegistrationPolicy::Open,
+            initial_access_token_lifetime: 24 * 3600,
+            introspection_clients: String::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Builds the configuration from environment variables.
+    ///
+    /// # Environment
+    /// - `ISSUER`: issuer identifier and base URL (default: `http://localhost:3033`).
+    /// - `REQUIRE_PKCE_FOR_PUBLIC_CLIENTS`: `true` or `false` (default: `true`).
+    /// - `SIGNING_KEY_PATH`: path to the signing key (default: `unsafe-private.pem`).
+    /// - `RETIRED_SIGNING_KEY_PATHS`: paths to retired keys still published (default: none).
+    /// - `JWKS_MAX_AGE`: how long clients may cache `/jwks.json` in seconds (default: 300).
+    /// - `KEY_SYNC_INTERVAL`: how often rotated keys are loaded from the storage, in seconds
+    ///   (default: 30).
+    /// - `ACCESS_TOKEN_PURGE_INTERVAL`: how often expired access tokens are removed from the
+    ///   storage, in seconds (default: 300).
+    /// - `ADMIN_TOKEN`: bearer token for the admin endpoints (default: unset, disabled).
+    /// - `AUTHORIZATION_CODE_LIFETIME`: authorization code lifetime in seconds (default: 600).
+    /// - `REFRESH_TOKEN_LIFETIME`: refresh token lifetime in seconds (default: 30 days).
+    /// - `SQLITE_PATH`: path to the SQLite database (default: unset, in-memory storage).
+    /// - `SUPPORTED_SCOPES`: scopes clients may register for (default: `openid read write`).
+    /// - `DEFAULT_SCOPE`: scope granted to clients registered without one (default: `read`).
+    /// - `JWT_ACCESS_TOKEN_PROFILE`: `true` to issue RFC 9068 access tokens (default: `false`).
+    /// - `RESOURCES`: resources clients may request tokens for (default: `http://localhost:3034`).
+    /// - `DEFAULT_RESOURCE`: audience of tokens requested without a resource
+    ///   (default: `http://localhost:3034`).
+    /// - `SESSION_LIFETIME`: how long users stay logged in, in seconds (default: 8 hours).
+    /// - `ERROR_URI`: page documenting errors, sent as `error_uri` (default: unset).
+    /// - `REGISTRATION_POLICY`: `open`, `initial_access_token` or `admin` (default: `open`).
+    /// - `INITIAL_ACCESS_TOKEN_LIFETIME`: initial access token lifetime in seconds
+    ///   (default: 24 hours).
+    /// - `INTROSPECTION_CLIENTS`: client IDs that may introspect any token (default: none).
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+
+        Self {
+            issuer: env_or("ISSUER", defaults.issuer),
+            require_pkce_for_public_clients: env_or(
+                "REQUIRE_PKCE_FOR_PUBLIC_CLIENTS",
+                defaults.require_pkce_for_public_clients,
+            ),
+            signing_key_path: env_or("SIGNING_KEY_PATH", defaults.signing_key_path),
+            retired_signing_key_paths: env_or(
+                "RETIRED_SIGNING_KEY_PATHS",
+                defaults.retired_signing_key_paths,
+            ),
+            jwks_max_age: env_or("JWKS_MAX_AGE", defaults.jwks_max_age),
+            key_sync_interval: env_or("KEY_SYNC_INTERVAL", defaults.key_sync_interval),
+            access_token_purge_interval: env_or(
+                "ACCESS_TOKEN_PURGE_INTERVAL",
+                defaults.access_token_purge_interval,
+            ),
+            admin_token: env::var("ADMIN_TOKEN").ok(),
+            authorization_code_lifetime: env_or(
+                "AUTHORIZATION_CODE_LIFETIME",
+                defaults.authorization_code_lifetime,
+            ),
+            refresh_token_lifetime: env_or(
+                "REFRESH_TOKEN_LIFETIME",
+                defaults.refresh_token_lifetime,
+            ),
+            sqlite_path: env::var("SQLITE_PATH").ok(),
+            supported_scopes: env_or("SUPPORTED_SCOPES", defaults.supported_scopes),
+            default_scope: env_or("DEFAULT_SCOPE", defaults.default_scope),
+            jwt_access_token_profile: env_or(
+                "JWT_ACCESS_TOKEN_PROFILE",
+                defaults.jwt_access_token_profile,
+            ),
+            resources: env_or("RESOURCES", defaults.resources),
+            default_resource: env_or("DEFAULT_RESOURCE", defaults.default_resource),
+            session_lifetime: env_or("SESSION_LIFETIME", defaults.session_lifetime),
+            error_uri: env::var("ERROR_URI").ok(),
+            registration_policy: env_or("REGISTRATION_POLICY", defaults.registration_policy),
+            initial_access_token_lifetime: env_or(
+                "INITIAL_ACCESS_TOKEN_LIFETIME",
+                defaults.initial_access_token_lifetime,
+            ),
+            introspection_clients: env_or("INTROSPECTION_CLIENTS", defaults.introspection_clients),
+        }
+    }
+
+    /// Returns whether `client_id` is one of the trusted resource servers listed in
+    /// `introspection_clients`.
+    pub fn is_introspection_client(&self, client_id: &str) -> bool {
+        self.introspection_clients
+            .split_whitespace()
+            .any(|trusted| trusted == client_id)
+    }
+}
+
+/// Reads and parses the environment variable `name`, returning `default` if it is unset or invalid.
+fn env_or<T: FromStr>(name: &str, default: T) -> T {
+    match env::var(name) {
+        Ok(value) => value.parse().unwrap_or_else(|_| {
+            tracing::warn!("Ignoring invalid value for {}: {}", name, value);
+            default
+        }),
+        Err(_) => default,
+    }
+}
@@ -0,0 +1,57 @@
+/// The `consent` module renders the consent screen shown by the `/authorize` endpoint, on which
+/// a logged in user allows or denies a client the scopes it requests. Granted scopes are
+/// remembered per user and client, so that later requests for them skip the screen.
+use crate::{
+    authorize::AuthorizationRequest,
+    login::{escape, hidden_fields, html_page},
+};
+use axum::{http::StatusCode, response::Response};
+
+/// Returns a human readable description of a scope, if it is a well-known one.
+fn describe(scope: &str) -> Option<&'static str> {
+    match scope {
+        "openid" => Some("Confirm your identity"),
+        "read" => Some("Read your data"),
+        "write" => Some("Modify your data"),
+        _ => None,
+    }
+}
+
+/// Renders the consent screen for an authorization request.
+///
+/// # Arguments
+/// - `request`: The authorization request, repeated in hidden fields of the form.
+/// - `client_name`: The name of the client asking for authorization.
+/// - `scopes`: The scopes the client asks for.
+/// - `csrf_token`: The anti-CSRF token the form must be submitted with.
+pub fn consent_page(
+    request: &AuthorizationRequest,
+    client_name: &str,
+    scopes: &[String],
+    csrf_token: &str,
+) -> Response {
+    let scopes = scopes
+        .iter()
+        .map(|scope| match describe(scope) {
+            Some(description) => format!("<li>{description} (<code>{}</code>)</li>", escape(scope)),
+            None => format!("<li><code>{}</code></li>", escape(scope)),
+        })
+        .collect::<Vec<_>>()
+        .join("\n      ");
+
+    let body = format!(
+        r#"<h1>{client_name} wants to access your account</h1>
+    <p>{client_name} asks for permission to:</p>
+    <ul>
+      {scopes}
+    </ul>
+    <form method="post" action="/authorize">
+      {hidden_fields}
+      <button type="submit" name="consent" value="allow">Allow</button>
+      <button type="submit" name="consent" value="deny">Deny</button>
+    </form>"#,
+        client_name = escape(client_name),
+        hidden_fields = hidden_fields(request, csrf_token),
+    );
+    html_page(StatusCode::OK, "Authorize access", &body)
+}
@@ -0,0 +1,71 @@
+/// The `id_token` module issues OpenID Connect ID tokens (OIDC Core, Section 2).
+/// An ID token is returned alongside the access token whenever the `openid` scope was granted,
+/// and tells the client who the authenticated user is.
+use crate::{
+    AppState, now,
+    token::{TokenError, sign_jwt},
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// The scope that turns an authorization request into an OpenID Connect authentication request.
+pub const OPENID_SCOPE: &str = "openid";
+
+/// The lifetime of issued ID tokens in seconds.
+pub(crate) const ID_TOKEN_LIFETIME: u64 = 3600;
+
+/// Represents the claims included in an ID token.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct IdTokenClaims {
+    /// The issuer identifier of the Authorization Server.
+    pub iss: String,
+    /// The subject, i.e. the authenticated user.
+    pub sub: String,
+    /// The audience, i.e. the client the ID token was issued to.
+    pub aud: String,
+    /// The expiration time of the token (UNIX timestamp).
+    pub exp: u64,
+    /// The time the token was issued (UNIX timestamp).
+    pub iat: u64,
+    /// The time the user authenticated (UNIX timestamp).
+    pub auth_time: u64,
+    /// The `nonce` sent to `/authorize`, which the client checks to detect replays.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    /// The hash of the access token issued alongside the ID token.
+    pub at_hash: String,
+}
+
+/// Computes the `at_hash` of an access token: the base64url encoding of the left-most half of
+/// its SHA-256 hash, matching the RS256 signature algorithm (OIDC Core, Section 3.1.3.6).
+pub(crate) fn at_hash(access_token: &str) -> String {
+    let hash = Sha256::digest(access_token.as_bytes());
+    URL_SAFE_NO_PAD.encode(&hash[..hash.len() / 2])
+}
+
+/// Generates a signed ID token for `sub`, issued to `client_id` alongside `access_token`.
+pub(crate) fn issue_id_token(
+    state: &AppState,
+    client_id: &str,
+    sub: &str,
+    auth_time: u64,
+    nonce: Option<&str>,
+    access_token: &str,
+) -> Result<String, TokenError> {
+    let iat = now();
+    let claims = IdTokenClaims {
+        iss: state.config.issuer.trim_end_matches('/').to_string(),
+        sub: sub.to_string(),
+        aud: client_id.to_string(),
+        exp: iat + ID_TOKEN_LIFETIME,
+        iat,
+        auth_time,
+        nonce: nonce.map(String::from),
+        at_hash: at_hash(access_token),
+    };
+
+    let id_token = sign_jwt(state, "JWT", &claims)?;
+    tracing::info!("Generated ID token for subject: {}", sub);
+    Ok(id_token)
+}
@@ -0,0 +1,180 @@
+/// The `introspect` module handles the `/introspect` endpoint of the Authorization Server.
+/// Resource servers authenticate as clients and learn whether a token is active and what it
+/// grants (RFC 7662).
+use crate::{
+    AppState, SharedAppState, now,
+    secret::{REDACTED, redact},
+    token::{TokenError, authenticate_client, decode_access_token, error_response, server_error},
+};
+use axum::{
+    extract::{Form, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Represents the request body for the `/introspect` endpoint.
+#[derive(Deserialize)]
+pub struct IntrospectionRequest {
+    /// The token to introspect.
+    pub token: String,
+    /// A hint about the type of the token ("access_token" or "refresh_token").
+    pub token_type_hint: Option<String>,
+    /// The client ID of the calling resource server.
+    pub client_id: String,
+    /// The client secret of the calling resource server.
+    pub client_secret: Option<String>,
+}
+
+/// Redacts the token and the client credentials of the request, so that it can be logged.
+impl fmt::Debug for IntrospectionRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IntrospectionRequest")
+            .field("token", &REDACTED)
+            .field("token_type_hint", &self.token_type_hint)
+            .field("client_id", &self.client_id)
+            .field("client_secret", &redact(&self.client_secret))
+            .finish()
+    }
+}
+
+/// Represents an introspection response (RFC 7662, Section 2.2).
+///
+/// Inactive tokens are reported with `active` alone, without revealing anything else.
+#[derive(Serialize, Default, Debug)]
+pub struct IntrospectionResponse {
+    /// Whether the token is currently active.
+    pub active: bool,
+    /// The space-delimited scope granted to the token.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    /// The client ID the token was issued to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    /// The subject of the token.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+    /// The type of the token ("Bearer" for access tokens, "refresh_token" for refresh tokens).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<String>,
+    /// The expiration time of the token (UNIX timestamp).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<u64>,
+    /// The time the token was issued (UNIX timestamp).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iat: Option<u64>,
+    /// The audience the token is intended for, if it is restricted to one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
+}
+
+/// Handles the `/introspect` endpoint.
+///
+/// Only confidential clients may introspect tokens. Tokens that are unknown, expired, revoked
+/// or already rotated are reported as inactive, and so are tokens issued to another client,
+/// unless the caller is one of the configured `introspection_clients`.
+///
+/// # Returns
+/// - `Json<IntrospectionResponse>`: The state of the token.
+/// - `(StatusCode, Json<TokenErrorResponse>)`: An error response if the caller is not authorized.
+pub async fn introspect(
+    State(app_state): State<SharedAppState>,
+    Form(payload): Form<IntrospectionRequest>,
+) -> Result<Json<IntrospectionResponse>, TokenError> {
+    tracing::info!(
+        "Received introspection request from client_id: {}",
+        payload.client_id
+    );
+
+    let state = app_state.lock().unwrap();
+
+    let client = authenticate_client(&state, &payload.client_id, payload.client_secret.as_deref())?;
+    if client.is_public() {
+        tracing::warn!(
+            "Public client_id may not introspect tokens: {}",
+            payload.client_id
+        );
+        return Err(error_response(StatusCode::UNAUTHORIZED, "invalid_client"));
+    }
+
+    // Look the token up as the hinted type first, then as the other type (RFC 7662, Section 2.1)
+    let response = if payload.token_type_hint.as_deref() == Some("refresh_token") {
+        match introspect_refresh_token(&state, &payload.token)? {
+            Some(response) => Some(response),
+            None => introspect_access_token(&state, &payload.token)?,
+        }
+    } else {
+        match introspect_access_token(&state, &payload.token)? {
+            Some(response) => Some(response),
+            None => introspect_refresh_token(&state, &payload.token)?,
+        }
+    };
+
+    // Do not reveal the tokens of other clients to callers that are not trusted resource servers
+    let trusted = state.config.is_introspection_client(&payload.client_id);
+    let response = response.filter(|response| {
+        trusted || response.client_id.as_deref() == Some(payload.client_id.as_str())
+    });
+
+    Ok(Json(response.unwrap_or_default()))
+}
+
+/// Introspects a JWT access token, returning `None` unless it is valid, unexpired and not revoked.
+fn introspect_access_token(
+    state: &AppState,
+    token: &str,
+) -> Result<Option<IntrospectionResponse>, TokenError> {
+    let Some(claims) = decode_access_token(state, token, false) else {
+        return Ok(None);
+    };
+
+    let revoked = state
+        .storage
+        .get_access_token(&claims.jti)
+        .map_err(server_error)?
+        .is_some_and(|access_token| access_token.revoked);
+    if revoked {
+        return Ok(None);
+    }
+
+    Ok(Some(IntrospectionResponse {
+        active: true,
+        scope: Some(claims.scope),
+        client_id: Some(claims.client_id),
+        sub: Some(claims.sub),
+        token_type: Some("Bearer".to_string()),
+        exp: Some(claims.exp),
+        iat: Some(claims.iat),
+        aud: Some(claims.aud),
+    }))
+}
+
+/// Introspects a refresh token, returning `None` unless it is known and can still be used.
+fn introspect_refresh_token(
+    state: &AppState,
+    token: &str,
+) -> Result<Option<IntrospectionResponse>, TokenError> {
+    let Some(refresh_token) = state
+        .storage
+        .get_refresh_token(token)
+        .map_err(server_error)?
+    else {
+        return Ok(None);
+    };
+
+    if refresh_token.revoked || refresh_token.rotated || refresh_token.expires_at < now() {
+        return Ok(None);
+    }
+
+    Ok(Some(IntrospectionResponse {
+        active: true,
+        scope: Some(refresh_token.scope),
+        client_id: Some(refresh_token.client_id),
+        sub: Some(refresh_token.sub),
+        token_type: Some("refresh_token".to_string()),
+        exp: Some(refresh_token.expires_at),
+        iat: Some(refresh_token.issued_at),
+        aud: refresh_token.resource,
+    }))
+}
@@ -1,50 +1,80 @@
-use axum::response::Json;
+/// The `jwks` module handles the `/jwks.json` endpoint of the Authorization Server.
+/// It publishes the keys access tokens are signed with as a JWK set (RFC 7517).
+use crate::{SharedAppState, keys::SigningKey};
+use axum::{
+    extract::State,
+    http::header,
+    response::{IntoResponse, Json},
+};
 use base64::Engine;
-use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
 use rsa::PublicKeyParts;
-use rsa::pkcs8::DecodePublicKey; // Import the DecodePublicKey trait for PKCS#8
 use serde::Serialize;
-use std::fs;
-use tracing;
+use sha2::{Digest, Sha256};
 
+/// Represents a public RSA signing key as a JSON Web Key (RFC 7517, RFC 7518 Section 6.3).
 #[derive(Serialize)]
-struct Jwk {
-    kty: String,
-    kid: String,
-    use_: String,
-    alg: String,
-    n: String,
-    e: String,
+pub struct Jwk {
+    /// The key type ("RSA").
+    pub kty: String,
+    /// The key ID, the key's JWK thumbprint (RFC 7638).
+    pub kid: String,
+    /// The intended use of the key ("sig").
+    #[serde(rename = "use")]
+    pub key_use: String,
+    /// The algorithm the key is used with ("RS256").
+    pub alg: String,
+    /// The modulus of the key (Base64urlUInt-encoded).
+    pub n: String,
+    /// The exponent of the key (Base64urlUInt-encoded).
+    pub e: String,
+    /// The X.509 certificate chain of the key (Base64-encoded DER), leaf certificate first.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x5c: Option<Vec<String>>,
+    /// The SHA-256 thumbprint of the leaf certificate (Base64url-encoded).
+    #[serde(rename = "x5t#S256", skip_serializing_if = "Option::is_none")]
+    pub x5t_s256: Option<String>,
 }
 
+impl From<&SigningKey> for Jwk {
+    fn from(key: &SigningKey) -> Self {
+        let certificates = (!key.certificate_chain.is_empty()).then_some(&key.certificate_chain);
+
+        Self {
+            kty: "RSA".to_string(),
+            kid: key.kid.clone(),
+            key_use: "sig".to_string(),
+            alg: "RS256".to_string(),
+            n: URL_SAFE_NO_PAD.encode(key.public_key.n().to_bytes_be()),
+            e: URL_SAFE_NO_PAD.encode(key.public_key.e().to_bytes_be()),
+            x5c: certificates.map(|chain| chain.iter().map(|der| STANDARD.encode(der)).collect()),
+            x5t_s256: certificates
+                .and_then(|chain| chain.first())
+                .map(|leaf| URL_SAFE_NO_PAD.encode(Sha256::digest(leaf))),
+        }
+    }
+}
+
+/// Represents a JWK set.
 #[derive(Serialize)]
 pub struct Jwks {
-    keys: Vec<Jwk>,
+    /// The published keys.
+    pub keys: Vec<Jwk>,
 }
 
-pub async fn jwks() -> Json<Jwks> {
-    // Read the public key from the `public.pem` file
-    let public_key_pem = fs::read_to_string("public.pem").expect("Failed to read public.pem");
-
-    // Parse the public key to extract the modulus (n) and exponent (e)
-    let public_key = rsa::RsaPublicKey::from_public_key_pem(&public_key_pem)
-        .expect("Failed to parse public key");
-
-    let n = URL_SAFE_NO_PAD.encode(public_key.n().to_bytes_be());
-    let e = URL_SAFE_NO_PAD.encode(public_key.e().to_bytes_be());
-
-    tracing::info!("Constructing JWKS with modulus (n): {}", n);
-    tracing::info!("Constructing JWKS with exponent (e): {}", e);
+/// Handles the `/jwks.json` endpoint.
+///
+/// Publishes the active signing key followed by every retired key whose tokens may still be
+/// valid. The response may be cached for `JWKS_MAX_AGE` seconds.
+pub async fn jwks(State(app_state): State<SharedAppState>) -> impl IntoResponse {
+    let state = app_state.lock().unwrap();
 
-    // Construct the JWK
-    let jwk = Jwk {
-        kty: "RSA".to_string(),
-        kid: "key-id-1".to_string(),
-        use_: "sig".to_string(),
-        alg: "RS256".to_string(),
-        n,
-        e,
-    };
+    let keys: Vec<Jwk> = state.keys.published().map(Jwk::from).collect();
+    tracing::info!("Publishing JWK set with {} key(s)", keys.len());
 
-    Json(Jwks { keys: vec![jwk] })
+    let cache_control = format!("public, max-age={}", state.config.jwks_max_age);
+    (
+        [(header::CACHE_CONTROL, cache_control)],
+        Json(Jwks { keys }),
+    )
 }
@@ -0,0 +1,333 @@
+/// The `keys` module manages the RSA keys the Authorization Server signs access tokens with.
+/// Configured keys are loaded at startup. Rotation generates a new key and writes it to the
+/// storage, from where it is loaded at startup and by other replicas; the retired key stays
+/// published until the access tokens it signed have expired.
+use crate::{AppState, SharedAppState, config::Config, now, token::ACCESS_TOKEN_LIFETIME};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use jsonwebtoken::{DecodingKey, EncodingKey};
+use rsa::{
+    PublicKeyParts, RsaPrivateKey, RsaPublicKey,
+    pkcs1::{DecodeRsaPrivateKey, EncodeRsaPrivateKey},
+    pkcs8::{DecodePrivateKey, EncodePrivateKey, LineEnding, der::pem},
+    rand_core::OsRng,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{fmt, fs, time::Duration};
+
+/// The size of keys generated on rotation, in bits.
+const GENERATED_KEY_BITS: usize = 2048;
+
+/// Represents an error raised while loading or generating a signing key.
+#[derive(Debug)]
+pub struct KeyError(pub String);
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "key error: {}", self.0)
+    }
+}
+
+impl std::error::Error for KeyError {}
+
+/// Represents an RSA key used to sign access tokens.
+#[derive(Clone)]
+pub struct SigningKey {
+    /// The key ID placed in the `kid` header of tokens signed with this key.
+    ///
+    /// This is the key's JWK thumbprint (RFC 7638), so it is stable across restarts.
+    pub kid: String,
+    /// The public half of the key, published at `/jwks.json`.
+    pub public_key: RsaPublicKey,
+    /// The DER-encoded X.509 certificate chain of the key, leaf certificate first, if any.
+    pub certificate_chain: Vec<Vec<u8>>,
+    /// The time the key stopped being used for signing (UNIX timestamp), if it was retired.
+    pub retired_at: Option<u64>,
+    private_key: RsaPrivateKey,
+    encoding_key: EncodingKey,
+}
+
+/// Represents a signing key created by a rotation, as kept in the storage.
+///
+/// The private key is stored unencrypted, so the storage must be protected like the key files
+/// named by `SIGNING_KEY_PATH`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct StoredSigningKey {
+    /// The unencrypted PKCS#8 PEM encoding of the private key.
+    pub private_key_pem: String,
+    /// The time the key was created (UNIX timestamp).
+    pub created_at: u64,
+    /// The time the key stopped being used for signing (UNIX timestamp), if it was retired.
+    pub retired_at: Option<u64>,
+}
+
+impl SigningKey {
+    /// Builds a signing key from an RSA private key and its certificate chain.
+    fn new(private_key: RsaPrivateKey, certificate_chain: Vec<Vec<u8>>) -> Result<Self, KeyError> {
+        let der = private_key
+            .to_pkcs1_der()
+            .map_err(|err| KeyError(err.to_string()))?;
+        let public_key = private_key.to_public_key();
+
+        Ok(Self {
+            kid: thumbprint(&public_key),
+            public_key,
+            certificate_chain,
+            retired_at: None,
+            private_key,
+            encoding_key: EncodingKey::from_rsa_der(der.as_bytes()),
+        })
+    }
+
+    /// Builds a signing key from PEM-encoded data.
+    ///
+    /// The data must contain an RSA private key (`PRIVATE KEY` or `RSA PRIVATE KEY`) and may
+    /// contain the key's certificate chain (`CERTIFICATE`), leaf certificate first.
+    pub fn from_pem(data: &str) -> Result<Self, KeyError> {
+        let mut private_key = None;
+        let mut certificate_chain = Vec::new();
+
+        for block in pem_blocks(data) {
+            let (label, der) =
+                pem::decode_vec(block.as_bytes()).map_err(|err| KeyError(err.to_string()))?;
+            match label {
+                "PRIVATE KEY" => {
+                    private_key = Some(
+                        RsaPrivateKey::from_pkcs8_der(&der)
+                            .map_err(|err| KeyError(err.to_string()))?,
+                    )
+                }
+                "RSA PRIVATE KEY" => {
+                    private_key = Some(
+                        RsaPrivateKey::from_pkcs1_der(&der)
+                            .map_err(|err| KeyError(err.to_string()))?,
+                    )
+                }
+                "CERTIFICATE" => certificate_chain.push(der),
+                _ => tracing::warn!("Ignoring PEM block with label: {}", label),
+            }
+        }
+
+        let private_key =
+            private_key.ok_or_else(|| KeyError("no private key found".to_string()))?;
+        Self::new(private_key, certificate_chain)
+    }
+
+    /// Reads a signing key from the PEM file at `path`.
+    pub fn load(path: &str) -> Result<Self, KeyError> {
+        let data = fs::read_to_string(path).map_err(|err| KeyError(format!("{path}: {err}")))?;
+        Self::from_pem(&data).map_err(|err| KeyError(format!("{path}: {}", err.0)))
+    }
+
+    /// Generates a new random signing key.
+    pub fn generate() -> Result<Self, KeyError> {
+        let private_key = RsaPrivateKey::new(&mut OsRng, GENERATED_KEY_BITS)
+            .map_err(|err| KeyError(err.to_string()))?;
+        Self::new(private_key, Vec::new())
+    }
+
+    /// Encodes the private key as PKCS#8 PEM, so that it can be stored.
+    pub fn private_key_pem(&self) -> Result<String, KeyError> {
+        self.private_key
+            .to_pkcs8_pem(LineEnding::LF)
+            .map(|pem| pem.to_string())
+            .map_err(|err| KeyError(err.to_string()))
+    }
+
+    /// Returns the key used to sign tokens.
+    pub fn encoding_key(&self) -> &EncodingKey {
+        &self.encoding_key
+    }
+
+    /// Returns the key used to verify tokens signed with this key.
+    pub fn decoding_key(&self) -> DecodingKey {
+        DecodingKey::from_rsa_raw_components(
+            &self.public_key.n().to_bytes_be(),
+            &self.public_key.e().to_bytes_be(),
+        )
+    }
+}
+
+/// Computes the JWK thumbprint of an RSA public key (RFC 7638).
+///
+/// The thumbprint is the SHA-256 hash of the required members of the JWK, serialized in
+/// lexicographic order without whitespace.
+pub fn thumbprint(public_key: &RsaPublicKey) -> String {
+    let jwk = format!(
+        r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#,
+        URL_SAFE_NO_PAD.encode(public_key.e().to_bytes_be()),
+        URL_SAFE_NO_PAD.encode(public_key.n().to_bytes_be()),
+    );
+    URL_SAFE_NO_PAD.encode(Sha256::digest(jwk.as_bytes()))
+}
+
+/// Splits PEM-encoded data into its individual `-----BEGIN ...-----` blocks.
+fn pem_blocks(data: &str) -> Vec<&str> {
+    let mut blocks = Vec::new();
+    let mut rest = data;
+    while let Some(start) = rest.find("-----BEGIN ") {
+        let block = &rest[start..];
+        // The block ends after the dashes closing its `-----END ...-----` line
+        let Some(end) = block.find("-----END ").and_then(|end| {
+            block[end + 9..]
+                .find("-----")
+                .map(|dashes| end + 9 + dashes + 5)
+        }) else {
+            break;
+        };
+        blocks.push(&block[..end]);
+        rest = &block[end..];
+    }
+    blocks
+}
+
+/// Holds the active signing key and the retired keys that are still published.
+#[derive(Clone, Default)]
+pub struct KeyManager {
+    active: Option<SigningKey>,
+    retired: Vec<SigningKey>,
+    /// How long retired keys stay published, in seconds.
+    retention: u64,
+}
+
+impl KeyManager {
+    /// Creates a key manager signing with `active` and publishing `retired` until they are pruned.
+    ///
+    /// Retired keys are kept for `retention` seconds after their retirement.
+    pub fn new(active: SigningKey, retired: Vec<SigningKey>, retention: u64) -> Self {
+        Self {
+            active: Some(active),
+            retired,
+            retention,
+        }
+    }
+
+    /// Loads the keys named in the configuration.
+    ///
+    /// Keys listed in `retired_signing_key_paths` are published but never used for signing.
+    /// Keys retired later on are published for as long as the access tokens they signed are valid.
+    pub fn from_config(config: &Config) -> Result<Self, KeyError> {
+        let active = SigningKey::load(&config.signing_key_path)?;
+        let retired = config
+            .retired_signing_key_paths
+            .split_whitespace()
+            .map(SigningKey::load)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        tracing::info!(
+            "Loaded signing key {} and {} retired key(s)",
+            active.kid,
+            retired.len()
+        );
+        Ok(Self::new(active, retired, ACCESS_TOKEN_LIFETIME))
+    }
+
+    /// Returns the key new tokens are signed with, if any.
+    pub fn active(&self) -> Option<&SigningKey> {
+        self.active.as_ref()
+    }
+
+    /// Returns every key whose tokens may still be in circulation, the active key first.
+    pub fn published(&self) -> impl Iterator<Item = &SigningKey> {
+        let now = now();
+        self.active
+            .iter()
+            .chain(self.retired.iter().filter(move |key| {
+                key.retired_at
+                    .is_none_or(|retired_at| retired_at + self.retention > now)
+            }))
+    }
+
+    /// Returns the published key with the given key ID.
+    pub fn find(&self, kid: &str) -> Option<&SigningKey> {
+        self.published().find(|key| key.kid == kid)
+    }
+
+    /// Returns whether a key retired at `retired_at` may still have valid tokens in circulation.
+    pub fn is_retained(&self, retired_at: u64) -> bool {
+        retired_at + self.retention > now()
+    }
+
+    /// Makes `key` the active key, retiring the previously active one.
+    pub fn rotate(&mut self, key: SigningKey) {
+        if let Some(mut previous) = self.active.replace(key) {
+            previous.retired_at = Some(now());
+            self.retired.push(previous);
+        }
+        self.prune();
+    }
+
+    /// Applies the rotations recorded in the storage, given as `stored` keys oldest first.
+    ///
+    /// The newest stored key that was never retired becomes the active key, and the keys it
+    /// replaced are published as retired keys. Keys that are already known are not parsed again.
+    pub fn apply_stored(&mut self, stored: &[(String, StoredSigningKey)]) -> Result<(), KeyError> {
+        for (kid, record) in stored {
+            let expired = record.retired_at.is_some_and(|at| !self.is_retained(at));
+            let known = self
+                .active
+                .iter_mut()
+                .chain(self.retired.iter_mut())
+                .find(|key| key.kid == *kid);
+            match known {
+                Some(key) => key.retired_at = key.retired_at.or(record.retired_at),
+                None if expired => {}
+                None => {
+                    let mut key = SigningKey::from_pem(&record.private_key_pem)?;
+                    key.retired_at = record.retired_at;
+                    self.retired.push(key);
+                }
+            }
+        }
+
+        let newest = stored
+            .iter()
+            .rev()
+            .find(|(_, record)| record.retired_at.is_none());
+        if let Some((kid, record)) = newest
+            && let Some(index) = self.retired.iter().position(|key| key.kid == *kid)
+        {
+            let key = self.retired.remove(index);
+            if let Some(mut previous) = self.active.replace(key) {
+                // A configured key is retired by the first rotation recorded in the storage
+                previous.retired_at.get_or_insert(record.created_at);
+                self.retired.push(previous);
+            }
+        }
+        self.prune();
+        Ok(())
+    }
+
+    /// Forgets retired keys whose tokens have all expired.
+    fn prune(&mut self) {
+        let now = now();
+        let retention = self.retention;
+        self.retired.retain(|key| {
+            key.retired_at
+                .is_none_or(|retired_at| retired_at + retention > now)
+        });
+    }
+}
+
+/// Loads the signing keys rotated in by any replica sharing the storage.
+pub fn sync(state: &mut AppState) -> Result<(), KeyError> {
+    let stored = state
+        .storage
+        .signing_keys()
+        .map_err(|err| KeyError(err.to_string()))?;
+    state.keys.apply_stored(&stored)
+}
+
+/// Spawns a task that loads the signing keys rotated by other replicas every `interval`.
+pub fn spawn_key_sync(app_state: SharedAppState, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(err) = sync(&mut app_state.lock().unwrap()) {
+                tracing::error!("Failed to load signing keys from the storage: {}", err);
+            }
+        }
+    });
+}
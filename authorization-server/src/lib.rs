@@ -1,17 +1,74 @@
+pub mod admin;
 pub mod authorize;
+pub mod config;
+pub mod consent;
+pub mod id_token;
+pub mod introspect;
 pub mod jwks;
+pub mod keys;
+pub mod login;
+pub mod metadata;
+pub mod pkce;
+pub mod redirect_uri;
 pub mod register;
+pub mod resource;
+pub mod revoke;
 pub mod router;
+pub mod scope;
+pub mod secret;
+pub mod session;
+pub mod storage;
 pub mod token;
+pub mod user;
+pub mod userinfo;
 
+use config::Config;
+use keys::KeyManager;
 use std::{
-    collections::HashMap,
     sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
 };
+use storage::{MemoryStorage, Storage};
 
 pub struct AppState {
-    pub authorization_state: HashMap<String, String>,
-    pub client_registry: HashMap<String, (String, Vec<String>)>,
+    pub storage: Box<dyn Storage>,
+    pub keys: KeyManager,
+    pub config: Config,
+}
+
+impl AppState {
+    /// Builds the state described by `config`: its storage backend and its signing keys,
+    /// including the keys rotated in earlier and kept in the storage.
+    ///
+    /// # Panics
+    /// Panics if the storage cannot be opened or the signing keys cannot be loaded.
+    pub fn from_config(config: Config) -> Self {
+        let mut state = Self {
+            storage: storage::from_config(&config),
+            keys: KeyManager::from_config(&config).expect("Failed to load signing keys"),
+            config,
+        };
+        keys::sync(&mut state).expect("Failed to load stored signing keys");
+        state
+    }
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self {
+            storage: Box::new(MemoryStorage::default()),
+            keys: KeyManager::default(),
+            config: Config::default(),
+        }
+    }
 }
 
 pub type SharedAppState = Arc<Mutex<AppState>>;
+
+/// Returns the current time as a UNIX timestamp.
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
@@ -0,0 +1,103 @@
+/// The `login` module renders the login form shown by the `/authorize` endpoint to users who
+/// are not logged in. The form posts the credentials back to `/authorize` together with the
+/// parameters of the authorization request.
+use crate::authorize::AuthorizationRequest;
+use axum::{
+    http::{HeaderValue, StatusCode, header},
+    response::{Html, IntoResponse, Response},
+};
+
+/// Escapes the characters that are special in HTML text and attribute values.
+pub(crate) fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#x27;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Renders the parameters of an authorization request and the anti-CSRF token as hidden form
+/// fields.
+pub(crate) fn hidden_fields(request: &AuthorizationRequest, csrf_token: &str) -> String {
+    request
+        .params()
+        .into_iter()
+        .chain([("csrf_token", csrf_token)])
+        .map(|(name, value)| {
+            format!(
+                r#"<input type="hidden" name="{name}" value="{}">"#,
+                escape(value)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n      ")
+}
+
+/// Wraps `body` in an HTML page that is neither cached nor framed by other sites.
+pub(crate) fn html_page(status: StatusCode, title: &str, body: &str) -> Response {
+    let page = format!(
+        r#"<!DOCTYPE html>
+<html>
+  <head>
+    <meta charset="utf-8">
+    <title>{title}</title>
+  </head>
+  <body>
+    {body}
+  </body>
+</html>
+"#
+    );
+
+    let mut response = (status, Html(page)).into_response();
+    let headers = response.headers_mut();
+    // The forms must not be cached, nor framed by other sites to trick users into submitting them
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    headers.insert(header::X_FRAME_OPTIONS, HeaderValue::from_static("DENY"));
+    headers.insert(
+        header::CONTENT_SECURITY_POLICY,
+        HeaderValue::from_static("frame-ancestors 'none'"),
+    );
+    response
+}
+
+/// Renders the login form for an authorization request.
+///
+/// # Arguments
+/// - `status`: The status of the response, `401 Unauthorized` after a failed login.
+/// - `request`: The authorization request, repeated in hidden fields of the form.
+/// - `client_name`: The name of the client asking for authorization.
+/// - `csrf_token`: The anti-CSRF token the form must be submitted with.
+/// - `error`: An error message to display above the form, if any.
+pub fn login_page(
+    status: StatusCode,
+    request: &AuthorizationRequest,
+    client_name: &str,
+    csrf_token: &str,
+    error: Option<&str>,
+) -> Response {
+    let error = error
+        .map(|error| format!(r#"<p class="error">{}</p>"#, escape(error)))
+        .unwrap_or_default();
+
+    let body = format!(
+        r#"<h1>Log in to continue to {client_name}</h1>
+    {error}
+    <form method="post" action="/authorize">
+      {hidden_fields}
+      <label>Username <input type="text" name="username" autocomplete="username" required></label>
+      <label>Password <input type="password" name="password" autocomplete="current-password" required></label>
+      <button type="submit">Log in</button>
+    </form>"#,
+        client_name = escape(client_name),
+        hidden_fields = hidden_fields(request, csrf_token),
+    );
+    html_page(status, "Log in", &body)
+}
@@ -1,12 +1,5 @@
+use authorization_server::router::router;
 use tokio::net::TcpListener;
-use tracing_subscriber;
-
-mod authorize;
-mod jwks;
-mod register;
-mod token;
-
-use authorization_server::{SharedAppState, router::router};
 
 #[tokio::main]
 async fn main() {
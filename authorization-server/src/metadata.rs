@@ -0,0 +1,124 @@
+/// The `metadata` module handles the `/.well-known/oauth-authorization-server` endpoint.
+/// It publishes the Authorization Server Metadata (RFC 8414) so that clients and resource
+/// servers can discover the endpoints and capabilities of this server. The same document, which
+/// includes the OpenID Provider Metadata, is served at `/.well-known/openid-configuration`.
+use crate::{SharedAppState, config::Config, pkce::CodeChallengeMethod, scope};
+use axum::{extract::State, response::Json};
+use serde::Serialize;
+
+/// The grant types accepted by the `/token` endpoint.
+pub const GRANT_TYPES_SUPPORTED: &[&str] =
+    &["authorization_code", "refresh_token", "client_credentials"];
+
+/// The response types accepted by the `/authorize` endpoint.
+pub const RESPONSE_TYPES_SUPPORTED: &[&str] = &["code"];
+
+/// The client authentication methods accepted by the `/token` endpoint.
+pub const TOKEN_ENDPOINT_AUTH_METHODS_SUPPORTED: &[&str] = &["client_secret_post", "none"];
+
+/// The claims that can be returned in ID tokens and by the `/userinfo` endpoint.
+pub const CLAIMS_SUPPORTED: &[&str] = &[
+    "iss",
+    "sub",
+    "aud",
+    "exp",
+    "iat",
+    "auth_time",
+    "nonce",
+    "at_hash",
+];
+
+/// Represents the Authorization Server Metadata document (RFC 8414, Section 2), extended with
+/// the OpenID Provider Metadata (OpenID Connect Discovery 1.0, Section 3).
+#[derive(Serialize)]
+pub struct AuthorizationServerMetadata {
+    /// The issuer identifier of the Authorization Server.
+    pub issuer: String,
+    /// The URL of the authorization endpoint.
+    pub authorization_endpoint: String,
+    /// The URL of the token endpoint.
+    pub token_endpoint: String,
+    /// The URL of the JWK set the access tokens are signed with.
+    pub jwks_uri: String,
+    /// The URL of the dynamic client registration endpoint.
+    pub registration_endpoint: String,
+    /// The URL of the token introspection endpoint.
+    pub introspection_endpoint: String,
+    /// The supported client authentication methods at the introspection endpoint.
+    pub introspection_endpoint_auth_methods_supported: Vec<String>,
+    /// The URL of the token revocation endpoint.
+    pub revocation_endpoint: String,
+    /// The supported client authentication methods at the revocation endpoint.
+    pub revocation_endpoint_auth_methods_supported: Vec<String>,
+    /// The URL of the list of revoked access tokens that resource servers poll.
+    ///
+    /// This is not defined by RFC 8414, which allows additional metadata parameters.
+    pub revoked_tokens_uri: String,
+    /// The scopes clients may request.
+    pub scopes_supported: Vec<String>,
+    /// The supported `response_type` values.
+    pub response_types_supported: Vec<String>,
+    /// The supported `grant_type` values.
+    pub grant_types_supported: Vec<String>,
+    /// The supported client authentication methods at the token endpoint.
+    pub token_endpoint_auth_methods_supported: Vec<String>,
+    /// The supported PKCE code challenge methods.
+    pub code_challenge_methods_supported: Vec<String>,
+    /// The URL of the OpenID Connect UserInfo endpoint.
+    pub userinfo_endpoint: String,
+    /// The supported subject identifier types.
+    pub subject_types_supported: Vec<String>,
+    /// The algorithms ID tokens are signed with.
+    pub id_token_signing_alg_values_supported: Vec<String>,
+    /// The claims that can be returned about the user.
+    pub claims_supported: Vec<String>,
+}
+
+impl AuthorizationServerMetadata {
+    /// Builds the metadata document from the server's configuration.
+    pub fn from_config(config: &Config) -> Self {
+        let issuer = config.issuer.trim_end_matches('/').to_string();
+        let endpoint = |path: &str| format!("{issuer}{path}");
+        let strings = |values: &[&str]| values.iter().map(|value| value.to_string()).collect();
+
+        Self {
+            authorization_endpoint: endpoint("/authorize"),
+            token_endpoint: endpoint("/token"),
+            jwks_uri: endpoint("/jwks.json"),
+            registration_endpoint: endpoint("/register"),
+            introspection_endpoint: endpoint("/introspect"),
+            // Only confidential clients may introspect tokens
+            introspection_endpoint_auth_methods_supported: strings(&["client_secret_post"]),
+            revocation_endpoint: endpoint("/revoke"),
+            revocation_endpoint_auth_methods_supported: strings(
+                TOKEN_ENDPOINT_AUTH_METHODS_SUPPORTED,
+            ),
+            revoked_tokens_uri: endpoint("/revoked"),
+            scopes_supported: scope::parse(&config.supported_scopes),
+            response_types_supported: strings(RESPONSE_TYPES_SUPPORTED),
+            grant_types_supported: strings(GRANT_TYPES_SUPPORTED),
+            token_endpoint_auth_methods_supported: strings(TOKEN_ENDPOINT_AUTH_METHODS_SUPPORTED),
+            code_challenge_methods_supported: [
+                CodeChallengeMethod::S256,
+                CodeChallengeMethod::Plain,
+            ]
+            .iter()
+            .map(|method| method.as_str().to_string())
+            .collect(),
+            userinfo_endpoint: endpoint("/userinfo"),
+            subject_types_supported: strings(&["public"]),
+            id_token_signing_alg_values_supported: strings(&["RS256"]),
+            claims_supported: strings(CLAIMS_SUPPORTED),
+            issuer,
+        }
+    }
+}
+
+/// Handles the `/.well-known/oauth-authorization-server` and `/.well-known/openid-configuration`
+/// endpoints.
+pub async fn metadata(
+    State(app_state): State<SharedAppState>,
+) -> Json<AuthorizationServerMetadata> {
+    let state = app_state.lock().unwrap();
+    Json(AuthorizationServerMetadata::from_config(&state.config))
+}
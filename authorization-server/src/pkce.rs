@@ -0,0 +1,74 @@
+/// The `pkce` module implements Proof Key for Code Exchange (RFC 7636).
+/// It validates code challenges at `/authorize` and code verifiers at `/token`.
+use crate::secret;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::str::FromStr;
+
+/// Represents the transformation applied to the code verifier to derive the code challenge.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CodeChallengeMethod {
+    /// The code challenge is the code verifier itself.
+    Plain,
+    /// The code challenge is `BASE64URL(SHA256(code_verifier))`.
+    S256,
+}
+
+impl CodeChallengeMethod {
+    /// Returns the name of the method as used in the `code_challenge_method` parameter.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CodeChallengeMethod::Plain => "plain",
+            CodeChallengeMethod::S256 => "S256",
+        }
+    }
+}
+
+impl FromStr for CodeChallengeMethod {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "plain" => Ok(CodeChallengeMethod::Plain),
+            "S256" => Ok(CodeChallengeMethod::S256),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Represents the code challenge stored alongside an authorization code.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CodeChallenge {
+    /// The code challenge sent to the `/authorize` endpoint.
+    pub challenge: String,
+    /// The method used to derive the challenge from the verifier.
+    pub method: CodeChallengeMethod,
+}
+
+impl CodeChallenge {
+    /// Checks whether `code_verifier` matches this challenge.
+    pub fn verify(&self, code_verifier: &str) -> bool {
+        if !is_valid_code_verifier(code_verifier) {
+            return false;
+        }
+
+        match self.method {
+            CodeChallengeMethod::Plain => secret::constant_time_eq(code_verifier, &self.challenge),
+            CodeChallengeMethod::S256 => {
+                let digest = Sha256::digest(code_verifier.as_bytes());
+                secret::constant_time_eq(&URL_SAFE_NO_PAD.encode(digest), &self.challenge)
+            }
+        }
+    }
+}
+
+/// Checks that `value` is a syntactically valid code verifier (or challenge):
+/// 43 to 128 characters from the unreserved set `[A-Z] / [a-z] / [0-9] / "-" / "." / "_" / "~"`.
+pub fn is_valid_code_verifier(value: &str) -> bool {
+    (43..=128).contains(&value.len())
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~'))
+}
@@ -0,0 +1,75 @@
+/// The `redirect_uri` module implements the rules for redirect URIs of the OAuth 2.0 Security
+/// Best Current Practice (RFC 9700, Section 2.1): which URIs clients may register, and how the
+/// `redirect_uri` of an authorization request is matched against them.
+use std::net::IpAddr;
+use url::{Host, Url};
+
+/// Returns whether `url` points at the loopback interface of the user's device.
+fn is_loopback(url: &Url) -> bool {
+    match url.host() {
+        Some(Host::Domain(domain)) => domain.eq_ignore_ascii_case("localhost"),
+        Some(Host::Ipv4(ip)) => IpAddr::V4(ip).is_loopback(),
+        Some(Host::Ipv6(ip)) => IpAddr::V6(ip).is_loopback(),
+        None => false,
+    }
+}
+
+/// Checks that `uri` may be registered as a redirect URI.
+///
+/// Redirect URIs must be absolute `https` URIs without a fragment, wildcards or credentials.
+/// Native apps may also register `http` URIs on the loopback interface (RFC 8252, Section 7.3).
+///
+/// # Returns
+/// - `Ok(())`: The URI may be registered.
+/// - `Err(&str)`: A description of the rule the URI violates.
+pub fn validate(uri: &str) -> Result<(), &'static str> {
+    if uri.contains('*') {
+        return Err("Redirect URIs must not contain wildcards");
+    }
+    let Ok(url) = Url::parse(uri) else {
+        return Err("Redirect URIs must be absolute URIs");
+    };
+    // RFC 6749, Section 3.1.2: the endpoint URI must not include a fragment component
+    if url.fragment().is_some() {
+        return Err("Redirect URIs must not contain a fragment");
+    }
+    if !url.username().is_empty() || url.password().is_some() {
+        return Err("Redirect URIs must not contain credentials");
+    }
+    match url.scheme() {
+        "https" if url.host().is_some() => Ok(()),
+        "http" if is_loopback(&url) => Ok(()),
+        _ => Err("Redirect URIs must use https, except for loopback redirects of native apps"),
+    }
+}
+
+/// Returns whether the `redirect_uri` of an authorization request matches the registered
+/// redirect URI `registered`.
+///
+/// URIs are compared by exact string matching, except that the port of an `http` loopback
+/// redirect may differ from the registered one: native apps listen on an ephemeral port that
+/// is only known at runtime (RFC 8252, Section 7.3).
+pub fn matches(registered: &str, requested: &str) -> bool {
+    if registered == requested {
+        return true;
+    }
+
+    let (Ok(mut registered), Ok(mut requested)) = (Url::parse(registered), Url::parse(requested))
+    else {
+        return false;
+    };
+    if registered.scheme() != "http" || !is_loopback(&registered) {
+        return false;
+    }
+    if !requested.username().is_empty()
+        || requested.password().is_some()
+        || requested.fragment().is_some()
+    {
+        return false;
+    }
+
+    // Loopback URIs are valid for every port, so resetting it cannot fail
+    let _ = registered.set_port(None);
+    let _ = requested.set_port(None);
+    registered == requested
+}
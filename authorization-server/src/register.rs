@@ -1,67 +1,823 @@
 /// The `register` module handles the `/register` endpoint of the Authorization Server.
-/// This endpoint allows clients to register and obtain a `client_id` and `client_secret`.
-use crate::SharedAppState;
+/// This endpoint implements Dynamic Client Registration (RFC 7591): clients register their
+/// metadata and obtain a `client_id` and `client_secret`. Registered clients are read, updated
+/// and deleted at their `registration_client_uri` (RFC 7592).
+use crate::{
+    AppState, SharedAppState,
+    config::{Config, RegistrationPolicy},
+    metadata::{GRANT_TYPES_SUPPORTED, RESPONSE_TYPES_SUPPORTED},
+    now, redirect_uri, scope, secret,
+    storage::StorageError,
+};
 use axum::{
-    extract::{Json, State},
-    http::StatusCode,
+    extract::{Json, Path, State},
+    http::{HeaderMap, StatusCode, header},
 };
 use serde::{Deserialize, Serialize};
-use tracing;
+use std::fmt;
+use url::Url;
 use uuid::Uuid;
 
-/// Represents the request body for the `/register` endpoint.
+/// Represents the request body for the `/register` endpoint (RFC 7591, Section 2).
 #[derive(Deserialize, Debug)]
 pub struct RegisterRequest {
+    /// The name of the client, shown to users (optional).
+    pub client_name: Option<String>,
+    /// The list of redirect URIs for the client.
+    #[serde(default)]
+    pub redirect_uris: Vec<String>,
+    /// The grant types the client may use at the `/token` endpoint (optional).
+    pub grant_types: Option<Vec<String>>,
+    /// The response types the client may use at the `/authorize` endpoint (optional).
+    pub response_types: Option<Vec<String>>,
+    /// How the client authenticates at the `/token` endpoint (optional).
+    /// Use "none" to register a public client without a `client_secret`.
+    pub token_endpoint_auth_method: Option<String>,
+    /// The space-delimited list of scopes the client may request (optional).
+    pub scope: Option<String>,
+    /// The URL of the client's home page (optional).
+    pub client_uri: Option<String>,
+    /// The URL of the client's logo (optional).
+    pub logo_uri: Option<String>,
+    /// Ways to contact the people responsible for the client, such as email addresses (optional).
+    #[serde(default)]
+    pub contacts: Vec<String>,
+    /// The URL of the client's JSON Web Key Set (optional).
+    pub jwks_uri: Option<String>,
+}
+
+/// Represents a client stored in the client registry.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RegisteredClient {
     /// The name of the client.
-    pub client_name: String,
+    pub client_name: Option<String>,
+    /// The salted hash of the client secret (`None` for public clients). Clients stored by
+    /// earlier versions have their secret here in plaintext.
+    #[serde(alias = "client_secret")]
+    pub client_secret_hash: Option<String>,
     /// The list of redirect URIs for the client.
     pub redirect_uris: Vec<String>,
+    /// The scopes the client may request.
+    pub scopes: Vec<String>,
+    /// The grant types the client may use.
+    #[serde(default = "legacy_grant_types")]
+    pub grant_types: Vec<String>,
+    /// The response types the client may use.
+    #[serde(default = "default_response_types")]
+    pub response_types: Vec<String>,
+    /// The URL of the client's home page.
+    #[serde(default)]
+    pub client_uri: Option<String>,
+    /// The URL of the client's logo.
+    #[serde(default)]
+    pub logo_uri: Option<String>,
+    /// Ways to contact the people responsible for the client.
+    #[serde(default)]
+    pub contacts: Vec<String>,
+    /// The URL of the client's JSON Web Key Set.
+    #[serde(default)]
+    pub jwks_uri: Option<String>,
+    /// The time the `client_id` was issued (UNIX timestamp).
+    #[serde(default)]
+    pub client_id_issued_at: u64,
+    /// The salted hash of the token that authorizes requests to the client configuration
+    /// endpoint. Clients stored by earlier versions have their token here in plaintext.
+    #[serde(default, alias = "registration_access_token")]
+    pub registration_access_token_hash: Option<String>,
+    /// The limits of the initial access token the client was registered with, which also
+    /// apply to updates of its metadata.
+    #[serde(default)]
+    pub registration_limits: Option<RegistrationLimits>,
+}
+
+/// Redacts the hashed credentials of the client, so that it can be logged.
+impl fmt::Debug for RegisteredClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RegisteredClient")
+            .field("client_name", &self.client_name)
+            .field(
+                "client_secret_hash",
+                &secret::redact(&self.client_secret_hash),
+            )
+            .field("redirect_uris", &self.redirect_uris)
+            .field("scopes", &self.scopes)
+            .field("grant_types", &self.grant_types)
+            .field("response_types", &self.response_types)
+            .field("client_uri", &self.client_uri)
+            .field("logo_uri", &self.logo_uri)
+            .field("contacts", &self.contacts)
+            .field("jwks_uri", &self.jwks_uri)
+            .field("client_id_issued_at", &self.client_id_issued_at)
+            .field(
+                "registration_access_token_hash",
+                &secret::redact(&self.registration_access_token_hash),
+            )
+            .field("registration_limits", &self.registration_limits)
+            .finish()
+    }
 }
 
-/// Represents a successful registration response.
+impl RegisteredClient {
+    /// Returns whether the client is a public client, i.e. has no `client_secret`.
+    pub fn is_public(&self) -> bool {
+        self.client_secret_hash.is_none()
+    }
+
+    /// Checks `client_secret` against the secret of the client, in constant time.
+    pub fn verify_secret(&self, client_secret: &str) -> bool {
+        self.client_secret_hash
+            .as_deref()
+            .is_some_and(|hash| secret::verify(client_secret, hash))
+    }
+
+    /// Returns whether the client registered the grant type `grant_type`.
+    pub fn allows_grant_type(&self, grant_type: &str) -> bool {
+        self.grant_types.iter().any(|allowed| allowed == grant_type)
+    }
+
+    /// Returns whether the client registered the response type `response_type`.
+    pub fn allows_response_type(&self, response_type: &str) -> bool {
+        self.response_types
+            .iter()
+            .any(|allowed| allowed == response_type)
+    }
+
+    /// Returns the method the client authenticates with at the `/token` endpoint.
+    pub fn token_endpoint_auth_method(&self) -> &'static str {
+        if self.is_public() {
+            "none"
+        } else {
+            "client_secret_post"
+        }
+    }
+}
+
+/// Clients stored before grant types were recorded keep every grant they could use back then.
+fn legacy_grant_types() -> Vec<String> {
+    GRANT_TYPES_SUPPORTED
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+fn default_response_types() -> Vec<String> {
+    vec!["code".to_string()]
+}
+
+/// Represents a successful registration response (RFC 7591, Section 3.2.1): the client
+/// credentials together with the registered metadata.
 #[derive(Serialize)]
 pub struct RegisterResponse {
     /// The client ID issued to the client.
     pub client_id: String,
-    /// The client secret issued to the client.
-    pub client_secret: String,
+    /// The client secret issued to the client. Only its hash is stored, so the secret is
+    /// returned once, when it is issued (omitted otherwise and for public clients).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+    /// The time the `client_id` was issued (UNIX timestamp).
+    pub client_id_issued_at: u64,
+    /// The time the `client_secret` expires, `0` if it never does (omitted for public clients).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_secret_expires_at: Option<u64>,
+    /// The name of the client.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_name: Option<String>,
+    /// The list of redirect URIs for the client.
+    pub redirect_uris: Vec<String>,
+    /// The grant types the client may use.
+    pub grant_types: Vec<String>,
+    /// The response types the client may use.
+    pub response_types: Vec<String>,
+    /// How the client authenticates at the `/token` endpoint.
+    pub token_endpoint_auth_method: String,
+    /// The space-delimited list of scopes the client may request.
+    pub scope: String,
+    /// The URL of the client's home page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_uri: Option<String>,
+    /// The URL of the client's logo.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logo_uri: Option<String>,
+    /// Ways to contact the people responsible for the client.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub contacts: Vec<String>,
+    /// The URL of the client's JSON Web Key Set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jwks_uri: Option<String>,
+    /// The token that authorizes requests to `registration_client_uri`.
+    pub registration_access_token: String,
+    /// The URL of the client configuration endpoint of the client (RFC 7592).
+    pub registration_client_uri: String,
+}
+
+impl RegisterResponse {
+    /// Builds the response describing the registered client `client_id`, including the
+    /// `client_secret` if one was just issued.
+    ///
+    /// Only hashes of the credentials are stored, so the `registration_access_token` is the one
+    /// just issued or presented by the caller.
+    pub fn new(
+        config: &Config,
+        client_id: &str,
+        client: RegisteredClient,
+        client_secret: Option<String>,
+        registration_access_token: String,
+    ) -> Self {
+        Self {
+            client_id: client_id.to_string(),
+            registration_client_uri: registration_client_uri(config, client_id),
+            client_secret_expires_at: client.client_secret_hash.as_ref().map(|_| 0),
+            token_endpoint_auth_method: client.token_endpoint_auth_method().to_string(),
+            scope: scope::join(&client.scopes),
+            client_secret,
+            client_id_issued_at: client.client_id_issued_at,
+            client_name: client.client_name,
+            redirect_uris: client.redirect_uris,
+            grant_types: client.grant_types,
+            response_types: client.response_types,
+            client_uri: client.client_uri,
+            logo_uri: client.logo_uri,
+            contacts: client.contacts,
+            jwks_uri: client.jwks_uri,
+            registration_access_token,
+        }
+    }
+}
+
+/// Returns the URL of the client configuration endpoint of the client `client_id`.
+fn registration_client_uri(config: &Config, client_id: &str) -> String {
+    format!(
+        "{}/register/{client_id}",
+        config.issuer.trim_end_matches('/')
+    )
+}
+
+/// Represents an error response for the `/register` endpoint (RFC 7591, Section 3.2.2).
+#[derive(Serialize)]
+pub struct RegistrationErrorResponse {
+    /// The error code, `invalid_redirect_uri` or `invalid_client_metadata`.
+    pub error: String,
+    /// A human-readable explanation of the error, for the client developer.
+    pub error_description: String,
+}
+
+/// The error type returned by the `/register` endpoint.
+pub(crate) type RegistrationError = (StatusCode, Json<RegistrationErrorResponse>);
+
+/// Builds an error response for the `/register` endpoint.
+pub(crate) fn error_response(error: &str, description: &str) -> RegistrationError {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(RegistrationErrorResponse {
+            error: error.to_string(),
+            error_description: description.to_string(),
+        }),
+    )
+}
+
+/// Logs a storage failure and maps it to a `server_error` response.
+fn server_error(err: StorageError) -> RegistrationError {
+    tracing::error!("Storage error: {}", err);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(RegistrationErrorResponse {
+            error: "server_error".to_string(),
+            error_description: "The authorization server encountered an unexpected error"
+                .to_string(),
+        }),
+    )
+}
+
+fn invalid_metadata(description: &str) -> RegistrationError {
+    error_response("invalid_client_metadata", description)
+}
+
+/// Builds the error response for a missing or invalid bearer token (RFC 6750, Section 3.1).
+fn invalid_token(description: &str) -> RegistrationError {
+    (
+        StatusCode::UNAUTHORIZED,
+        Json(RegistrationErrorResponse {
+            error: "invalid_token".to_string(),
+            error_description: description.to_string(),
+        }),
+    )
+}
+
+/// Returns the bearer token of the `Authorization` header, if any.
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+}
+
+/// Limits on the metadata of the clients registered with an initial access token.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RegistrationLimits {
+    /// The scopes the clients may register for, `None` for every supported scope.
+    pub scopes: Option<Vec<String>>,
+    /// The grant types the clients may register for, `None` for every supported grant type.
+    pub grant_types: Option<Vec<String>>,
+}
+
+impl RegistrationLimits {
+    /// Checks that the metadata of `client` stays within the limits.
+    fn check(&self, client: &RegisteredClient) -> Result<(), RegistrationError> {
+        if let Some(scopes) = &self.scopes
+            && !scope::is_subset(&client.scopes, scopes)
+        {
+            tracing::warn!("Scope exceeds the registration limits: {:?}", client.scopes);
+            return Err(invalid_metadata(
+                "A scope is not allowed by the initial access token",
+            ));
+        }
+        if let Some(grant_types) = &self.grant_types
+            && !client
+                .grant_types
+                .iter()
+                .all(|grant_type| grant_types.contains(grant_type))
+        {
+            tracing::warn!(
+                "Grant types exceed the registration limits: {:?}",
+                client.grant_types
+            );
+            return Err(invalid_metadata(
+                "A grant type is not allowed by the initial access token",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// An initial access token (RFC 7591, Section 3) issued by an admin.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InitialAccessToken {
+    /// The limits on the metadata of the clients registered with the token.
+    #[serde(flatten)]
+    pub limits: RegistrationLimits,
+    /// The time at which the token expires, in seconds since the Unix epoch.
+    #[serde(default)]
+    pub expires_at: u64,
+    /// How many more clients may be registered with the token, `None` for no limit.
+    pub remaining_uses: Option<u32>,
+}
+
+/// Checks that the registration request is allowed by the registration policy, and returns
+/// the initial access token it carries, if the policy requires one, with its lookup hash.
+fn authorize_registration(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<Option<(String, InitialAccessToken)>, RegistrationError> {
+    let presented = bearer_token(headers);
+    match state.config.registration_policy {
+        RegistrationPolicy::Open => Ok(None),
+        RegistrationPolicy::InitialAccessToken => {
+            let token = match presented {
+                Some(token) => {
+                    let token_hash = secret::lookup_hash(token);
+                    state
+                        .storage
+                        .get_initial_access_token(&token_hash)
+                        .map_err(server_error)?
+                        .filter(|token| token.expires_at > now())
+                        .map(|token| (token_hash, token))
+                }
+                None => None,
+            };
+            token.map(Some).ok_or_else(|| {
+                tracing::warn!("Rejected registration without a valid initial access token");
+                invalid_token("A valid initial access token is required to register clients")
+            })
+        }
+        RegistrationPolicy::Admin => {
+            let authorized = presented
+                .zip(state.config.admin_token.as_deref())
+                .is_some_and(|(presented, admin_token)| {
+                    secret::constant_time_eq(presented, admin_token)
+                });
+            if !authorized {
+                tracing::warn!("Rejected registration without the admin token");
+                return Err(invalid_token(
+                    "The admin token is required to register clients",
+                ));
+            }
+            Ok(None)
+        }
+    }
+}
+
+/// Returns whether `value` is an absolute `http` or `https` URL.
+fn is_web_url(value: &str) -> bool {
+    Url::parse(value).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+}
+
+/// Validates the registration request and builds the client to register, issuing a
+/// `client_secret` to confidential clients. The client only stores the hash of the secret,
+/// so the secret itself is returned alongside it.
+///
+/// Omitted metadata is replaced by the server's defaults: clients use the authorization code
+/// grant together with refresh tokens, and may request the default scope.
+pub(crate) fn new_client(
+    payload: RegisterRequest,
+    config: &Config,
+) -> Result<(RegisteredClient, Option<String>), RegistrationError> {
+    let grant_types = payload.grant_types.unwrap_or_else(|| {
+        vec![
+            "authorization_code".to_string(),
+            "refresh_token".to_string(),
+        ]
+    });
+    if let Some(grant_type) = grant_types
+        .iter()
+        .find(|grant_type| !GRANT_TYPES_SUPPORTED.contains(&grant_type.as_str()))
+    {
+        tracing::warn!("Unsupported grant_type: {}", grant_type);
+        return Err(invalid_metadata("A grant type is not supported"));
+    }
+
+    let response_types = payload.response_types.unwrap_or_else(|| {
+        if grant_types
+            .iter()
+            .any(|grant_type| grant_type == "authorization_code")
+        {
+            default_response_types()
+        } else {
+            Vec::new()
+        }
+    });
+    if let Some(response_type) = response_types
+        .iter()
+        .find(|response_type| !RESPONSE_TYPES_SUPPORTED.contains(&response_type.as_str()))
+    {
+        tracing::warn!("Unsupported response_type: {}", response_type);
+        return Err(invalid_metadata("A response type is not supported"));
+    }
+
+    // RFC 7591, Section 2.1: the code response type goes together with the authorization code grant
+    let uses_code = grant_types
+        .iter()
+        .any(|grant_type| grant_type == "authorization_code");
+    if uses_code
+        != response_types
+            .iter()
+            .any(|response_type| response_type == "code")
+    {
+        tracing::warn!(
+            "Inconsistent grant_types {:?} and response_types {:?}",
+            grant_types,
+            response_types
+        );
+        return Err(invalid_metadata(
+            "The code response type requires the authorization_code grant type and vice versa",
+        ));
+    }
+
+    // Public clients cannot keep a secret, so none is issued to them
+    let is_public_client = match payload.token_endpoint_auth_method.as_deref() {
+        None | Some("client_secret_post") => false,
+        Some("none") => true,
+        Some(method) => {
+            tracing::warn!("Unsupported token_endpoint_auth_method: {}", method);
+            return Err(invalid_metadata(
+                "The token endpoint authentication method is not supported",
+            ));
+        }
+    };
+    if is_public_client
+        && grant_types
+            .iter()
+            .any(|grant_type| grant_type == "client_credentials")
+    {
+        tracing::warn!("Public client registered for the client_credentials grant");
+        return Err(invalid_metadata(
+            "The client_credentials grant requires client authentication",
+        ));
+    }
+
+    if uses_code && payload.redirect_uris.is_empty() {
+        tracing::warn!("Missing redirect_uris");
+        return Err(error_response(
+            "invalid_redirect_uri",
+            "The authorization_code grant type requires at least one redirect URI",
+        ));
+    }
+    for redirect_uri in &payload.redirect_uris {
+        if let Err(description) = redirect_uri::validate(redirect_uri) {
+            tracing::warn!("Invalid redirect_uri: {}", redirect_uri);
+            return Err(error_response("invalid_redirect_uri", description));
+        }
+    }
+
+    // Clients registered without a scope may request the server's default scope
+    let scopes = scope::parse(payload.scope.as_deref().unwrap_or(&config.default_scope));
+    if !scope::is_subset(&scopes, &scope::parse(&config.supported_scopes)) {
+        tracing::warn!("Unsupported scope: {:?}", payload.scope);
+        return Err(invalid_metadata("A scope is not supported"));
+    }
+
+    for (name, uri) in [
+        ("client_uri", &payload.client_uri),
+        ("logo_uri", &payload.logo_uri),
+        ("jwks_uri", &payload.jwks_uri),
+    ] {
+        if let Some(uri) = uri
+            && !is_web_url(uri)
+        {
+            tracing::warn!("Invalid {}: {}", name, uri);
+            return Err(invalid_metadata(&format!(
+                "The {name} must be an http or https URL"
+            )));
+        }
+    }
+
+    if payload
+        .contacts
+        .iter()
+        .any(|contact| contact.trim().is_empty())
+    {
+        tracing::warn!("Empty contact: {:?}", payload.contacts);
+        return Err(invalid_metadata("Contacts must not be empty"));
+    }
+
+    let client_secret = (!is_public_client).then(|| Uuid::new_v4().to_string());
+    let client = RegisteredClient {
+        client_name: payload.client_name,
+        client_secret_hash: client_secret.as_deref().map(secret::hash),
+        redirect_uris: payload.redirect_uris,
+        scopes,
+        grant_types,
+        response_types,
+        client_uri: payload.client_uri,
+        logo_uri: payload.logo_uri,
+        contacts: payload.contacts,
+        jwks_uri: payload.jwks_uri,
+        client_id_issued_at: now(),
+        registration_access_token_hash: None,
+        registration_limits: None,
+    };
+    Ok((client, client_secret))
 }
 
 /// Handles the `/register` endpoint.
 ///
-/// This function registers a new client, generates a `client_id` and `client_secret`,
-/// and stores the client information in the shared application state.
+/// This function checks the registration policy, validates the client metadata, generates a
+/// `client_id` and (unless the client is public) a `client_secret`, and stores the client
+/// information in the shared application state.
 ///
 /// # Arguments
 /// - `State(app_state)`: Shared application state.
-/// - `Json(payload)`: The request body containing the registration parameters.
+/// - `headers`: The request headers, carrying the initial access token or admin token if the
+///   registration policy requires one.
+/// - `Json(payload)`: The request body containing the client metadata.
 ///
 /// # Returns
-/// - `Json<RegisterResponse>`: A successful registration response with the `client_id` and `client_secret`.
-/// - `(StatusCode, String)`: An error response if validation fails.
+/// - `(StatusCode, Json<RegisterResponse>)`: `201 Created` with the client credentials and the
+///   registered metadata.
+/// - `(StatusCode, Json<RegistrationErrorResponse>)`: An error response if the request is not
+///   authorized or validation fails.
 #[axum_macros::debug_handler]
 pub async fn register_client(
     State(app_state): State<SharedAppState>,
+    headers: HeaderMap,
     Json(payload): Json<RegisterRequest>,
-) -> Result<Json<RegisterResponse>, (StatusCode, String)> {
+) -> Result<(StatusCode, Json<RegisterResponse>), RegistrationError> {
     tracing::info!("Received client registration request: {:?}", payload);
 
     let mut state = app_state.lock().unwrap();
+    let initial_access_token = authorize_registration(&state, &headers)?;
 
-    // Generate a unique client_id and client_secret
+    let (mut client, client_secret) = new_client(payload, &state.config)?;
+    if let Some((_, token)) = &initial_access_token {
+        token.limits.check(&client)?;
+        client.registration_limits = Some(token.limits.clone());
+    }
+    let registration_access_token = Uuid::new_v4().to_string();
+    client.registration_access_token_hash = Some(secret::hash(&registration_access_token));
     let client_id = Uuid::new_v4().to_string();
-    let client_secret = Uuid::new_v4().to_string();
 
     // Store the client information in the state
-    state.client_registry.insert(
-        client_id.clone(),
-        (client_secret.clone(), payload.redirect_uris.clone()),
-    );
+    state
+        .storage
+        .put_client(&client_id, client.clone())
+        .map_err(server_error)?;
 
     tracing::info!("Registered new client with client_id: {}", client_id);
 
-    Ok(Json(RegisterResponse {
-        client_id,
+    // Count the registration against the uses left on the initial access token
+    if let Some((token_hash, mut token)) = initial_access_token
+        && let Some(remaining_uses) = token.remaining_uses
+    {
+        if remaining_uses > 1 {
+            token.remaining_uses = Some(remaining_uses - 1);
+            state
+                .storage
+                .put_initial_access_token(&token_hash, token)
+                .map_err(server_error)?;
+        } else {
+            state
+                .storage
+                .remove_initial_access_token(&token_hash)
+                .map_err(server_error)?;
+        }
+    }
+
+    Ok((
+        StatusCode::CREATED,
+        Json(RegisterResponse::new(
+            &state.config,
+            &client_id,
+            client,
+            client_secret,
+            registration_access_token,
+        )),
+    ))
+}
+
+/// Represents the request body of a client update (RFC 7592, Section 2.2): the complete
+/// metadata of the client, which replaces the registered metadata.
+#[derive(Deserialize)]
+pub struct ClientUpdateRequest {
+    /// The client ID, which must match the client being updated.
+    pub client_id: String,
+    /// The current client secret. A new secret is issued to confidential clients that omit it.
+    pub client_secret: Option<String>,
+    /// The new metadata of the client.
+    #[serde(flatten)]
+    pub metadata: RegisterRequest,
+}
+
+/// Checks the registration access token presented for the client `client_id` and returns the
+/// client together with the token.
+///
+/// Unknown clients are answered like invalid tokens, so that the endpoint does not reveal
+/// which clients exist.
+fn authenticate_registration(
+    state: &AppState,
+    client_id: &str,
+    headers: &HeaderMap,
+) -> Result<(RegisteredClient, String), RegistrationError> {
+    let client = state.storage.get_client(client_id).map_err(server_error)?;
+    let presented = bearer_token(headers);
+
+    match (client, presented) {
+        (Some(client), Some(presented))
+            if client
+                .registration_access_token_hash
+                .as_deref()
+                .is_some_and(|hash| secret::verify(presented, hash)) =>
+        {
+            Ok((client, presented.to_string()))
+        }
+        _ => {
+            tracing::warn!(
+                "Invalid registration access token for client_id: {}",
+                client_id
+            );
+            Err(invalid_token("The registration access token is invalid"))
+        }
+    }
+}
+
+/// Handles `GET` requests to the client configuration endpoint (RFC 7592, Section 2.1).
+///
+/// # Arguments
+/// - `State(app_state)`: Shared application state.
+/// - `Path(client_id)`: The client ID from the `registration_client_uri`.
+/// - `headers`: The request headers, carrying the registration access token.
+///
+/// # Returns
+/// - `Json<RegisterResponse>`: The registered metadata, without the `client_secret`, which is
+///   only returned when it is issued.
+/// - `(StatusCode, Json<RegistrationErrorResponse>)`: `401 Unauthorized` if the token is invalid.
+pub async fn read_client(
+    State(app_state): State<SharedAppState>,
+    Path(client_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<RegisterResponse>, RegistrationError> {
+    let state = app_state.lock().unwrap();
+    let (client, registration_access_token) =
+        authenticate_registration(&state, &client_id, &headers)?;
+    Ok(Json(RegisterResponse::new(
+        &state.config,
+        &client_id,
+        client,
+        None,
+        registration_access_token,
+    )))
+}
+
+/// Handles `PUT` requests to the client configuration endpoint (RFC 7592, Section 2.2).
+///
+/// The request replaces the registered metadata. The `client_id`, the time it was issued and
+/// the registration access token stay the same; the `client_secret` is kept if the request
+/// repeats it and rotated otherwise.
+///
+/// # Arguments
+/// - `State(app_state)`: Shared application state.
+/// - `Path(client_id)`: The client ID from the `registration_client_uri`.
+/// - `headers`: The request headers, carrying the registration access token.
+/// - `Json(payload)`: The new metadata of the client.
+///
+/// # Returns
+/// - `Json<RegisterResponse>`: The client credentials and the updated metadata.
+/// - `(StatusCode, Json<RegistrationErrorResponse>)`: An error response if the token is invalid
+///   or validation fails.
+pub async fn update_client(
+    State(app_state): State<SharedAppState>,
+    Path(client_id): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<ClientUpdateRequest>,
+) -> Result<Json<RegisterResponse>, RegistrationError> {
+    tracing::info!(
+        "Received client update request for client_id: {}",
+        client_id
+    );
+
+    let mut state = app_state.lock().unwrap();
+    let (current, registration_access_token) =
+        authenticate_registration(&state, &client_id, &headers)?;
+
+    if payload.client_id != client_id {
+        tracing::warn!("Mismatching client_id in update: {}", payload.client_id);
+        return Err(invalid_metadata("The client_id does not match the client"));
+    }
+    if let Some(client_secret) = &payload.client_secret
+        && !current.verify_secret(client_secret)
+    {
+        tracing::warn!("Mismatching client_secret in update for: {}", client_id);
+        return Err(invalid_metadata(
+            "The client_secret does not match the client",
+        ));
+    }
+
+    let (mut client, mut client_secret) = new_client(payload.metadata, &state.config)?;
+    if let Some(limits) = &current.registration_limits {
+        limits.check(&client)?;
+    }
+    client.client_id_issued_at = current.client_id_issued_at;
+    client.registration_access_token_hash = current.registration_access_token_hash;
+    client.registration_limits = current.registration_limits;
+    if client.client_secret_hash.is_some() && payload.client_secret.is_some() {
+        client.client_secret_hash = current.client_secret_hash;
+        client_secret = payload.client_secret;
+    }
+
+    state
+        .storage
+        .put_client(&client_id, client.clone())
+        .map_err(server_error)?;
+
+    tracing::info!("Updated client with client_id: {}", client_id);
+
+    Ok(Json(RegisterResponse::new(
+        &state.config,
+        &client_id,
+        client,
         client_secret,
-    }))
+        registration_access_token,
+    )))
+}
+
+/// Handles `DELETE` requests to the client configuration endpoint (RFC 7592, Section 2.3).
+///
+/// Once deleted, the client can no longer authenticate. Its access and refresh tokens are
+/// revoked, so that introspection reports them inactive and resource servers reject them, and
+/// the consents users gave it are forgotten.
+///
+/// # Arguments
+/// - `State(app_state)`: Shared application state.
+/// - `Path(client_id)`: The client ID from the `registration_client_uri`.
+/// - `headers`: The request headers, carrying the registration access token.
+///
+/// # Returns
+/// - `StatusCode`: `204 No Content` once the client is deleted.
+/// - `(StatusCode, Json<RegistrationErrorResponse>)`: `401 Unauthorized` if the token is invalid.
+pub async fn delete_client(
+    State(app_state): State<SharedAppState>,
+    Path(client_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<StatusCode, RegistrationError> {
+    let mut state = app_state.lock().unwrap();
+    authenticate_registration(&state, &client_id, &headers)?;
+
+    state
+        .storage
+        .remove_client(&client_id)
+        .map_err(server_error)?;
+    state
+        .storage
+        .revoke_client_refresh_tokens(&client_id)
+        .map_err(server_error)?;
+    state
+        .storage
+        .revoke_client_access_tokens(&client_id)
+        .map_err(server_error)?;
+    state
+        .storage
+        .remove_client_consents(&client_id)
+        .map_err(server_error)?;
+
+    tracing::info!("Deleted client with client_id: {}", client_id);
+
+    Ok(StatusCode::NO_CONTENT)
 }
@@ -0,0 +1,22 @@
+/// The `resource` module resolves the resource indicators (RFC 8707) clients send to `/authorize`
+/// and `/token`. The resource a token is requested for becomes its audience (`aud` claim), so that
+/// a token minted for one API is rejected by every other API.
+use crate::config::Config;
+
+/// Returns the audience of a token requested for `resource`, or `None` if the resource is not
+/// one of the configured resources. Tokens requested without a resource are issued for the
+/// default resource.
+pub fn audience(config: &Config, resource: Option<&str>) -> Option<String> {
+    match resource {
+        None => Some(config.default_resource.clone()),
+        Some(resource)
+            if config
+                .resources
+                .split_whitespace()
+                .any(|known| known == resource) =>
+        {
+            Some(resource.to_string())
+        }
+        Some(_) => None,
+    }
+}
@@ -0,0 +1,239 @@
+/// The `revoke` module handles the `/revoke` endpoint of the Authorization Server (RFC 7009)
+/// and the `/revoked` denylist, which lets resource servers learn about revoked access tokens
+/// that are still within their lifetime.
+use crate::{
+    AppState, SharedAppState,
+    secret::{self, REDACTED, redact},
+    token::{
+        TokenError, authenticate_client, decode_access_token, error_response, revoke_token_family,
+        server_error,
+    },
+};
+use axum::{
+    extract::{Form, State},
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Json},
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Represents the request body for the `/revoke` endpoint.
+#[derive(Deserialize)]
+pub struct RevocationRequest {
+    /// The token to revoke.
+    pub token: String,
+    /// A hint about the type of the token ("access_token" or "refresh_token").
+    pub token_type_hint: Option<String>,
+    /// The client ID of the revoking client.
+    pub client_id: String,
+    /// The client secret (optional for public clients).
+    pub client_secret: Option<String>,
+}
+
+/// Redacts the token and the client credentials of the request, so that it can be logged.
+impl fmt::Debug for RevocationRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RevocationRequest")
+            .field("token", &REDACTED)
+            .field("token_type_hint", &self.token_type_hint)
+            .field("client_id", &self.client_id)
+            .field("client_secret", &redact(&self.client_secret))
+            .finish()
+    }
+}
+
+/// Represents a revoked access token that has not expired yet.
+#[derive(Serialize)]
+pub struct RevokedToken {
+    /// The unique identifier of the token.
+    pub jti: String,
+    /// The expiration time of the token (UNIX timestamp), after which it can be forgotten.
+    pub exp: u64,
+}
+
+/// Represents the response of the `/revoked` endpoint.
+#[derive(Serialize)]
+pub struct RevokedTokens {
+    /// The revoked access tokens that have not expired yet.
+    pub revoked: Vec<RevokedToken>,
+}
+
+/// Handles the `/revoke` endpoint.
+///
+/// Clients may only revoke tokens that were issued to them. Revoking a refresh token also
+/// revokes every token descending from the same authorization. Unknown tokens are ignored, as
+/// required by RFC 7009, Section 2.2.
+///
+/// # Returns
+/// - `StatusCode::OK`: The token is revoked (or was unknown).
+/// - `(StatusCode, Json<TokenErrorResponse>)`: An error response if the request is refused.
+pub async fn revoke(
+    State(app_state): State<SharedAppState>,
+    Form(payload): Form<RevocationRequest>,
+) -> Result<StatusCode, TokenError> {
+    tracing::info!(
+        "Received revocation request from client_id: {}",
+        payload.client_id
+    );
+
+    let mut state = app_state.lock().unwrap();
+
+    authenticate_client(&state, &payload.client_id, payload.client_secret.as_deref())?;
+
+    // Look the token up as the hinted type first, then as the other type
+    let revoked = match payload.token_type_hint.as_deref() {
+        Some("refresh_token") => {
+            revoke_refresh_token(&mut state, &payload)?
+                || revoke_access_token(&mut state, &payload)?
+        }
+        _ => {
+            revoke_access_token(&mut state, &payload)?
+                || revoke_refresh_token(&mut state, &payload)?
+        }
+    };
+
+    if !revoked {
+        tracing::info!("Ignoring revocation of an unknown token");
+    }
+    Ok(StatusCode::OK)
+}
+
+/// Revokes the presented access token, returning whether it was known.
+fn revoke_access_token(
+    state: &mut AppState,
+    payload: &RevocationRequest,
+) -> Result<bool, TokenError> {
+    // Expired tokens can still be revoked; they simply drop off the denylist sooner
+    let Some(claims) = decode_access_token(state, &payload.token, true) else {
+        return Ok(false);
+    };
+    let Some(mut access_token) = state
+        .storage
+        .get_access_token(&claims.jti)
+        .map_err(server_error)?
+    else {
+        return Ok(false);
+    };
+
+    if access_token.client_id != payload.client_id {
+        tracing::warn!(
+            "Access token was not issued to client_id: {}",
+            payload.client_id
+        );
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            "unauthorized_client",
+        ));
+    }
+
+    access_token.revoked = true;
+    state
+        .storage
+        .put_access_token(&claims.jti, access_token)
+        .map_err(server_error)?;
+    tracing::info!("Revoked access token: {}", claims.jti);
+    Ok(true)
+}
+
+/// Revokes the presented refresh token and its family, returning whether it was known.
+fn revoke_refresh_token(
+    state: &mut AppState,
+    payload: &RevocationRequest,
+) -> Result<bool, TokenError> {
+    let Some(refresh_token) = state
+        .storage
+        .get_refresh_token(&payload.token)
+        .map_err(server_error)?
+    else {
+        return Ok(false);
+    };
+
+    if refresh_token.client_id != payload.client_id {
+        tracing::warn!(
+            "Refresh token was not issued to client_id: {}",
+            payload.client_id
+        );
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            "unauthorized_client",
+        ));
+    }
+
+    revoke_token_family(state, &refresh_token.family_id)?;
+    tracing::info!("Revoked token family: {}", refresh_token.family_id);
+    Ok(true)
+}
+
+/// Checks that a request to `/revoked` carries the admin token, or the credentials of one of
+/// the `introspection_clients` in an HTTP Basic `Authorization` header (RFC 6749,
+/// Section 2.3.1).
+fn authorize_revoked_tokens(state: &AppState, headers: &HeaderMap) -> Result<(), TokenError> {
+    let authorization = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+
+    if let Some(presented) = authorization.and_then(|value| value.strip_prefix("Bearer ")) {
+        let admin_token = state.config.admin_token.as_deref();
+        if admin_token.is_some_and(|admin_token| secret::constant_time_eq(presented, admin_token)) {
+            return Ok(());
+        }
+        tracing::warn!("Rejected revoked tokens request with an invalid admin token");
+        return Err(error_response(StatusCode::UNAUTHORIZED, "invalid_client"));
+    }
+
+    let credentials = authorization
+        .and_then(|value| value.strip_prefix("Basic "))
+        .and_then(|encoded| STANDARD.decode(encoded).ok())
+        .and_then(|decoded| String::from_utf8(decoded).ok());
+    let Some((client_id, client_secret)) = credentials
+        .as_deref()
+        .and_then(|credentials| credentials.split_once(':'))
+    else {
+        tracing::warn!("Rejected revoked tokens request without credentials");
+        return Err(error_response(StatusCode::UNAUTHORIZED, "invalid_client"));
+    };
+
+    authenticate_client(state, client_id, Some(client_secret))?;
+    if !state.config.is_introspection_client(client_id) {
+        tracing::warn!(
+            "Client may not list revoked tokens, it is not a resource server: {}",
+            client_id
+        );
+        return Err(error_response(StatusCode::FORBIDDEN, "unauthorized_client"));
+    }
+    Ok(())
+}
+
+/// Handles the `/revoked` endpoint.
+///
+/// Lists the revoked access tokens that have not expired yet, so that resource servers
+/// validating JWTs locally can reject them. The list must not be cached, since it is polled.
+/// Only the resource servers listed in `introspection_clients` and admins may read it.
+///
+/// # Returns
+/// - `Json<RevokedTokens>`: The revoked access tokens.
+/// - `(StatusCode, Json<TokenErrorResponse>)`: An error response if the caller is not authorized.
+pub async fn revoked_tokens(
+    State(app_state): State<SharedAppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, TokenError> {
+    let state = app_state.lock().unwrap();
+    authorize_revoked_tokens(&state, &headers)?;
+
+    let revoked = state
+        .storage
+        .revoked_access_tokens()
+        .map_err(server_error)?
+        .into_iter()
+        .map(|(jti, access_token)| RevokedToken {
+            jti,
+            exp: access_token.expires_at,
+        })
+        .collect();
+
+    Ok((
+        [(header::CACHE_CONTROL, "no-cache")],
+        Json(RevokedTokens { revoked }),
+    ))
+}
@@ -1,27 +1,71 @@
-use crate::{AppState, SharedAppState, authorize, jwks, register, token};
+use crate::{
+    AppState, SharedAppState, admin, authorize, config::Config, introspect, jwks, keys, metadata,
+    register, revoke, token, userinfo,
+};
 use axum::{
     Router,
     routing::{get, post},
 };
+use dotenvy::dotenv;
 use std::{
-    collections::HashMap,
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
 pub fn router() -> Router {
+    dotenv().ok(); // Load environment variables from .env, once at startup
+
     // Create the shared application state
-    let app_state: SharedAppState = Arc::new(Mutex::new(AppState {
-        authorization_state: HashMap::new(),
-        client_registry: HashMap::new(),
-    }));
+    let config = Config::from_env();
+    let key_sync_interval = Duration::from_secs(config.key_sync_interval);
+    let access_token_purge_interval = Duration::from_secs(config.access_token_purge_interval);
+    let app_state: SharedAppState = Arc::new(Mutex::new(AppState::from_config(config)));
+
+    // Pick up keys rotated by other replicas sharing the storage
+    keys::spawn_key_sync(app_state.clone(), key_sync_interval);
+    // Forget the access tokens that have expired, which can no longer be revoked
+    token::spawn_access_token_purge(app_state.clone(), access_token_purge_interval);
 
+    router_with_state(app_state)
+}
+
+pub fn router_with_state(app_state: SharedAppState) -> Router {
     // Build the application with routes for OAuth 2.0
-    let app = Router::new()
-        .route("/authorize", get(authorize::authorize))
+    Router::new()
+        .route(
+            "/authorize",
+            get(authorize::authorize).post(authorize::submit_form),
+        )
         .route("/token", post(token::token))
+        .route("/introspect", post(introspect::introspect))
+        .route("/revoke", post(revoke::revoke))
+        .route("/revoked", get(revoke::revoked_tokens))
+        .route(
+            "/userinfo",
+            get(userinfo::userinfo).post(userinfo::userinfo),
+        )
         .route("/register", post(register::register_client))
+        .route(
+            "/register/{client_id}",
+            get(register::read_client)
+                .put(register::update_client)
+                .delete(register::delete_client),
+        )
         .route("/jwks.json", get(jwks::jwks)) // Add the JWKS route
-        .with_state(app_state); // Use the unified state
-
-    app
+        .route(
+            "/.well-known/oauth-authorization-server",
+            get(metadata::metadata),
+        )
+        .route("/.well-known/openid-configuration", get(metadata::metadata))
+        .route("/admin/keys/rotate", post(admin::rotate_keys))
+        .route("/admin/users", post(admin::create_user))
+        .route(
+            "/admin/initial-access-tokens",
+            post(admin::create_initial_access_token),
+        )
+        .route(
+            "/admin/initial-access-tokens/revoke",
+            post(admin::revoke_initial_access_token),
+        )
+        .with_state(app_state) // Use the unified state
 }
@@ -0,0 +1,44 @@
+/// The `scope` module provides helpers for working with OAuth 2.0 scope values.
+/// A scope is a space-delimited list of case-sensitive scope tokens (RFC 6749, Section 3.3).
+use std::collections::HashSet;
+
+/// Splits a space-delimited scope string into its scope tokens, dropping repeated tokens.
+pub fn parse(scope: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    scope
+        .split_whitespace()
+        .filter(|token| seen.insert(*token))
+        .map(String::from)
+        .collect()
+}
+
+/// Joins scope tokens into a space-delimited scope string.
+pub fn join(scopes: &[String]) -> String {
+    scopes.join(" ")
+}
+
+/// Checks whether every scope token in `requested` is contained in `allowed`.
+pub fn is_subset(requested: &[String], allowed: &[String]) -> bool {
+    requested.iter().all(|scope| allowed.contains(scope))
+}
+
+/// Returns the scope tokens of `requested` that are contained in `allowed`.
+pub fn intersection(requested: &[String], allowed: &[String]) -> Vec<String> {
+    requested
+        .iter()
+        .filter(|scope| allowed.contains(scope))
+        .cloned()
+        .collect()
+}
+
+/// Returns the scope tokens of `scopes`, followed by those of `additional` not already in it.
+pub fn union(scopes: &[String], additional: &[String]) -> Vec<String> {
+    let mut union = scopes.to_vec();
+    union.extend(
+        additional
+            .iter()
+            .filter(|scope| !scopes.contains(scope))
+            .cloned(),
+    );
+    union
+}
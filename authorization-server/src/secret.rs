@@ -0,0 +1,85 @@
+/// The `secret` module handles the credentials the Authorization Server issues or is configured
+/// with: it hashes client secrets and other issued credentials before they are stored, and
+/// compares presented credentials in constant time so that response times do not reveal how much
+/// of a guess was right.
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use rsa::rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+/// The prefix of hashed secrets, which tells them apart from secrets stored in plaintext by
+/// earlier versions.
+const HASH_PREFIX: &str = "$sha256$";
+
+/// The number of random bytes in the salt of a hashed secret.
+const SALT_LEN: usize = 16;
+
+/// The placeholder printed instead of secrets in `Debug` output.
+pub const REDACTED: &str = "[redacted]";
+
+fn digest(salt: &[u8], secret: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(salt);
+    hasher.update(secret.as_bytes());
+    hasher.finalize().into()
+}
+
+/// Hashes a secret with SHA-256 and a fresh random salt.
+///
+/// Client secrets are random values generated by the server, so a fast hash is enough: unlike
+/// passwords, they cannot be guessed from a dictionary.
+pub fn hash(secret: &str) -> String {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    format!(
+        "{HASH_PREFIX}{}${}",
+        URL_SAFE_NO_PAD.encode(salt),
+        URL_SAFE_NO_PAD.encode(digest(&salt, secret))
+    )
+}
+
+/// Hashes a secret with SHA-256 and no salt, for secrets that are looked up by their hash.
+///
+/// Like `hash`, this is only suitable for random values generated by the server.
+pub fn lookup_hash(secret: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(secret.as_bytes()))
+}
+
+/// Checks `secret` against the stored hash `stored` in constant time.
+///
+/// Secrets stored in plaintext by earlier versions are compared as they are.
+pub fn verify(secret: &str, stored: &str) -> bool {
+    let Some(encoded) = stored.strip_prefix(HASH_PREFIX) else {
+        return constant_time_eq(secret, stored);
+    };
+
+    let decoded = encoded.split_once('$').and_then(|(salt, hash)| {
+        Some((
+            URL_SAFE_NO_PAD.decode(salt).ok()?,
+            URL_SAFE_NO_PAD.decode(hash).ok()?,
+        ))
+    });
+    match decoded {
+        Some((salt, hash)) => digest(&salt, secret)[..].ct_eq(&hash).into(),
+        None => {
+            tracing::error!("Malformed secret hash");
+            false
+        }
+    }
+}
+
+/// Compares two secrets in constant time.
+///
+/// Both values are hashed first, so that the comparison does not reveal their lengths either.
+pub fn constant_time_eq(a: &str, b: &str) -> bool {
+    Sha256::digest(a.as_bytes())
+        .as_slice()
+        .ct_eq(Sha256::digest(b.as_bytes()).as_slice())
+        .into()
+}
+
+/// Replaces an optional secret with a placeholder, for `Debug` implementations.
+pub fn redact<T>(secret: &Option<T>) -> Option<&'static str> {
+    secret.as_ref().map(|_| REDACTED)
+}
@@ -0,0 +1,106 @@
+/// The `session` module keeps users logged in across authorization requests.
+/// After a successful login, the browser receives a session cookie; later requests to
+/// `/authorize` carrying a valid cookie skip the login form. A second cookie holds the anti-CSRF
+/// token that the login and consent forms must repeat when they are submitted.
+use crate::{AppState, now, secret, storage::StorageResult};
+use axum::http::{HeaderMap, header};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// The name of the session cookie.
+pub const SESSION_COOKIE: &str = "session";
+
+/// The name of the cookie holding the anti-CSRF token of the login and consent forms.
+pub const CSRF_COOKIE: &str = "csrf";
+
+/// Represents the login session of a user.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Session {
+    /// The ID of the logged in user.
+    pub user_id: String,
+    /// The time the user logged in (UNIX timestamp).
+    pub auth_time: u64,
+    /// The time the session expires (UNIX timestamp).
+    pub expires_at: u64,
+}
+
+/// Returns the value of the cookie `name` sent in the `Cookie` header, if any.
+fn cookie<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(cookie_name, _)| *cookie_name == name)
+        .map(|(_, value)| value)
+}
+
+/// Returns the session ID sent in the `Cookie` header, if any.
+pub fn session_id(headers: &HeaderMap) -> Option<&str> {
+    cookie(headers, SESSION_COOKIE)
+}
+
+/// Returns the unexpired session the request belongs to, if any.
+pub fn current_session(state: &AppState, headers: &HeaderMap) -> StorageResult<Option<Session>> {
+    let Some(session_id) = session_id(headers) else {
+        return Ok(None);
+    };
+    let session = state.storage.get_session(session_id)?;
+    Ok(session.filter(|session| session.expires_at >= now()))
+}
+
+/// Starts a session for `user_id` and returns its ID and record.
+pub fn create_session(state: &mut AppState, user_id: &str) -> StorageResult<(String, Session)> {
+    state.storage.remove_expired_sessions()?;
+
+    let session_id = Uuid::new_v4().to_string();
+    let auth_time = now();
+    let session = Session {
+        user_id: user_id.to_string(),
+        auth_time,
+        expires_at: auth_time + state.config.session_lifetime,
+    };
+    state.storage.put_session(&session_id, session.clone())?;
+    Ok((session_id, session))
+}
+
+/// Builds the `Set-Cookie` header value carrying `session_id`.
+///
+/// The cookie is out of reach of scripts, is not sent on cross-site subrequests, and is only sent
+/// over HTTPS when the issuer is an HTTPS URL.
+pub fn session_cookie(state: &AppState, session_id: &str) -> String {
+    let mut cookie = format!(
+        "{SESSION_COOKIE}={session_id}; Path=/; HttpOnly; SameSite=Lax; Max-Age={}",
+        state.config.session_lifetime
+    );
+    if state.config.issuer.starts_with("https://") {
+        cookie.push_str("; Secure");
+    }
+    cookie
+}
+
+/// Returns the anti-CSRF token of the browser, together with the `Set-Cookie` header value
+/// carrying a new token if the request did not send one.
+///
+/// The token lasts for the browser session, so that forms opened in several tabs stay valid.
+pub fn csrf_token(state: &AppState, headers: &HeaderMap) -> (String, Option<String>) {
+    if let Some(token) = cookie(headers, CSRF_COOKIE) {
+        return (token.to_string(), None);
+    }
+
+    let token = Uuid::new_v4().to_string();
+    let mut cookie = format!("{CSRF_COOKIE}={token}; Path=/authorize; HttpOnly; SameSite=Lax");
+    if state.config.issuer.starts_with("https://") {
+        cookie.push_str("; Secure");
+    }
+    (token, Some(cookie))
+}
+
+/// Checks that a submitted form carries the anti-CSRF token of the browser, in constant time.
+///
+/// Other sites can make the browser post a form, but cannot read the cookie to repeat its token.
+pub fn verify_csrf_token(headers: &HeaderMap, submitted: &str) -> bool {
+    cookie(headers, CSRF_COOKIE)
+        .is_some_and(|expected| secret::constant_time_eq(submitted, expected))
+}
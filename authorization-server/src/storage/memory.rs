@@ -0,0 +1,282 @@
+/// The `memory` module implements the storage traits with in-memory `HashMap`s.
+/// All data is lost when the server stops.
+use super::{
+    AccessTokenStore, AuthorizationCodeStore, ClientStore, ConsentStore, InitialAccessTokenStore,
+    RefreshTokenStore, SessionStore, SigningKeyStore, StorageResult, UserStore,
+};
+use crate::{
+    authorize::AuthorizationCode,
+    keys::StoredSigningKey,
+    now,
+    register::{InitialAccessToken, RegisteredClient},
+    session::Session,
+    token::{AccessToken, RefreshToken},
+    user::User,
+};
+use std::collections::{HashMap, HashSet};
+
+/// Represents a storage backend that keeps all data in memory.
+#[derive(Default)]
+pub struct MemoryStorage {
+    clients: HashMap<String, RegisteredClient>,
+    initial_access_tokens: HashMap<String, InitialAccessToken>,
+    authorization_codes: HashMap<String, AuthorizationCode>,
+    refresh_tokens: HashMap<String, RefreshToken>,
+    access_tokens: HashMap<String, AccessToken>,
+    consents: HashMap<(String, String), Vec<String>>,
+    users: HashMap<String, User>,
+    sessions: HashMap<String, Session>,
+    signing_keys: HashMap<String, StoredSigningKey>,
+}
+
+impl ClientStore for MemoryStorage {
+    fn get_client(&self, client_id: &str) -> StorageResult<Option<RegisteredClient>> {
+        Ok(self.clients.get(client_id).cloned())
+    }
+
+    fn put_client(&mut self, client_id: &str, client: RegisteredClient) -> StorageResult<()> {
+        self.clients.insert(client_id.to_string(), client);
+        Ok(())
+    }
+
+    fn remove_client(&mut self, client_id: &str) -> StorageResult<()> {
+        self.clients.remove(client_id);
+        Ok(())
+    }
+}
+
+impl InitialAccessTokenStore for MemoryStorage {
+    fn get_initial_access_token(
+        &self,
+        token_hash: &str,
+    ) -> StorageResult<Option<InitialAccessToken>> {
+        Ok(self.initial_access_tokens.get(token_hash).cloned())
+    }
+
+    fn put_initial_access_token(
+        &mut self,
+        token_hash: &str,
+        token: InitialAccessToken,
+    ) -> StorageResult<()> {
+        self.initial_access_tokens
+            .insert(token_hash.to_string(), token);
+        Ok(())
+    }
+
+    fn remove_initial_access_token(&mut self, token_hash: &str) -> StorageResult<()> {
+        self.initial_access_tokens.remove(token_hash);
+        Ok(())
+    }
+}
+
+impl AuthorizationCodeStore for MemoryStorage {
+    fn get_code(&self, code: &str) -> StorageResult<Option<AuthorizationCode>> {
+        Ok(self.authorization_codes.get(code).cloned())
+    }
+
+    fn put_code(&mut self, code: &str, authorization_code: AuthorizationCode) -> StorageResult<()> {
+        self.authorization_codes
+            .insert(code.to_string(), authorization_code);
+        Ok(())
+    }
+
+    fn remove_code(&mut self, code: &str) -> StorageResult<()> {
+        self.authorization_codes.remove(code);
+        Ok(())
+    }
+
+    fn redeem_code(&mut self, code: &str, refresh_token_family: &str) -> StorageResult<bool> {
+        match self.authorization_codes.get_mut(code) {
+            Some(authorization_code) if !authorization_code.redeemed => {
+                authorization_code.redeemed = true;
+                authorization_code.refresh_token_family = Some(refresh_token_family.to_string());
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn remove_expired_codes(&mut self, lifetime: u64) -> StorageResult<()> {
+        self.authorization_codes
+            .retain(|_, authorization_code| !authorization_code.is_expired(lifetime));
+        Ok(())
+    }
+}
+
+impl RefreshTokenStore for MemoryStorage {
+    fn get_refresh_token(&self, token: &str) -> StorageResult<Option<RefreshToken>> {
+        Ok(self.refresh_tokens.get(token).cloned())
+    }
+
+    fn put_refresh_token(&mut self, token: &str, refresh_token: RefreshToken) -> StorageResult<()> {
+        self.refresh_tokens.insert(token.to_string(), refresh_token);
+        Ok(())
+    }
+
+    fn rotate_refresh_token(&mut self, token: &str) -> StorageResult<bool> {
+        match self.refresh_tokens.get_mut(token) {
+            Some(refresh_token) if !refresh_token.rotated && !refresh_token.revoked => {
+                refresh_token.rotated = true;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn revoke_refresh_token_family(&mut self, family_id: &str) -> StorageResult<()> {
+        self.refresh_tokens
+            .values_mut()
+            .filter(|refresh_token| refresh_token.family_id == family_id)
+            .for_each(|refresh_token| refresh_token.revoked = true);
+        Ok(())
+    }
+
+    fn revoke_client_refresh_tokens(&mut self, client_id: &str) -> StorageResult<()> {
+        self.refresh_tokens
+            .values_mut()
+            .filter(|refresh_token| refresh_token.client_id == client_id)
+            .for_each(|refresh_token| refresh_token.revoked = true);
+        Ok(())
+    }
+
+    fn remove_expired_refresh_tokens(&mut self) -> StorageResult<()> {
+        let now = now();
+        let live_families: HashSet<String> = self
+            .refresh_tokens
+            .values()
+            .filter(|refresh_token| refresh_token.expires_at >= now)
+            .map(|refresh_token| refresh_token.family_id.clone())
+            .collect();
+        self.refresh_tokens
+            .retain(|_, refresh_token| live_families.contains(&refresh_token.family_id));
+        Ok(())
+    }
+}
+
+impl AccessTokenStore for MemoryStorage {
+    fn get_access_token(&self, jti: &str) -> StorageResult<Option<AccessToken>> {
+        Ok(self.access_tokens.get(jti).cloned())
+    }
+
+    fn put_access_token(&mut self, jti: &str, access_token: AccessToken) -> StorageResult<()> {
+        self.access_tokens.insert(jti.to_string(), access_token);
+        Ok(())
+    }
+
+    fn revoke_access_token_family(&mut self, family_id: &str) -> StorageResult<()> {
+        self.access_tokens
+            .values_mut()
+            .filter(|access_token| access_token.family_id.as_deref() == Some(family_id))
+            .for_each(|access_token| access_token.revoked = true);
+        Ok(())
+    }
+
+    fn revoke_client_access_tokens(&mut self, client_id: &str) -> StorageResult<()> {
+        self.access_tokens
+            .values_mut()
+            .filter(|access_token| access_token.client_id == client_id)
+            .for_each(|access_token| access_token.revoked = true);
+        Ok(())
+    }
+
+    fn revoked_access_tokens(&self) -> StorageResult<Vec<(String, AccessToken)>> {
+        let now = now();
+        Ok(self
+            .access_tokens
+            .iter()
+            .filter(|(_, access_token)| access_token.revoked && access_token.expires_at >= now)
+            .map(|(jti, access_token)| (jti.clone(), access_token.clone()))
+            .collect())
+    }
+
+    fn remove_expired_access_tokens(&mut self) -> StorageResult<()> {
+        let now = now();
+        self.access_tokens
+            .retain(|_, access_token| access_token.expires_at >= now);
+        Ok(())
+    }
+}
+
+impl ConsentStore for MemoryStorage {
+    fn get_consent(&self, user_id: &str, client_id: &str) -> StorageResult<Option<Vec<String>>> {
+        Ok(self
+            .consents
+            .get(&(user_id.to_string(), client_id.to_string()))
+            .cloned())
+    }
+
+    fn put_consent(
+        &mut self,
+        user_id: &str,
+        client_id: &str,
+        scopes: Vec<String>,
+    ) -> StorageResult<()> {
+        self.consents
+            .insert((user_id.to_string(), client_id.to_string()), scopes);
+        Ok(())
+    }
+
+    fn remove_client_consents(&mut self, client_id: &str) -> StorageResult<()> {
+        self.consents
+            .retain(|(_, consent_client_id), _| consent_client_id != client_id);
+        Ok(())
+    }
+}
+
+impl UserStore for MemoryStorage {
+    fn get_user(&self, user_id: &str) -> StorageResult<Option<User>> {
+        Ok(self.users.get(user_id).cloned())
+    }
+
+    fn get_user_by_username(&self, username: &str) -> StorageResult<Option<(String, User)>> {
+        Ok(self
+            .users
+            .iter()
+            .find(|(_, user)| user.username == username)
+            .map(|(user_id, user)| (user_id.clone(), user.clone())))
+    }
+
+    fn put_user(&mut self, user_id: &str, user: User) -> StorageResult<()> {
+        self.users.insert(user_id.to_string(), user);
+        Ok(())
+    }
+}
+
+impl SessionStore for MemoryStorage {
+    fn get_session(&self, session_id: &str) -> StorageResult<Option<Session>> {
+        Ok(self.sessions.get(session_id).cloned())
+    }
+
+    fn put_session(&mut self, session_id: &str, session: Session) -> StorageResult<()> {
+        self.sessions.insert(session_id.to_string(), session);
+        Ok(())
+    }
+
+    fn remove_expired_sessions(&mut self) -> StorageResult<()> {
+        let now = now();
+        self.sessions.retain(|_, session| session.expires_at >= now);
+        Ok(())
+    }
+}
+
+impl SigningKeyStore for MemoryStorage {
+    fn signing_keys(&self) -> StorageResult<Vec<(String, StoredSigningKey)>> {
+        let mut keys: Vec<_> = self
+            .signing_keys
+            .iter()
+            .map(|(kid, key)| (kid.clone(), key.clone()))
+            .collect();
+        keys.sort_by(|(a_kid, a), (b_kid, b)| (a.created_at, a_kid).cmp(&(b.created_at, b_kid)));
+        Ok(keys)
+    }
+
+    fn put_signing_key(&mut self, kid: &str, key: StoredSigningKey) -> StorageResult<()> {
+        self.signing_keys.insert(kid.to_string(), key);
+        Ok(())
+    }
+
+    fn remove_signing_key(&mut self, kid: &str) -> StorageResult<()> {
+        self.signing_keys.remove(kid);
+        Ok(())
+    }
+}
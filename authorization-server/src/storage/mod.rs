@@ -0,0 +1,212 @@
+/// The `storage` module defines how the Authorization Server persists its data.
+/// Each kind of record has its own storage trait, and a backend implements all of them.
+/// The in-memory backend is the default; the SQLite backend survives restarts and can be
+/// shared between replicas.
+use crate::{
+    authorize::AuthorizationCode,
+    config::Config,
+    keys::StoredSigningKey,
+    register::{InitialAccessToken, RegisteredClient},
+    session::Session,
+    token::{AccessToken, RefreshToken},
+    user::User,
+};
+use std::fmt;
+
+pub mod memory;
+pub mod sqlite;
+
+pub use memory::MemoryStorage;
+pub use sqlite::SqliteStorage;
+
+/// Represents a failure of the storage backend.
+#[derive(Debug)]
+pub struct StorageError(pub String);
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "storage error: {}", self.0)
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+/// The result type returned by the storage traits.
+pub type StorageResult<T> = Result<T, StorageError>;
+
+/// Stores registered clients.
+pub trait ClientStore {
+    /// Returns the client with the given `client_id`, if any.
+    fn get_client(&self, client_id: &str) -> StorageResult<Option<RegisteredClient>>;
+    /// Inserts or replaces the client with the given `client_id`.
+    fn put_client(&mut self, client_id: &str, client: RegisteredClient) -> StorageResult<()>;
+    /// Removes the client with the given `client_id`.
+    fn remove_client(&mut self, client_id: &str) -> StorageResult<()>;
+}
+
+/// Stores initial access tokens (RFC 7591, Section 3) with the limits they impose on the
+/// clients registered with them. Tokens are stored and looked up by their `secret::lookup_hash`.
+pub trait InitialAccessTokenStore {
+    /// Returns the initial access token hashed to `token_hash`, if it exists.
+    fn get_initial_access_token(
+        &self,
+        token_hash: &str,
+    ) -> StorageResult<Option<InitialAccessToken>>;
+    /// Inserts or replaces the initial access token hashed to `token_hash`.
+    fn put_initial_access_token(
+        &mut self,
+        token_hash: &str,
+        token: InitialAccessToken,
+    ) -> StorageResult<()>;
+    /// Removes the initial access token hashed to `token_hash`.
+    fn remove_initial_access_token(&mut self, token_hash: &str) -> StorageResult<()>;
+}
+
+/// Stores issued authorization codes.
+pub trait AuthorizationCodeStore {
+    /// Returns the authorization code record for `code`, if any.
+    fn get_code(&self, code: &str) -> StorageResult<Option<AuthorizationCode>>;
+    /// Inserts or replaces the authorization code record for `code`.
+    fn put_code(&mut self, code: &str, authorization_code: AuthorizationCode) -> StorageResult<()>;
+    /// Removes the authorization code record for `code`.
+    fn remove_code(&mut self, code: &str) -> StorageResult<()>;
+    /// Marks `code` as redeemed and records the family of the refresh tokens issued from it,
+    /// unless it was redeemed before. Returns whether this call redeemed the code.
+    ///
+    /// The check and the update are one atomic step, so that a code is only redeemed once,
+    /// even by replicas sharing the storage.
+    fn redeem_code(&mut self, code: &str, refresh_token_family: &str) -> StorageResult<bool>;
+    /// Removes every authorization code older than `lifetime` seconds.
+    fn remove_expired_codes(&mut self, lifetime: u64) -> StorageResult<()>;
+}
+
+/// Stores issued refresh tokens.
+pub trait RefreshTokenStore {
+    /// Returns the refresh token record for `token`, if any.
+    fn get_refresh_token(&self, token: &str) -> StorageResult<Option<RefreshToken>>;
+    /// Inserts or replaces the refresh token record for `token`.
+    fn put_refresh_token(&mut self, token: &str, refresh_token: RefreshToken) -> StorageResult<()>;
+    /// Marks `token` as rotated, unless it was rotated or revoked before. Returns whether this
+    /// call rotated the token.
+    ///
+    /// The check and the update are one atomic step, so that a token is only exchanged once,
+    /// even by replicas sharing the storage.
+    fn rotate_refresh_token(&mut self, token: &str) -> StorageResult<bool>;
+    /// Marks every refresh token of the given family as revoked.
+    fn revoke_refresh_token_family(&mut self, family_id: &str) -> StorageResult<()>;
+    /// Marks every refresh token issued to `client_id` as revoked.
+    fn revoke_client_refresh_tokens(&mut self, client_id: &str) -> StorageResult<()>;
+    /// Removes every refresh token family whose tokens have all expired.
+    ///
+    /// Rotated and revoked tokens are kept as long as their family has a live token, so that
+    /// their reuse is still detected.
+    fn remove_expired_refresh_tokens(&mut self) -> StorageResult<()>;
+}
+
+/// Stores issued access tokens by their `jti`, so that they can be revoked.
+pub trait AccessTokenStore {
+    /// Returns the access token record for `jti`, if any.
+    fn get_access_token(&self, jti: &str) -> StorageResult<Option<AccessToken>>;
+    /// Inserts or replaces the access token record for `jti`.
+    fn put_access_token(&mut self, jti: &str, access_token: AccessToken) -> StorageResult<()>;
+    /// Marks every access token of the given family as revoked.
+    fn revoke_access_token_family(&mut self, family_id: &str) -> StorageResult<()>;
+    /// Marks every access token issued to `client_id` as revoked.
+    fn revoke_client_access_tokens(&mut self, client_id: &str) -> StorageResult<()>;
+    /// Returns the `jti` and record of every revoked access token that has not expired yet.
+    fn revoked_access_tokens(&self) -> StorageResult<Vec<(String, AccessToken)>>;
+    /// Removes every access token record that has expired.
+    fn remove_expired_access_tokens(&mut self) -> StorageResult<()>;
+}
+
+/// Stores the scopes users have consented to grant to clients.
+pub trait ConsentStore {
+    /// Returns the scopes `user_id` has granted to `client_id`, if any.
+    fn get_consent(&self, user_id: &str, client_id: &str) -> StorageResult<Option<Vec<String>>>;
+    /// Records the scopes `user_id` has granted to `client_id`.
+    fn put_consent(
+        &mut self,
+        user_id: &str,
+        client_id: &str,
+        scopes: Vec<String>,
+    ) -> StorageResult<()>;
+    /// Removes the consents every user has given to `client_id`.
+    fn remove_client_consents(&mut self, client_id: &str) -> StorageResult<()>;
+}
+
+/// Stores the resource owners who can log in to the Authorization Server.
+pub trait UserStore {
+    /// Returns the user with the given `user_id`, if any.
+    fn get_user(&self, user_id: &str) -> StorageResult<Option<User>>;
+    /// Returns the ID and record of the user with the given `username`, if any.
+    fn get_user_by_username(&self, username: &str) -> StorageResult<Option<(String, User)>>;
+    /// Inserts or replaces the user with the given `user_id`.
+    fn put_user(&mut self, user_id: &str, user: User) -> StorageResult<()>;
+}
+
+/// Stores the login sessions of users, by the ID sent in the session cookie.
+pub trait SessionStore {
+    /// Returns the session with the given `session_id`, if any.
+    fn get_session(&self, session_id: &str) -> StorageResult<Option<Session>>;
+    /// Inserts or replaces the session with the given `session_id`.
+    fn put_session(&mut self, session_id: &str, session: Session) -> StorageResult<()>;
+    /// Removes every session that has expired.
+    fn remove_expired_sessions(&mut self) -> StorageResult<()>;
+}
+
+/// Stores the signing keys created by key rotations, so that they survive restarts and every
+/// replica signs with the same key.
+pub trait SigningKeyStore {
+    /// Returns the key ID and record of every stored signing key, oldest first.
+    fn signing_keys(&self) -> StorageResult<Vec<(String, StoredSigningKey)>>;
+    /// Inserts or replaces the signing key with the given `kid`.
+    fn put_signing_key(&mut self, kid: &str, key: StoredSigningKey) -> StorageResult<()>;
+    /// Removes the signing key with the given `kid`.
+    fn remove_signing_key(&mut self, kid: &str) -> StorageResult<()>;
+}
+
+/// A storage backend implementing every storage trait.
+pub trait Storage:
+    ClientStore
+    + InitialAccessTokenStore
+    + AuthorizationCodeStore
+    + RefreshTokenStore
+    + AccessTokenStore
+    + ConsentStore
+    + UserStore
+    + SessionStore
+    + SigningKeyStore
+    + Send
+{
+}
+
+impl<T> Storage for T where
+    T: ClientStore
+        + InitialAccessTokenStore
+        + AuthorizationCodeStore
+        + RefreshTokenStore
+        + AccessTokenStore
+        + ConsentStore
+        + UserStore
+        + SessionStore
+        + SigningKeyStore
+        + Send
+{
+}
+
+/// Creates the storage backend selected by the configuration.
+///
+/// # Panics
+/// Panics if the SQLite database cannot be opened, since the server cannot run without it.
+pub fn from_config(config: &Config) -> Box<dyn Storage> {
+    match &config.sqlite_path {
+        Some(path) => {
+            tracing::info!("Using SQLite storage at {}", path);
+            Box::new(SqliteStorage::open(path).expect("Failed to open SQLite database"))
+        }
+        None => {
+            tracing::info!("Using in-memory storage");
+            Box::new(MemoryStorage::default())
+        }
+    }
+}
@@ -0,0 +1,489 @@
+/// The `sqlite` module implements the storage traits on top of a SQLite database.
+/// Records are stored as JSON documents next to the columns needed to query them.
+use super::{
+    AccessTokenStore, AuthorizationCodeStore, ClientStore, ConsentStore, InitialAccessTokenStore,
+    RefreshTokenStore, SessionStore, SigningKeyStore, StorageError, StorageResult, UserStore,
+};
+use crate::{
+    authorize::AuthorizationCode,
+    keys::StoredSigningKey,
+    now,
+    register::{InitialAccessToken, RegisteredClient},
+    session::Session,
+    token::{AccessToken, RefreshToken},
+    user::User,
+};
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Serialize, de::DeserializeOwned};
+use std::path::Path;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS clients (
+        client_id TEXT PRIMARY KEY,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS initial_access_tokens (
+        token TEXT PRIMARY KEY,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS authorization_codes (
+        code TEXT PRIMARY KEY,
+        issued_at INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS refresh_tokens (
+        token TEXT PRIMARY KEY,
+        family_id TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS refresh_tokens_family_id ON refresh_tokens (family_id);
+    CREATE TABLE IF NOT EXISTS access_tokens (
+        jti TEXT PRIMARY KEY,
+        family_id TEXT,
+        expires_at INTEGER NOT NULL,
+        revoked INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS access_tokens_family_id ON access_tokens (family_id);
+    CREATE TABLE IF NOT EXISTS consents (
+        user_id TEXT NOT NULL,
+        client_id TEXT NOT NULL,
+        data TEXT NOT NULL,
+        PRIMARY KEY (user_id, client_id)
+    );
+    CREATE TABLE IF NOT EXISTS users (
+        user_id TEXT PRIMARY KEY,
+        username TEXT NOT NULL UNIQUE,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS sessions (
+        session_id TEXT PRIMARY KEY,
+        expires_at INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS signing_keys (
+        kid TEXT PRIMARY KEY,
+        created_at INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+";
+
+impl From<rusqlite::Error> for StorageError {
+    fn from(err: rusqlite::Error) -> Self {
+        StorageError(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for StorageError {
+    fn from(err: serde_json::Error) -> Self {
+        StorageError(err.to_string())
+    }
+}
+
+/// Represents a storage backend that persists all data in a SQLite database.
+pub struct SqliteStorage {
+    connection: Connection,
+}
+
+impl SqliteStorage {
+    /// Opens (and if needed creates) the SQLite database at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> StorageResult<Self> {
+        let connection = Connection::open(path)?;
+        // Wait for locks held by other replicas instead of failing immediately
+        connection.busy_timeout(std::time::Duration::from_secs(5))?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection })
+    }
+
+    /// Runs `query` and deserializes the JSON document it returns, if any.
+    fn get<T: DeserializeOwned>(
+        &self,
+        query: &str,
+        key: impl rusqlite::Params,
+    ) -> StorageResult<Option<T>> {
+        let data: Option<String> = self
+            .connection
+            .query_row(query, key, |row| row.get(0))
+            .optional()?;
+        Ok(data.map(|data| serde_json::from_str(&data)).transpose()?)
+    }
+}
+
+/// Serializes a record to its JSON document.
+fn to_json<T: Serialize>(value: &T) -> StorageResult<String> {
+    Ok(serde_json::to_string(value)?)
+}
+
+impl ClientStore for SqliteStorage {
+    fn get_client(&self, client_id: &str) -> StorageResult<Option<RegisteredClient>> {
+        self.get(
+            "SELECT data FROM clients WHERE client_id = ?1",
+            params![client_id],
+        )
+    }
+
+    fn put_client(&mut self, client_id: &str, client: RegisteredClient) -> StorageResult<()> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO clients (client_id, data) VALUES (?1, ?2)",
+            params![client_id, to_json(&client)?],
+        )?;
+        Ok(())
+    }
+
+    fn remove_client(&mut self, client_id: &str) -> StorageResult<()> {
+        self.connection.execute(
+            "DELETE FROM clients WHERE client_id = ?1",
+            params![client_id],
+        )?;
+        Ok(())
+    }
+}
+
+impl InitialAccessTokenStore for SqliteStorage {
+    fn get_initial_access_token(
+        &self,
+        token_hash: &str,
+    ) -> StorageResult<Option<InitialAccessToken>> {
+        self.get(
+            "SELECT data FROM initial_access_tokens WHERE token = ?1",
+            params![token_hash],
+        )
+    }
+
+    fn put_initial_access_token(
+        &mut self,
+        token_hash: &str,
+        token: InitialAccessToken,
+    ) -> StorageResult<()> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO initial_access_tokens (token, data) VALUES (?1, ?2)",
+            params![token_hash, to_json(&token)?],
+        )?;
+        Ok(())
+    }
+
+    fn remove_initial_access_token(&mut self, token_hash: &str) -> StorageResult<()> {
+        self.connection.execute(
+            "DELETE FROM initial_access_tokens WHERE token = ?1",
+            params![token_hash],
+        )?;
+        Ok(())
+    }
+}
+
+impl AuthorizationCodeStore for SqliteStorage {
+    fn get_code(&self, code: &str) -> StorageResult<Option<AuthorizationCode>> {
+        self.get(
+            "SELECT data FROM authorization_codes WHERE code = ?1",
+            params![code],
+        )
+    }
+
+    fn put_code(&mut self, code: &str, authorization_code: AuthorizationCode) -> StorageResult<()> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO authorization_codes (code, issued_at, data) VALUES (?1, ?2, ?3)",
+            params![
+                code,
+                authorization_code.issued_at,
+                to_json(&authorization_code)?
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn remove_code(&mut self, code: &str) -> StorageResult<()> {
+        self.connection.execute(
+            "DELETE FROM authorization_codes WHERE code = ?1",
+            params![code],
+        )?;
+        Ok(())
+    }
+
+    fn redeem_code(&mut self, code: &str, refresh_token_family: &str) -> StorageResult<bool> {
+        // A conditional update, so that only one of several concurrent redemptions wins
+        let updated = self.connection.execute(
+            "UPDATE authorization_codes
+             SET data = json_set(data, '$.redeemed', json('true'), '$.refresh_token_family', ?2)
+             WHERE code = ?1 AND json_extract(data, '$.redeemed') = 0",
+            params![code, refresh_token_family],
+        )?;
+        Ok(updated == 1)
+    }
+
+    fn remove_expired_codes(&mut self, lifetime: u64) -> StorageResult<()> {
+        self.connection.execute(
+            "DELETE FROM authorization_codes WHERE issued_at + ?1 < ?2",
+            params![lifetime, now()],
+        )?;
+        Ok(())
+    }
+}
+
+impl RefreshTokenStore for SqliteStorage {
+    fn get_refresh_token(&self, token: &str) -> StorageResult<Option<RefreshToken>> {
+        self.get(
+            "SELECT data FROM refresh_tokens WHERE token = ?1",
+            params![token],
+        )
+    }
+
+    fn put_refresh_token(&mut self, token: &str, refresh_token: RefreshToken) -> StorageResult<()> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO refresh_tokens (token, family_id, data) VALUES (?1, ?2, ?3)",
+            params![token, refresh_token.family_id, to_json(&refresh_token)?],
+        )?;
+        Ok(())
+    }
+
+    fn rotate_refresh_token(&mut self, token: &str) -> StorageResult<bool> {
+        // A conditional update, so that only one of several concurrent rotations wins
+        let updated = self.connection.execute(
+            "UPDATE refresh_tokens SET data = json_set(data, '$.rotated', json('true'))
+             WHERE token = ?1
+               AND json_extract(data, '$.rotated') = 0
+               AND json_extract(data, '$.revoked') = 0",
+            params![token],
+        )?;
+        Ok(updated == 1)
+    }
+
+    fn revoke_refresh_token_family(&mut self, family_id: &str) -> StorageResult<()> {
+        let transaction = self.connection.transaction()?;
+        {
+            let mut select = transaction
+                .prepare("SELECT token, data FROM refresh_tokens WHERE family_id = ?1")?;
+            let rows = select
+                .query_map(params![family_id], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            for (token, data) in rows {
+                let mut refresh_token: RefreshToken = serde_json::from_str(&data)?;
+                refresh_token.revoked = true;
+                transaction.execute(
+                    "UPDATE refresh_tokens SET data = ?2 WHERE token = ?1",
+                    params![token, to_json(&refresh_token)?],
+                )?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn revoke_client_refresh_tokens(&mut self, client_id: &str) -> StorageResult<()> {
+        self.connection.execute(
+            "UPDATE refresh_tokens SET data = json_set(data, '$.revoked', json('true'))
+             WHERE json_extract(data, '$.client_id') = ?1",
+            params![client_id],
+        )?;
+        Ok(())
+    }
+
+    fn remove_expired_refresh_tokens(&mut self) -> StorageResult<()> {
+        self.connection.execute(
+            "DELETE FROM refresh_tokens WHERE family_id IN (
+                 SELECT family_id FROM refresh_tokens
+                 GROUP BY family_id
+                 HAVING MAX(json_extract(data, '$.expires_at')) < ?1
+             )",
+            params![now()],
+        )?;
+        Ok(())
+    }
+}
+
+impl AccessTokenStore for SqliteStorage {
+    fn get_access_token(&self, jti: &str) -> StorageResult<Option<AccessToken>> {
+        self.get(
+            "SELECT data FROM access_tokens WHERE jti = ?1",
+            params![jti],
+        )
+    }
+
+    fn put_access_token(&mut self, jti: &str, access_token: AccessToken) -> StorageResult<()> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO access_tokens (jti, family_id, expires_at, revoked, data)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                jti,
+                access_token.family_id,
+                access_token.expires_at,
+                access_token.revoked,
+                to_json(&access_token)?
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn revoke_access_token_family(&mut self, family_id: &str) -> StorageResult<()> {
+        let transaction = self.connection.transaction()?;
+        {
+            let mut select =
+                transaction.prepare("SELECT jti, data FROM access_tokens WHERE family_id = ?1")?;
+            let rows = select
+                .query_map(params![family_id], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            for (jti, data) in rows {
+                let mut access_token: AccessToken = serde_json::from_str(&data)?;
+                access_token.revoked = true;
+                transaction.execute(
+                    "UPDATE access_tokens SET revoked = 1, data = ?2 WHERE jti = ?1",
+                    params![jti, to_json(&access_token)?],
+                )?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn revoke_client_access_tokens(&mut self, client_id: &str) -> StorageResult<()> {
+        self.connection.execute(
+            "UPDATE access_tokens SET revoked = 1, data = json_set(data, '$.revoked', json('true'))
+             WHERE json_extract(data, '$.client_id') = ?1",
+            params![client_id],
+        )?;
+        Ok(())
+    }
+
+    fn revoked_access_tokens(&self) -> StorageResult<Vec<(String, AccessToken)>> {
+        let mut select = self.connection.prepare(
+            "SELECT jti, data FROM access_tokens WHERE revoked = 1 AND expires_at >= ?1",
+        )?;
+        let rows = select
+            .query_map(params![now()], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        rows.into_iter()
+            .map(|(jti, data)| Ok((jti, serde_json::from_str(&data)?)))
+            .collect()
+    }
+
+    fn remove_expired_access_tokens(&mut self) -> StorageResult<()> {
+        self.connection.execute(
+            "DELETE FROM access_tokens WHERE expires_at < ?1",
+            params![now()],
+        )?;
+        Ok(())
+    }
+}
+
+impl ConsentStore for SqliteStorage {
+    fn get_consent(&self, user_id: &str, client_id: &str) -> StorageResult<Option<Vec<String>>> {
+        self.get(
+            "SELECT data FROM consents WHERE user_id = ?1 AND client_id = ?2",
+            params![user_id, client_id],
+        )
+    }
+
+    fn put_consent(
+        &mut self,
+        user_id: &str,
+        client_id: &str,
+        scopes: Vec<String>,
+    ) -> StorageResult<()> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO consents (user_id, client_id, data) VALUES (?1, ?2, ?3)",
+            params![user_id, client_id, to_json(&scopes)?],
+        )?;
+        Ok(())
+    }
+
+    fn remove_client_consents(&mut self, client_id: &str) -> StorageResult<()> {
+        self.connection.execute(
+            "DELETE FROM consents WHERE client_id = ?1",
+            params![client_id],
+        )?;
+        Ok(())
+    }
+}
+
+impl UserStore for SqliteStorage {
+    fn get_user(&self, user_id: &str) -> StorageResult<Option<User>> {
+        self.get(
+            "SELECT data FROM users WHERE user_id = ?1",
+            params![user_id],
+        )
+    }
+
+    fn get_user_by_username(&self, username: &str) -> StorageResult<Option<(String, User)>> {
+        let row: Option<(String, String)> = self
+            .connection
+            .query_row(
+                "SELECT user_id, data FROM users WHERE username = ?1",
+                params![username],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        Ok(row
+            .map(|(user_id, data)| serde_json::from_str(&data).map(|user| (user_id, user)))
+            .transpose()?)
+    }
+
+    fn put_user(&mut self, user_id: &str, user: User) -> StorageResult<()> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO users (user_id, username, data) VALUES (?1, ?2, ?3)",
+            params![user_id, user.username, to_json(&user)?],
+        )?;
+        Ok(())
+    }
+}
+
+impl SessionStore for SqliteStorage {
+    fn get_session(&self, session_id: &str) -> StorageResult<Option<Session>> {
+        self.get(
+            "SELECT data FROM sessions WHERE session_id = ?1",
+            params![session_id],
+        )
+    }
+
+    fn put_session(&mut self, session_id: &str, session: Session) -> StorageResult<()> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO sessions (session_id, expires_at, data) VALUES (?1, ?2, ?3)",
+            params![session_id, session.expires_at, to_json(&session)?],
+        )?;
+        Ok(())
+    }
+
+    fn remove_expired_sessions(&mut self) -> StorageResult<()> {
+        self.connection
+            .execute("DELETE FROM sessions WHERE expires_at < ?1", params![now()])?;
+        Ok(())
+    }
+}
+
+impl SigningKeyStore for SqliteStorage {
+    fn signing_keys(&self) -> StorageResult<Vec<(String, StoredSigningKey)>> {
+        let mut select = self
+            .connection
+            .prepare("SELECT kid, data FROM signing_keys ORDER BY created_at, kid")?;
+        let rows = select
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        rows.into_iter()
+            .map(|(kid, data)| Ok((kid, serde_json::from_str(&data)?)))
+            .collect()
+    }
+
+    fn put_signing_key(&mut self, kid: &str, key: StoredSigningKey) -> StorageResult<()> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO signing_keys (kid, created_at, data) VALUES (?1, ?2, ?3)",
+            params![kid, key.created_at, to_json(&key)?],
+        )?;
+        Ok(())
+    }
+
+    fn remove_signing_key(&mut self, kid: &str) -> StorageResult<()> {
+        self.connection
+            .execute("DELETE FROM signing_keys WHERE kid = ?1", params![kid])?;
+        Ok(())
+    }
+}
@@ -1,30 +1,61 @@
 /// The `token` module handles the `/token` endpoint of the Authorization Server.
-/// This endpoint is responsible for exchanging authorization codes for access tokens.
-use crate::SharedAppState;
+/// This endpoint is responsible for issuing access tokens for authorization codes, refresh tokens
+/// and client credentials.
+use crate::{
+    AppState, SharedAppState, id_token, now, register::RegisteredClient, resource, scope,
+    secret::redact, storage::StorageError,
+};
 use axum::{
     extract::{Form, State},
+    http::StatusCode,
     response::Json,
 };
-use dotenvy::dotenv;
-use jsonwebtoken::{EncodingKey, Header, encode};
+use jsonwebtoken::{Algorithm, Header, Validation, decode, decode_header, encode};
 use serde::{Deserialize, Serialize};
-use std::{
-    env, fs,
-    time::{SystemTime, UNIX_EPOCH},
-};
-use tracing;
+use std::{fmt, time::Duration};
+use uuid::Uuid;
+
+/// The lifetime of issued access tokens in seconds.
+pub(crate) const ACCESS_TOKEN_LIFETIME: u64 = 3600;
 
 /// Represents the request body for the `/token` endpoint.
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize)]
 pub struct TokenRequest {
-    /// The grant type (e.g., "authorization_code").
+    /// The grant type ("authorization_code", "refresh_token" or "client_credentials").
     pub grant_type: String,
     /// The authorization code issued by the `/authorize` endpoint.
     pub code: Option<String>,
+    /// The redirect URI sent to the `/authorize` endpoint, if any.
+    pub redirect_uri: Option<String>,
+    /// The refresh token issued by a previous token response.
+    pub refresh_token: Option<String>,
     /// The client ID of the requesting client.
     pub client_id: String,
     /// The client secret (optional for public clients).
     pub client_secret: Option<String>,
+    /// The PKCE code verifier matching the code challenge sent to `/authorize` (optional).
+    pub code_verifier: Option<String>,
+    /// The requested scope (optional, used by the refresh token and client credentials grants).
+    pub scope: Option<String>,
+    /// The resource the access token is requested for (RFC 8707, optional).
+    pub resource: Option<String>,
+}
+
+/// Redacts the credentials of the request, so that it can be logged.
+impl fmt::Debug for TokenRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenRequest")
+            .field("grant_type", &self.grant_type)
+            .field("code", &redact(&self.code))
+            .field("redirect_uri", &self.redirect_uri)
+            .field("refresh_token", &redact(&self.refresh_token))
+            .field("client_id", &self.client_id)
+            .field("client_secret", &redact(&self.client_secret))
+            .field("code_verifier", &redact(&self.code_verifier))
+            .field("scope", &self.scope)
+            .field("resource", &self.resource)
+            .finish()
+    }
 }
 
 /// Represents a successful token response.
@@ -36,6 +67,15 @@ pub struct TokenResponse {
     pub token_type: String,
     /// The expiration time of the token in seconds.
     pub expires_in: u64,
+    /// The refresh token that can be used to obtain a new access token.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    /// The granted scope, included when it differs from the requested scope.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    /// The OpenID Connect ID token, included when the `openid` scope was granted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_token: Option<String>,
 }
 
 /// Represents an error response for the `/token` endpoint.
@@ -43,23 +83,105 @@ pub struct TokenResponse {
 pub struct TokenErrorResponse {
     /// The error message.
     pub error: String,
+    /// A human-readable description of the error.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_description: Option<String>,
+    /// A URI identifying a web page with information about the error.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_uri: Option<String>,
+}
+
+/// Represents an issued refresh token.
+///
+/// Refresh tokens are rotated on every use. All tokens descending from the same authorization
+/// share a `family_id`, so that the whole family can be revoked when reuse is detected.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RefreshToken {
+    /// The client ID the token was issued to.
+    pub client_id: String,
+    /// The subject the token was issued for.
+    pub sub: String,
+    /// The scope granted to the token.
+    pub scope: String,
+    /// The identifier shared by all refresh tokens descending from the same authorization.
+    pub family_id: String,
+    /// The resource the access tokens issued from the token are intended for.
+    #[serde(default)]
+    pub resource: Option<String>,
+    /// The time the token was issued (UNIX timestamp).
+    #[serde(default)]
+    pub issued_at: u64,
+    /// The expiration time of the token (UNIX timestamp).
+    pub expires_at: u64,
+    /// Whether the token has already been exchanged for a new one.
+    pub rotated: bool,
+    /// Whether the token has been revoked.
+    pub revoked: bool,
+}
+
+/// Represents an issued access token, recorded under its `jti`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AccessToken {
+    /// The client ID the token was issued to.
+    pub client_id: String,
+    /// The subject the token was issued for.
+    pub sub: String,
+    /// The family of the refresh token issued alongside the token, if any.
+    pub family_id: Option<String>,
+    /// The expiration time of the token (UNIX timestamp).
+    pub expires_at: u64,
+    /// Whether the token has been revoked.
+    pub revoked: bool,
 }
 
 /// Represents the claims included in the JWT access token.
-#[derive(Serialize)]
-struct Claims {
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Claims {
+    /// The issuer identifier of the Authorization Server.
+    pub iss: String,
     /// The subject (e.g., client ID).
-    sub: String,
+    pub sub: String,
+    /// The audience, i.e. the resource the token is intended for.
+    pub aud: String,
+    /// The client ID the token was issued to.
+    pub client_id: String,
+    /// The unique identifier of the token.
+    pub jti: String,
+    /// The time the token was issued (UNIX timestamp).
+    pub iat: u64,
+    /// The time before which the token must not be accepted (UNIX timestamp).
+    pub nbf: u64,
     /// The expiration time of the token (UNIX timestamp).
-    exp: u64,
+    pub exp: u64,
     /// The scope of the token.
-    scope: String,
+    pub scope: String,
+}
+
+/// The error type returned by the `/token` endpoint.
+pub(crate) type TokenError = (StatusCode, Json<TokenErrorResponse>);
+
+/// Builds an error response for the `/token` endpoint.
+pub(crate) fn error_response(status: StatusCode, error: &str) -> TokenError {
+    (
+        status,
+        Json(TokenErrorResponse {
+            error: error.to_string(),
+            error_description: None,
+            error_uri: None,
+        }),
+    )
+}
+
+/// Logs a storage failure and maps it to a `server_error` response.
+pub(crate) fn server_error(err: StorageError) -> TokenError {
+    tracing::error!("Storage error: {}", err);
+    error_response(StatusCode::INTERNAL_SERVER_ERROR, "server_error")
 }
 
 /// Handles the `/token` endpoint.
 ///
-/// This function validates the authorization code, client credentials, and grant type,
-/// and issues a signed JWT as the access token.
+/// This function validates the client credentials and the grant, and issues a signed JWT as the
+/// access token together with a refresh token where applicable.
 ///
 /// # Arguments
 /// - `State(app_state)`: Shared application state.
@@ -72,85 +194,606 @@ struct Claims {
 pub async fn token(
     State(app_state): State<SharedAppState>,
     Form(payload): Form<TokenRequest>,
-) -> Result<Json<TokenResponse>, Json<TokenErrorResponse>> {
+) -> Result<Json<TokenResponse>, TokenError> {
     tracing::info!("Received token request: {:?}", payload);
 
-    dotenv().ok(); // Load environment variables from .env
+    let mut state = app_state.lock().unwrap();
+
+    match payload.grant_type.as_str() {
+        "authorization_code" => authorization_code_grant(&mut state, &payload),
+        "refresh_token" => refresh_token_grant(&mut state, &payload),
+        "client_credentials" => client_credentials_grant(&mut state, &payload),
+        _ => {
+            tracing::warn!("Unsupported grant_type: {}", payload.grant_type);
+            Err(error_response(
+                StatusCode::BAD_REQUEST,
+                "unsupported_grant_type",
+            ))
+        }
+    }
+    .map(Json)
+}
+
+/// Exchanges an authorization code for an access token and a refresh token.
+///
+/// Presenting a code that has already been redeemed indicates that it was intercepted,
+/// in which case the tokens issued from it are revoked.
+fn authorization_code_grant(
+    state: &mut AppState,
+    payload: &TokenRequest,
+) -> Result<TokenResponse, TokenError> {
+    let client = authenticate_client(state, &payload.client_id, payload.client_secret.as_deref())?;
+    check_grant_type(&client, &payload.grant_type)?;
+
+    let code = payload.code.as_deref().unwrap_or_default();
+    let lifetime = state.config.authorization_code_lifetime;
 
-    // Load the private key from the `unsafe-private.pem` file
-    let private_key =
-        fs::read_to_string("unsafe-private.pem").expect("Failed to read unsafe-private.pem");
-    let encoding_key = EncodingKey::from_rsa_pem(private_key.as_bytes())
-        .expect("Failed to create encoding key from private key");
+    // Check if the authorization code exists in the storage
+    let Some(authorization_code) = state.storage.get_code(code).map_err(server_error)? else {
+        tracing::warn!("Invalid authorization code");
+        return Err(error_response(StatusCode::BAD_REQUEST, "invalid_grant"));
+    };
 
-    if payload.grant_type != "authorization_code" {
-        tracing::warn!("Unsupported grant_type: {}", payload.grant_type);
-        return Err(Json(TokenErrorResponse {
-            error: "unsupported_grant_type".to_string(),
-        }));
+    // The code must have been issued to the authenticated client
+    if authorization_code.client_id != payload.client_id {
+        tracing::warn!(
+            "Authorization code was not issued to client_id: {}",
+            payload.client_id
+        );
+        return Err(error_response(StatusCode::BAD_REQUEST, "invalid_grant"));
     }
 
-    let mut state = app_state.lock().unwrap();
+    if authorization_code.redeemed {
+        return reject_code_replay(state, code, &payload.client_id);
+    }
+
+    if authorization_code.is_expired(lifetime) {
+        tracing::warn!("Expired authorization code");
+        state.storage.remove_code(code).map_err(server_error)?;
+        return Err(error_response(StatusCode::BAD_REQUEST, "invalid_grant"));
+    }
 
-    // Check if the authorization code exists in the state
-    if let Some(client_id) = state
-        .authorization_state
-        .remove(&payload.code.clone().unwrap_or_default())
+    // RFC 6749, Section 4.1.3: the redirect_uri must be repeated if it was sent to /authorize
+    if authorization_code.redirect_uri.is_some()
+        && authorization_code.redirect_uri != payload.redirect_uri
     {
-        tracing::info!("Authorization code validated for client_id: {}", client_id);
-
-        // Validate the client_id
-        if let Some((stored_client_secret, _)) = state.client_registry.get(&client_id) {
-            // If the client is confidential, validate the client_secret
-            if let Some(client_secret) = &payload.client_secret {
-                if client_secret != stored_client_secret {
-                    tracing::warn!("Invalid client_secret for client_id: {}", client_id);
-                    return Err(Json(TokenErrorResponse {
-                        error: "invalid_client".to_string(),
-                    }));
-                }
-            } else if !stored_client_secret.is_empty() {
-                // Confidential client must provide a client_secret
-                tracing::warn!(
-                    "Missing client_secret for confidential client_id: {}",
-                    client_id
-                );
-                return Err(Json(TokenErrorResponse {
-                    error: "invalid_client".to_string(),
-                }));
-            }
+        tracing::warn!("Mismatching redirect_uri: {:?}", payload.redirect_uri);
+        return Err(error_response(StatusCode::BAD_REQUEST, "invalid_grant"));
+    }
+
+    tracing::info!(
+        "Authorization code validated for client_id: {}",
+        payload.client_id
+    );
+
+    // If the code is bound to a PKCE code challenge, validate the code_verifier
+    let pkce_valid = match (&authorization_code.code_challenge, &payload.code_verifier) {
+        (Some(code_challenge), Some(code_verifier)) => code_challenge.verify(code_verifier),
+        (None, None) => true,
+        _ => false,
+    };
+    if !pkce_valid {
+        tracing::warn!(
+            "PKCE verification failed for client_id: {}",
+            payload.client_id
+        );
+        return Err(error_response(StatusCode::BAD_REQUEST, "invalid_grant"));
+    }
+
+    // The code is bound to the resource requested at /authorize, which may be repeated here
+    let audience = match &authorization_code.resource {
+        Some(resource) => resource.clone(),
+        None => state.config.default_resource.clone(),
+    };
+    if payload
+        .resource
+        .as_ref()
+        .is_some_and(|resource| *resource != audience)
+    {
+        tracing::warn!(
+            "Resource differs from authorization: {:?}",
+            payload.resource
+        );
+        return Err(error_response(StatusCode::BAD_REQUEST, "invalid_target"));
+    }
+
+    // Only a valid exchange redeems the code, so that a request with a wrong redirect_uri or
+    // code_verifier cannot burn it. From here on it cannot be presented again, even to other
+    // replicas.
+    let family_id = Uuid::new_v4().to_string();
+    if !state
+        .storage
+        .redeem_code(code, &family_id)
+        .map_err(server_error)?
+    {
+        return reject_code_replay(state, code, &payload.client_id);
+    }
+
+    let sub = authorization_code.sub.clone();
+    let granted_scope = scope::join(&authorization_code.scopes);
+    let access_token = issue_access_token(
+        state,
+        &payload.client_id,
+        &sub,
+        &granted_scope,
+        &audience,
+        Some(&family_id),
+    )?;
+    // Refresh tokens are only issued to clients registered for the refresh token grant
+    let refresh_token = if client.allows_grant_type("refresh_token") {
+        Some(issue_refresh_token(
+            state,
+            &payload.client_id,
+            &sub,
+            &granted_scope,
+            &audience,
+            family_id,
+        )?)
+    } else {
+        None
+    };
+
+    // OIDC Core, Section 3.1.3.3: authentication requests are answered with an ID token
+    let id_token = if authorization_code
+        .scopes
+        .iter()
+        .any(|scope| scope == id_token::OPENID_SCOPE)
+    {
+        Some(id_token::issue_id_token(
+            state,
+            &payload.client_id,
+            &sub,
+            authorization_code.auth_time,
+            authorization_code.nonce.as_deref(),
+            &access_token,
+        )?)
+    } else {
+        None
+    };
+
+    Ok(TokenResponse {
+        access_token,
+        token_type: "Bearer".to_string(),
+        expires_in: ACCESS_TOKEN_LIFETIME,
+        refresh_token,
+        scope: response_scope(
+            authorization_code.requested_scope.as_deref(),
+            &authorization_code.scopes,
+        ),
+        id_token,
+    })
+}
+
+/// Rejects a replayed authorization code and revokes the tokens issued from it, since the
+/// code was most likely intercepted (RFC 6749, Section 4.1.2).
+fn reject_code_replay(
+    state: &mut AppState,
+    code: &str,
+    client_id: &str,
+) -> Result<TokenResponse, TokenError> {
+    tracing::warn!(
+        "Authorization code replay detected for client_id: {}",
+        client_id
+    );
+    if let Some(mut authorization_code) = state.storage.get_code(code).map_err(server_error)?
+        && let Some(family_id) = authorization_code.refresh_token_family.take()
+    {
+        revoke_token_family(state, &family_id)?;
+        state
+            .storage
+            .put_code(code, authorization_code)
+            .map_err(server_error)?;
+    }
+    Err(error_response(StatusCode::BAD_REQUEST, "invalid_grant"))
+}
+
+/// Exchanges a refresh token for a new access token and a new (rotated) refresh token.
+///
+/// Presenting a refresh token that has already been rotated indicates that it was leaked,
+/// in which case every refresh token of its family is revoked.
+fn refresh_token_grant(
+    state: &mut AppState,
+    payload: &TokenRequest,
+) -> Result<TokenResponse, TokenError> {
+    let client = authenticate_client(state, &payload.client_id, payload.client_secret.as_deref())?;
+    check_grant_type(&client, &payload.grant_type)?;
+
+    let presented = payload.refresh_token.as_deref().unwrap_or_default();
+    let Some(refresh_token) = state
+        .storage
+        .get_refresh_token(presented)
+        .map_err(server_error)?
+    else {
+        tracing::warn!("Unknown refresh token");
+        return Err(error_response(StatusCode::BAD_REQUEST, "invalid_grant"));
+    };
+
+    if refresh_token.client_id != payload.client_id {
+        tracing::warn!(
+            "Refresh token was not issued to client_id: {}",
+            payload.client_id
+        );
+        return Err(error_response(StatusCode::BAD_REQUEST, "invalid_grant"));
+    }
+
+    if refresh_token.revoked {
+        tracing::warn!("Revoked refresh token presented");
+        return Err(error_response(StatusCode::BAD_REQUEST, "invalid_grant"));
+    }
+
+    if refresh_token.rotated {
+        return reject_refresh_token_reuse(state, &refresh_token.family_id);
+    }
+
+    if refresh_token.expires_at < now() {
+        tracing::warn!("Expired refresh token presented");
+        return Err(error_response(StatusCode::BAD_REQUEST, "invalid_grant"));
+    }
+
+    if let Some(requested) = &payload.scope
+        && !scope::is_subset(
+            &scope::parse(requested),
+            &scope::parse(&refresh_token.scope),
+        )
+    {
+        tracing::warn!("Requested scope exceeds refresh token scope: {}", requested);
+        return Err(error_response(StatusCode::BAD_REQUEST, "invalid_scope"));
+    }
+
+    let audience = match &refresh_token.resource {
+        Some(resource) => resource.clone(),
+        None => state.config.default_resource.clone(),
+    };
+    if payload
+        .resource
+        .as_ref()
+        .is_some_and(|resource| *resource != audience)
+    {
+        tracing::warn!(
+            "Resource differs from authorization: {:?}",
+            payload.resource
+        );
+        return Err(error_response(StatusCode::BAD_REQUEST, "invalid_target"));
+    }
+
+    // Rotate the refresh token; losing a concurrent rotation counts as reuse
+    if !state
+        .storage
+        .rotate_refresh_token(presented)
+        .map_err(server_error)?
+    {
+        return reject_refresh_token_reuse(state, &refresh_token.family_id);
+    }
+    let RefreshToken {
+        client_id,
+        sub,
+        scope,
+        family_id,
+        ..
+    } = refresh_token;
+
+    // The access token may be issued with a narrower scope than the refresh token,
+    // while the rotated refresh token keeps the originally granted scope
+    let scopes = match &payload.scope {
+        Some(requested) => scope::parse(requested),
+        None => scope::parse(&scope),
+    };
+    let access_token = issue_access_token(
+        state,
+        &client_id,
+        &sub,
+        &scope::join(&scopes),
+        &audience,
+        Some(&family_id),
+    )?;
+    let refresh_token = issue_refresh_token(state, &client_id, &sub, &scope, &audience, family_id)?;
 
-            // Generate a signed JWT as the access token
-            let expiration = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs()
-                + 3600; // Token expires in 1 hour
+    tracing::info!("Rotated refresh token for client_id: {}", client_id);
 
-            let claims = Claims {
-                sub: client_id.clone(),
-                exp: expiration,
-                scope: "read".to_string(), // Example scope
-            };
+    Ok(TokenResponse {
+        access_token,
+        token_type: "Bearer".to_string(),
+        expires_in: ACCESS_TOKEN_LIFETIME,
+        refresh_token: Some(refresh_token),
+        scope: response_scope(payload.scope.as_deref(), &scopes),
+        id_token: None,
+    })
+}
+
+/// Rejects a refresh token that was already rotated and revokes its whole family, since the
+/// token was most likely leaked.
+fn reject_refresh_token_reuse(
+    state: &mut AppState,
+    family_id: &str,
+) -> Result<TokenResponse, TokenError> {
+    tracing::warn!(
+        "Refresh token reuse detected, revoking token family: {}",
+        family_id
+    );
+    revoke_token_family(state, family_id)?;
+    Err(error_response(StatusCode::BAD_REQUEST, "invalid_grant"))
+}
+
+/// Issues an access token to a confidential client acting on its own behalf.
+///
+/// The token's subject is the client itself, and the requested scope must be within the
+/// scopes the client was registered for. No refresh token is issued.
+fn client_credentials_grant(
+    state: &mut AppState,
+    payload: &TokenRequest,
+) -> Result<TokenResponse, TokenError> {
+    let client = authenticate_client(state, &payload.client_id, payload.client_secret.as_deref())?;
+
+    // Public clients cannot authenticate, so they cannot use this grant
+    if client.is_public() {
+        tracing::warn!(
+            "Public client_id may not use client_credentials: {}",
+            payload.client_id
+        );
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            "unauthorized_client",
+        ));
+    }
+    check_grant_type(&client, &payload.grant_type)?;
 
-            // Explicitly set the algorithm to RS256
-            let mut header = Header::new(jsonwebtoken::Algorithm::RS256);
-            header.typ = Some("JWT".to_string());
+    // Grant the requested scope, or every registered scope if none was requested
+    let scopes = match &payload.scope {
+        Some(requested) => scope::parse(requested),
+        None => client.scopes.clone(),
+    };
+    if !scope::is_subset(&scopes, &client.scopes) {
+        tracing::warn!(
+            "Requested scope {:?} exceeds registered scope for client_id: {}",
+            payload.scope,
+            payload.client_id
+        );
+        return Err(error_response(StatusCode::BAD_REQUEST, "invalid_scope"));
+    }
 
-            let token = encode(&header, &claims, &encoding_key).unwrap();
+    let Some(audience) = resource::audience(&state.config, payload.resource.as_deref()) else {
+        tracing::warn!("Unknown resource: {:?}", payload.resource);
+        return Err(error_response(StatusCode::BAD_REQUEST, "invalid_target"));
+    };
 
-            tracing::info!("Generated access token for client_id: {}", client_id);
+    let access_token = issue_access_token(
+        state,
+        &payload.client_id,
+        &payload.client_id,
+        &scope::join(&scopes),
+        &audience,
+        None,
+    )?;
 
-            return Ok(Json(TokenResponse {
-                access_token: token,
-                token_type: "Bearer".to_string(),
-                expires_in: 3600,
-            }));
+    Ok(TokenResponse {
+        access_token,
+        token_type: "Bearer".to_string(),
+        expires_in: ACCESS_TOKEN_LIFETIME,
+        refresh_token: None,
+        scope: response_scope(payload.scope.as_deref(), &scopes),
+        id_token: None,
+    })
+}
+
+/// Validates the credentials a client presented and returns the authenticated client.
+pub(crate) fn authenticate_client(
+    state: &AppState,
+    client_id: &str,
+    client_secret: Option<&str>,
+) -> Result<RegisteredClient, TokenError> {
+    let Some(client) = state.storage.get_client(client_id).map_err(server_error)? else {
+        tracing::warn!("Unknown client_id: {}", client_id);
+        return Err(error_response(StatusCode::UNAUTHORIZED, "invalid_client"));
+    };
+
+    match (client.is_public(), client_secret) {
+        // Confidential client must provide a matching client_secret
+        (false, Some(client_secret)) => {
+            if !client.verify_secret(client_secret) {
+                tracing::warn!("Invalid client_secret for client_id: {}", client_id);
+                return Err(error_response(StatusCode::UNAUTHORIZED, "invalid_client"));
+            }
         }
+        (false, None) => {
+            tracing::warn!(
+                "Missing client_secret for confidential client_id: {}",
+                client_id
+            );
+            return Err(error_response(StatusCode::UNAUTHORIZED, "invalid_client"));
+        }
+        // Public clients have no secret to present
+        (true, Some(_)) => {
+            tracing::warn!(
+                "Unexpected client_secret for public client_id: {}",
+                client_id
+            );
+            return Err(error_response(StatusCode::UNAUTHORIZED, "invalid_client"));
+        }
+        (true, None) => {}
+    }
+
+    Ok(client)
+}
+
+/// Rejects grant types the client did not register for (RFC 7591, Section 2).
+fn check_grant_type(client: &RegisteredClient, grant_type: &str) -> Result<(), TokenError> {
+    if !client.allows_grant_type(grant_type) {
+        tracing::warn!("Client is not registered for grant_type: {}", grant_type);
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            "unauthorized_client",
+        ));
     }
+    Ok(())
+}
+
+/// Returns the scope to include in a token response: the granted scope if it differs from the
+/// requested one (RFC 6749, Section 5.1), `None` otherwise.
+fn response_scope(requested: Option<&str>, granted: &[String]) -> Option<String> {
+    let unchanged = requested.is_some_and(|requested| scope::parse(requested) == granted);
+    (!unchanged).then(|| scope::join(granted))
+}
+
+/// Generates a signed JWT as the access token, intended for `audience`.
+///
+/// The token is recorded under its `jti`, together with the family of the refresh token issued
+/// alongside it (if any), so that it can be revoked before it expires.
+fn issue_access_token(
+    state: &mut AppState,
+    client_id: &str,
+    sub: &str,
+    scope: &str,
+    audience: &str,
+    family_id: Option<&str>,
+) -> Result<String, TokenError> {
+    let iat = now();
+    let claims = Claims {
+        iss: state.config.issuer.trim_end_matches('/').to_string(),
+        sub: sub.to_string(),
+        aud: audience.to_string(),
+        client_id: client_id.to_string(),
+        jti: Uuid::new_v4().to_string(),
+        iat,
+        nbf: iat,
+        exp: iat + ACCESS_TOKEN_LIFETIME,
+        scope: scope.to_string(),
+    };
+
+    // RFC 9068, Section 2.1: profiled access tokens are typed, so they cannot be confused with
+    // other JWTs such as ID tokens
+    let typ = if state.config.jwt_access_token_profile {
+        "at+jwt"
+    } else {
+        "JWT"
+    };
+    let token = sign_jwt(state, typ, &claims)?;
+
+    state
+        .storage
+        .put_access_token(
+            &claims.jti,
+            AccessToken {
+                client_id: claims.client_id,
+                sub: claims.sub,
+                family_id: family_id.map(String::from),
+                expires_at: claims.exp,
+                revoked: false,
+            },
+        )
+        .map_err(server_error)?;
+
+    tracing::info!("Generated access token for subject: {}", sub);
+
+    Ok(token)
+}
+
+/// Signs `claims` as a JWT of type `typ` with the active signing key.
+pub(crate) fn sign_jwt<T: Serialize>(
+    state: &AppState,
+    typ: &str,
+    claims: &T,
+) -> Result<String, TokenError> {
+    let Some(signing_key) = state.keys.active() else {
+        tracing::error!("No signing key available");
+        return Err(error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "server_error",
+        ));
+    };
+
+    // Explicitly set the algorithm to RS256
+    let mut header = Header::new(jsonwebtoken::Algorithm::RS256);
+    header.typ = Some(typ.to_string());
+    header.kid = Some(signing_key.kid.clone());
 
-    tracing::warn!("Invalid authorization code or client_id");
-    Err(Json(TokenErrorResponse {
-        error: "invalid_grant".to_string(),
-    }))
+    encode(&header, claims, signing_key.encoding_key()).map_err(|err| {
+        tracing::error!("Failed to sign JWT: {}", err);
+        error_response(StatusCode::INTERNAL_SERVER_ERROR, "server_error")
+    })
+}
+
+/// Generates an opaque refresh token and stores it in the storage.
+fn issue_refresh_token(
+    state: &mut AppState,
+    client_id: &str,
+    sub: &str,
+    scope: &str,
+    resource: &str,
+    family_id: String,
+) -> Result<String, TokenError> {
+    let token = Uuid::new_v4().to_string();
+    let issued_at = now();
+    let expires_at = issued_at + state.config.refresh_token_lifetime;
+
+    state
+        .storage
+        .remove_expired_refresh_tokens()
+        .map_err(server_error)?;
+    state
+        .storage
+        .put_refresh_token(
+            &token,
+            RefreshToken {
+                client_id: client_id.to_string(),
+                sub: sub.to_string(),
+                scope: scope.to_string(),
+                family_id,
+                resource: Some(resource.to_string()),
+                issued_at,
+                expires_at,
+                rotated: false,
+                revoked: false,
+            },
+        )
+        .map_err(server_error)?;
+
+    Ok(token)
+}
+
+/// Revokes every refresh token and access token descending from the same authorization.
+pub(crate) fn revoke_token_family(state: &mut AppState, family_id: &str) -> Result<(), TokenError> {
+    state
+        .storage
+        .revoke_refresh_token_family(family_id)
+        .map_err(server_error)?;
+    state
+        .storage
+        .revoke_access_token_family(family_id)
+        .map_err(server_error)
+}
+
+/// Verifies the signature of a JWT access token issued by this server and returns its claims.
+///
+/// Expired tokens are rejected unless `allow_expired` is set.
+pub(crate) fn decode_access_token(
+    state: &AppState,
+    token: &str,
+    allow_expired: bool,
+) -> Option<Claims> {
+    let kid = decode_header(token).ok()?.kid?;
+    let key = state.keys.find(&kid)?;
+
+    let mut validation = Validation::new(Algorithm::RS256);
+    validation.validate_exp = !allow_expired;
+    validation.set_issuer(&[state.config.issuer.trim_end_matches('/')]);
+
+    decode::<Claims>(token, &key.decoding_key(), &validation)
+        .map_err(|err| tracing::info!("Presented access token is invalid: {}", err))
+        .ok()
+        .map(|data| data.claims)
+}
+
+/// Spawns a task that removes the expired access tokens from the storage every `interval`, so
+/// that the records kept to revoke them do not pile up.
+pub fn spawn_access_token_purge(app_state: SharedAppState, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let mut state = app_state.lock().unwrap();
+            if let Err(err) = state.storage.remove_expired_access_tokens() {
+                tracing::error!("Failed to remove expired access tokens: {}", err);
+            }
+        }
+    });
 }
@@ -0,0 +1,65 @@
+/// The `user` module manages the resource owners who log in to the Authorization Server.
+/// Passwords are never stored; only their argon2 hashes are.
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+};
+use rsa::rand_core::OsRng;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// Represents a user who can log in to authorize clients.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct User {
+    /// The name the user logs in with.
+    pub username: String,
+    /// The argon2 hash of the user's password, in PHC string format.
+    pub password_hash: String,
+}
+
+impl User {
+    /// Creates a user, hashing `password` with a fresh random salt.
+    ///
+    /// Hashing is deliberately slow, so call this outside of the application state lock.
+    pub fn new(username: &str, password: &str) -> Result<Self, argon2::password_hash::Error> {
+        Ok(Self {
+            username: username.to_string(),
+            password_hash: hash_password(password)?,
+        })
+    }
+}
+
+/// Hashes a password with argon2 and a fresh random salt.
+pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    Ok(Argon2::default()
+        .hash_password(password.as_bytes(), &salt)?
+        .to_string())
+}
+
+/// Checks `password` against the hash of `user`'s password.
+///
+/// When there is no such user, the password is checked against a dummy hash anyway, so that the
+/// response time does not reveal which usernames exist.
+pub fn verify_password(user: Option<&User>, password: &str) -> bool {
+    static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+
+    let password_hash = match user {
+        Some(user) => user.password_hash.as_str(),
+        None => DUMMY_HASH.get_or_init(|| {
+            hash_password("dummy password").expect("Failed to hash the dummy password")
+        }),
+    };
+
+    let verified = PasswordHash::new(password_hash)
+        .map(|hash| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok()
+        })
+        .unwrap_or_else(|err| {
+            tracing::error!("Malformed password hash: {}", err);
+            false
+        });
+    verified && user.is_some()
+}
@@ -0,0 +1,80 @@
+/// The `userinfo` module handles the `/userinfo` endpoint of the Authorization Server
+/// (OIDC Core, Section 5.3). It returns claims about the user an access token was issued for,
+/// provided the token was granted the `openid` scope.
+use crate::{SharedAppState, id_token::OPENID_SCOPE, scope, token::decode_access_token};
+use axum::{
+    extract::State,
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Json, Response},
+};
+use serde::Serialize;
+
+/// Represents the response of the `/userinfo` endpoint.
+#[derive(Serialize)]
+pub struct UserInfo {
+    /// The subject, i.e. the user the access token was issued for.
+    pub sub: String,
+}
+
+/// Builds a bearer token error response (RFC 6750, Section 3).
+fn error_response(status: StatusCode, error: &str) -> Response {
+    let mut response = status.into_response();
+    let challenge = format!(r#"Bearer error="{error}""#);
+    if let Ok(value) = HeaderValue::from_str(&challenge) {
+        response
+            .headers_mut()
+            .insert(header::WWW_AUTHENTICATE, value);
+    }
+    response
+}
+
+/// Handles the `/userinfo` endpoint.
+///
+/// The access token is sent in the `Authorization` header. It must be valid, unrevoked and carry
+/// the `openid` scope.
+///
+/// # Returns
+/// - `Json<UserInfo>`: The claims about the user.
+/// - `Response`: A `401 Unauthorized` or `403 Forbidden` response with a bearer token challenge.
+#[axum_macros::debug_handler]
+pub async fn userinfo(
+    State(app_state): State<SharedAppState>,
+    headers: HeaderMap,
+) -> Result<Json<UserInfo>, Response> {
+    let Some(token) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        tracing::warn!("UserInfo request without a bearer token");
+        return Err(error_response(StatusCode::UNAUTHORIZED, "invalid_request"));
+    };
+
+    let state = app_state.lock().unwrap();
+
+    let Some(claims) = decode_access_token(&state, token, false) else {
+        return Err(error_response(StatusCode::UNAUTHORIZED, "invalid_token"));
+    };
+
+    let revoked = match state.storage.get_access_token(&claims.jti) {
+        Ok(access_token) => access_token.is_some_and(|access_token| access_token.revoked),
+        Err(err) => {
+            tracing::error!("Storage error: {}", err);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response());
+        }
+    };
+    if revoked {
+        tracing::warn!("Revoked access token presented: {}", claims.jti);
+        return Err(error_response(StatusCode::UNAUTHORIZED, "invalid_token"));
+    }
+
+    if !scope::parse(&claims.scope)
+        .iter()
+        .any(|scope| scope == OPENID_SCOPE)
+    {
+        tracing::warn!("Access token lacks the openid scope: {}", claims.jti);
+        return Err(error_response(StatusCode::FORBIDDEN, "insufficient_scope"));
+    }
+
+    Ok(Json(UserInfo { sub: claims.sub }))
+}
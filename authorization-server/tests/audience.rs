@@ -0,0 +1,208 @@
+mod common;
+
+use authorization_server::{config::Config, router::router_with_state};
+use axum::{Router, http::StatusCode};
+use serde_json::{Value, json};
+
+const API: &str = "https://api.example";
+const OTHER_API: &str = "https://other-api.example";
+
+fn app() -> Router {
+    router_with_state(common::app_state_with(Config {
+        issuer: "https://issuer.example/".to_string(),
+        resources: format!("{API} {OTHER_API}"),
+        default_resource: API.to_string(),
+        ..common::test_config()
+    }))
+}
+
+/// Returns the metadata of a client using every grant that issues access tokens.
+fn metadata() -> Value {
+    json!({ "grant_types": ["authorization_code", "refresh_token", "client_credentials"] })
+}
+
+/// Requests an authorization code, optionally for `resource`, and returns the code or the
+/// error the client was redirected with.
+async fn authorize(
+    app: &Router,
+    client_id: &str,
+    resource: Option<&str>,
+) -> Result<String, String> {
+    let mut uri = format!("/authorize?client_id={client_id}&response_type=code");
+    if let Some(resource) = resource {
+        uri.push_str(&format!("&resource={resource}"));
+    }
+    let response = common::authorize(app, &uri).await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    common::redirect_param(&response, "code")
+        .ok_or_else(|| common::redirect_param(&response, "error").unwrap())
+}
+
+#[tokio::test]
+async fn test_access_token_carries_registered_claims() {
+    let app = app();
+    let (client_id, client_secret) = common::register_client(&app, metadata()).await;
+    let code = authorize(&app, &client_id, None).await.unwrap();
+
+    let (status, tokens) =
+        common::exchange_code(&app, &client_id, &client_secret, &code, &[]).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let claims = common::jwt_claims(tokens["access_token"].as_str().unwrap());
+    assert_eq!(claims["iss"], "https://issuer.example");
+    assert_eq!(claims["aud"], API);
+    assert_eq!(claims["client_id"], client_id);
+    assert!(claims["jti"].is_string());
+    assert_eq!(claims["nbf"], claims["iat"]);
+    assert_eq!(
+        claims["exp"].as_u64().unwrap(),
+        claims["iat"].as_u64().unwrap() + 3600
+    );
+}
+
+#[tokio::test]
+async fn test_requested_resource_becomes_the_audience() {
+    let app = app();
+    let (client_id, client_secret) = common::register_client(&app, metadata()).await;
+    let code = authorize(&app, &client_id, Some(OTHER_API)).await.unwrap();
+
+    // The token request may only repeat the resource the code was issued for
+    let (status, error) = common::exchange_code(
+        &app,
+        &client_id,
+        &client_secret,
+        &code,
+        &[("resource", API)],
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["error"], "invalid_target");
+
+    let code = authorize(&app, &client_id, Some(OTHER_API)).await.unwrap();
+    let (status, tokens) = common::exchange_code(
+        &app,
+        &client_id,
+        &client_secret,
+        &code,
+        &[("resource", OTHER_API)],
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let claims = common::jwt_claims(tokens["access_token"].as_str().unwrap());
+    assert_eq!(claims["aud"], OTHER_API);
+
+    // Refreshed access tokens keep the audience of the authorization
+    let response = common::post_form(
+        &app,
+        "/token",
+        &[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", tokens["refresh_token"].as_str().unwrap()),
+            ("client_id", &client_id),
+            ("client_secret", &client_secret),
+        ],
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let tokens = common::body_json(response).await;
+    let claims = common::jwt_claims(tokens["access_token"].as_str().unwrap());
+    assert_eq!(claims["aud"], OTHER_API);
+}
+
+#[tokio::test]
+async fn test_unknown_resource_is_rejected() {
+    let app = app();
+    let (client_id, client_secret) = common::register_client(&app, metadata()).await;
+
+    let error = authorize(&app, &client_id, Some("https://unknown.example")).await;
+    assert_eq!(error.unwrap_err(), "invalid_target");
+
+    let response = common::post_form(
+        &app,
+        "/token",
+        &[
+            ("grant_type", "client_credentials"),
+            ("client_id", &client_id),
+            ("client_secret", &client_secret),
+            ("resource", "https://unknown.example"),
+        ],
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(common::body_json(response).await["error"], "invalid_target");
+}
+
+#[tokio::test]
+async fn test_client_credentials_token_for_requested_resource() {
+    let app = app();
+    let (client_id, client_secret) = common::register_client(&app, metadata()).await;
+
+    let response = common::post_form(
+        &app,
+        "/token",
+        &[
+            ("grant_type", "client_credentials"),
+            ("client_id", &client_id),
+            ("client_secret", &client_secret),
+            ("resource", OTHER_API),
+        ],
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let tokens = common::body_json(response).await;
+    let access_token = tokens["access_token"].as_str().unwrap();
+    assert_eq!(common::jwt_claims(access_token)["aud"], OTHER_API);
+
+    // Introspection reports the audience
+    let response = common::post_form(
+        &app,
+        "/introspect",
+        &[
+            ("token", access_token),
+            ("client_id", &client_id),
+            ("client_secret", &client_secret),
+        ],
+    )
+    .await;
+    assert_eq!(common::body_json(response).await["aud"], OTHER_API);
+}
+
+#[tokio::test]
+async fn test_jwt_access_token_profile_types_tokens() {
+    let app = router_with_state(common::app_state_with(Config {
+        jwt_access_token_profile: true,
+        ..common::test_config()
+    }));
+    let (client_id, client_secret) = common::register_client(&app, metadata()).await;
+
+    let response = common::post_form(
+        &app,
+        "/token",
+        &[
+            ("grant_type", "client_credentials"),
+            ("client_id", &client_id),
+            ("client_secret", &client_secret),
+        ],
+    )
+    .await;
+    let tokens = common::body_json(response).await;
+    let access_token = tokens["access_token"].as_str().unwrap();
+
+    let header = jsonwebtoken::decode_header(access_token).unwrap();
+    assert_eq!(header.typ.as_deref(), Some("at+jwt"));
+
+    // RFC 9068, Section 2.2: the claims every profiled access token carries
+    let claims = common::jwt_claims(access_token);
+    for claim in [
+        "iss",
+        "exp",
+        "aud",
+        "sub",
+        "client_id",
+        "iat",
+        "jti",
+        "scope",
+    ] {
+        assert!(claims.get(claim).is_some(), "missing claim: {claim}");
+    }
+}
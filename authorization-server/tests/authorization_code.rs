@@ -0,0 +1,201 @@
+mod common;
+
+use authorization_server::{SharedAppState, router::router_with_state};
+use axum::{Router, http::StatusCode};
+use serde_json::json;
+
+const REDIRECT_URI: &str = "http://localhost/callback";
+
+/// Registers a confidential client and requests an authorization code for it.
+async fn client_with_code(app: &Router) -> (String, String, String) {
+    let (client_id, client_secret) = common::register_client(app, json!({})).await;
+    let response = common::authorize(
+        app,
+        &format!("/authorize?client_id={client_id}&response_type=code&redirect_uri={REDIRECT_URI}"),
+    )
+    .await;
+    let code = common::redirect_param(&response, "code").unwrap();
+
+    (client_id, client_secret, code)
+}
+
+#[tokio::test]
+async fn test_code_stores_authorization_request() {
+    let state: SharedAppState = common::app_state();
+    let app = router_with_state(state.clone());
+    let registration = common::register(
+        &app,
+        json!({
+            "client_name": "Test Client",
+            "redirect_uris": [REDIRECT_URI],
+            "scope": "read write"
+        }),
+    )
+    .await;
+    let client_id = registration["client_id"].as_str().unwrap();
+
+    let response = common::authorize(
+        &app,
+        &format!(
+            "/authorize?client_id={client_id}&response_type=code&redirect_uri={REDIRECT_URI}&scope=write&state=xyz"
+        ),
+    )
+    .await;
+    let code = common::redirect_param(&response, "code").unwrap();
+
+    let state = state.lock().unwrap();
+    let authorization_code = state.storage.get_code(&code).unwrap().unwrap();
+    assert_eq!(authorization_code.client_id, client_id);
+    assert_eq!(
+        authorization_code.redirect_uri.as_deref(),
+        Some(REDIRECT_URI)
+    );
+    assert_eq!(authorization_code.scopes, vec!["write".to_string()]);
+    assert_eq!(authorization_code.state.as_deref(), Some("xyz"));
+    assert!(!authorization_code.redeemed);
+}
+
+#[tokio::test]
+async fn test_expired_code_is_rejected() {
+    let state: SharedAppState = common::app_state();
+    let app = router_with_state(state.clone());
+    let (client_id, client_secret, code) = client_with_code(&app).await;
+
+    // Age the code beyond the configured lifetime
+    {
+        let mut state = state.lock().unwrap();
+        let lifetime = state.config.authorization_code_lifetime;
+        let mut authorization_code = state.storage.get_code(&code).unwrap().unwrap();
+        authorization_code.issued_at -= lifetime + 1;
+        state.storage.put_code(&code, authorization_code).unwrap();
+    }
+
+    let (status, error) = common::exchange_code(
+        &app,
+        &client_id,
+        &client_secret,
+        &code,
+        &[("redirect_uri", REDIRECT_URI)],
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["error"], "invalid_grant");
+}
+
+#[tokio::test]
+async fn test_redirect_uri_must_match() {
+    let app = router_with_state(common::app_state());
+
+    let (client_id, client_secret, code) = client_with_code(&app).await;
+    let (status, error) = common::exchange_code(&app, &client_id, &client_secret, &code, &[]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["error"], "invalid_grant");
+
+    let (client_id, client_secret, code) = client_with_code(&app).await;
+    let (status, error) = common::exchange_code(
+        &app,
+        &client_id,
+        &client_secret,
+        &code,
+        &[("redirect_uri", "http://localhost/other")],
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["error"], "invalid_grant");
+}
+
+#[tokio::test]
+async fn test_failed_exchange_does_not_burn_code() {
+    let app = router_with_state(common::app_state());
+    let (client_id, client_secret, code) = client_with_code(&app).await;
+
+    for extra in [&[][..], &[("redirect_uri", "http://localhost/other")]] {
+        let (status, error) =
+            common::exchange_code(&app, &client_id, &client_secret, &code, extra).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error["error"], "invalid_grant");
+    }
+
+    // The legitimate exchange still succeeds and is not treated as a replay
+    let (status, tokens) = common::exchange_code(
+        &app,
+        &client_id,
+        &client_secret,
+        &code,
+        &[("redirect_uri", REDIRECT_URI)],
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(tokens["refresh_token"].is_string());
+}
+
+#[tokio::test]
+async fn test_code_is_bound_to_client() {
+    let app = router_with_state(common::app_state());
+    let (_, _, code) = client_with_code(&app).await;
+    let (other_client_id, other_client_secret, _) = client_with_code(&app).await;
+
+    let (status, error) = common::exchange_code(
+        &app,
+        &other_client_id,
+        &other_client_secret,
+        &code,
+        &[("redirect_uri", REDIRECT_URI)],
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["error"], "invalid_grant");
+}
+
+#[tokio::test]
+async fn test_replayed_code_revokes_issued_tokens() {
+    let state = common::app_state();
+    let app = router_with_state(state.clone());
+    let (client_id, client_secret, code) = client_with_code(&app).await;
+
+    let (status, tokens) = common::exchange_code(
+        &app,
+        &client_id,
+        &client_secret,
+        &code,
+        &[("redirect_uri", REDIRECT_URI)],
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let refresh_token = tokens["refresh_token"].as_str().unwrap();
+
+    let (status, error) = common::exchange_code(
+        &app,
+        &client_id,
+        &client_secret,
+        &code,
+        &[("redirect_uri", REDIRECT_URI)],
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["error"], "invalid_grant");
+
+    // The refresh token issued from the replayed code no longer works
+    let response = common::post_form(
+        &app,
+        "/token",
+        &[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+            ("client_id", &client_id),
+            ("client_secret", &client_secret),
+        ],
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(common::body_json(response).await["error"], "invalid_grant");
+
+    // Neither does the access token, which is now on the denylist
+    let access_token = tokens["access_token"].as_str().unwrap();
+    let claims = common::jwt_claims(access_token);
+    let (resource_server_id, resource_server_secret) =
+        common::register_resource_server(&state, &app).await;
+    let response = common::revoked_tokens(&app, &resource_server_id, &resource_server_secret).await;
+    let revoked = common::body_json(response).await;
+    assert_eq!(revoked["revoked"][0]["jti"], claims["jti"]);
+}
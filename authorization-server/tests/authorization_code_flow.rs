@@ -1,12 +1,12 @@
-use std::{
-    collections::HashMap,
-    sync::{Arc, Mutex},
-};
+mod common;
 
 mod tests {
     use super::*;
     use authorization_server::{
-        AppState, SharedAppState, authorize::authorize, register::register_client, token::token,
+        SharedAppState,
+        authorize::{authorize, submit_form},
+        register::register_client,
+        token::token,
     };
     use axum::{
         Router,
@@ -19,14 +19,14 @@ mod tests {
     #[tokio::test]
     async fn test_authorization_code_flow() {
         // Create the shared state
-        let state: SharedAppState = Arc::new(Mutex::new(AppState {
-            authorization_state: HashMap::new(),
-            client_registry: HashMap::new(),
-        }));
+        let state: SharedAppState = common::app_state();
 
         // Build the application
         let app = Router::new()
-            .route("/authorize", axum::routing::get(authorize))
+            .route(
+                "/authorize",
+                axum::routing::get(authorize).post(submit_form),
+            )
             .route("/token", axum::routing::post(token))
             .route("/register", axum::routing::post(register_client))
             .with_state(state.clone());
@@ -47,7 +47,7 @@ mod tests {
             .unwrap();
 
         let response = app.clone().oneshot(registration_request).await.unwrap();
-        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.status(), StatusCode::CREATED);
 
         let body = axum::body::to_bytes(response.into_body(), usize::MAX)
             .await
@@ -57,15 +57,58 @@ mod tests {
         let client_id = registration_response["client_id"].as_str().unwrap();
         let client_secret = registration_response["client_secret"].as_str().unwrap();
 
-        // Step 2: Simulate an /authorize request
-        let authorize_request = Request::builder()
-            .uri(format!(
+        // Step 2: Simulate an /authorize request, which shows the login form
+        let (csrf_cookie, csrf_token) = common::csrf(
+            &app,
+            &format!(
                 "/authorize?client_id={client_id}&response_type=code&state=xyz&redirect_uri=http://localhost/callback"
-            ))
-            .body(Body::empty())
+            ),
+        )
+        .await
+        .unwrap();
+
+        // Step 2b: Log in on the login form
+        let login_request_body = format!(
+            "client_id={client_id}&response_type=code&state=xyz&redirect_uri=http://localhost/callback&username={}&password={}&csrf_token={csrf_token}",
+            common::USERNAME,
+            common::PASSWORD.replace(' ', "+"),
+        );
+
+        let login_request = Request::builder()
+            .method("POST")
+            .uri("/authorize")
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .header("Cookie", &csrf_cookie)
+            .body(Body::from(login_request_body))
+            .unwrap();
+
+        let response = app.clone().oneshot(login_request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let session_cookie = response
+            .headers()
+            .get("set-cookie")
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .split(';')
+            .next()
+            .unwrap()
+            .to_string();
+
+        // Step 2c: Allow the client on the consent screen
+        let consent_request_body = format!(
+            "client_id={client_id}&response_type=code&state=xyz&redirect_uri=http://localhost/callback&consent=allow&csrf_token={csrf_token}"
+        );
+
+        let consent_request = Request::builder()
+            .method("POST")
+            .uri("/authorize")
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .header("Cookie", format!("{csrf_cookie}; {session_cookie}"))
+            .body(Body::from(consent_request_body))
             .unwrap();
 
-        let response = app.clone().oneshot(authorize_request).await.unwrap();
+        let response = app.clone().oneshot(consent_request).await.unwrap();
         assert_eq!(response.status(), StatusCode::SEE_OTHER);
 
         let redirect_location = response
@@ -91,7 +134,7 @@ mod tests {
 
         // Step 3: Simulate a /token request
         let token_request_body = format!(
-            "grant_type=authorization_code&code={code}&client_id={client_id}&client_secret={client_secret}"
+            "grant_type=authorization_code&code={code}&client_id={client_id}&client_secret={client_secret}&redirect_uri=http://localhost/callback"
         );
 
         let token_request = Request::builder()
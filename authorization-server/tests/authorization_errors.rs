@@ -0,0 +1,93 @@
+mod common;
+
+use authorization_server::{config::Config, router::router_with_state};
+use axum::http::{StatusCode, header};
+use serde_json::json;
+
+#[tokio::test]
+async fn test_invalid_client_or_redirect_uri_is_shown_to_the_user() {
+    let app = router_with_state(common::app_state());
+    let (client_id, _) = common::register_client(&app, json!({ "scope": "read" })).await;
+
+    let response = common::get(
+        &app,
+        "/authorize?client_id=unknown&response_type=code&redirect_uri=http://localhost/callback",
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert!(response.headers().get(header::LOCATION).is_none());
+    let error = common::body_json(response).await;
+    assert_eq!(error["error"], "invalid_client");
+    assert!(error["error_description"].is_string());
+
+    // Never redirect to a URI the client did not register
+    let response = common::get(
+        &app,
+        &format!(
+            "/authorize?client_id={client_id}&response_type=token&redirect_uri=https://attacker.example/"
+        ),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(response.headers().get(header::LOCATION).is_none());
+    assert_eq!(
+        common::body_json(response).await["error"],
+        "invalid_request"
+    );
+}
+
+#[tokio::test]
+async fn test_other_errors_are_redirected_to_the_client() {
+    let app = router_with_state(common::app_state());
+    let (client_id, _) = common::register_client(&app, json!({ "scope": "read" })).await;
+
+    for (query, error) in [
+        ("response_type=token", "unsupported_response_type"),
+        ("", "invalid_request"),
+        ("response_type=code&scope=write", "invalid_scope"),
+        (
+            "response_type=code&resource=https://unknown.example",
+            "invalid_target",
+        ),
+    ] {
+        let response = common::get(
+            &app,
+            &format!("/authorize?client_id={client_id}&state=xyz&{query}"),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let location = response.headers()[header::LOCATION].to_str().unwrap();
+        assert!(location.starts_with("http://localhost/callback?"));
+        assert_eq!(common::redirect_param(&response, "error").unwrap(), error);
+        assert!(common::redirect_param(&response, "error_description").is_some());
+        assert!(common::redirect_param(&response, "error_uri").is_none());
+        assert_eq!(common::redirect_param(&response, "state").unwrap(), "xyz");
+        assert!(common::redirect_param(&response, "code").is_none());
+    }
+}
+
+#[tokio::test]
+async fn test_error_uri_points_at_the_configured_documentation() {
+    let app = router_with_state(common::app_state_with(Config {
+        error_uri: Some("https://docs.example/oauth-errors".to_string()),
+        ..common::test_config()
+    }));
+    let (client_id, _) = common::register_client(&app, json!({ "scope": "read" })).await;
+
+    let response = common::get(
+        &app,
+        &format!("/authorize?client_id={client_id}&response_type=code&scope=write"),
+    )
+    .await;
+    assert_eq!(
+        common::redirect_param(&response, "error_uri").unwrap(),
+        "https://docs.example/oauth-errors#invalid_scope"
+    );
+    assert!(common::redirect_param(&response, "state").is_none());
+
+    let response = common::get(&app, "/authorize?client_id=unknown&response_type=code").await;
+    assert_eq!(
+        common::body_json(response).await["error_uri"],
+        "https://docs.example/oauth-errors#invalid_client"
+    );
+}
@@ -0,0 +1,370 @@
+mod common;
+
+use authorization_server::{config::Config, router::router_with_state};
+use axum::{
+    Router,
+    body::Body,
+    http::{Method, Request, Response, StatusCode, header},
+};
+use serde_json::{Value, json};
+use tower::util::ServiceExt;
+
+fn app() -> Router {
+    router_with_state(common::app_state_with(Config {
+        issuer: "https://issuer.example/".to_string(),
+        ..common::test_config()
+    }))
+}
+
+/// Sends a request to the client configuration endpoint of `registration`.
+async fn configure(
+    app: &Router,
+    method: Method,
+    registration: &Value,
+    token: &str,
+    body: Option<Value>,
+) -> Response<Body> {
+    let client_id = registration["client_id"].as_str().unwrap();
+    let mut builder = Request::builder()
+        .method(method)
+        .uri(format!("/register/{client_id}"))
+        .header(header::AUTHORIZATION, format!("Bearer {token}"));
+    if body.is_some() {
+        builder = builder.header(header::CONTENT_TYPE, "application/json");
+    }
+    let body = body.map_or_else(Body::empty, |body| Body::from(body.to_string()));
+    app.clone()
+        .oneshot(builder.body(body).unwrap())
+        .await
+        .unwrap()
+}
+
+fn token(registration: &Value) -> &str {
+    registration["registration_access_token"].as_str().unwrap()
+}
+
+/// Returns `response` without the `client_secret`, which is only returned when it is issued.
+fn without_secret(response: &Value) -> Value {
+    let mut response = response.clone();
+    response.as_object_mut().unwrap().remove("client_secret");
+    response
+}
+
+#[tokio::test]
+async fn test_registration_returns_configuration_endpoint() {
+    let app = app();
+    let registration =
+        common::register(&app, common::client_metadata(json!({ "scope": "read" }))).await;
+
+    let client_id = registration["client_id"].as_str().unwrap();
+    assert_eq!(
+        registration["registration_client_uri"],
+        format!("https://issuer.example/register/{client_id}")
+    );
+
+    let response = configure(&app, Method::GET, &registration, token(&registration), None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let configuration = common::body_json(response).await;
+    assert!(configuration.get("client_secret").is_none());
+    assert_eq!(configuration, without_secret(&registration));
+}
+
+#[tokio::test]
+async fn test_invalid_registration_access_token_is_rejected() {
+    let app = app();
+    let registration =
+        common::register(&app, common::client_metadata(json!({ "scope": "read" }))).await;
+    let other = common::register(&app, common::client_metadata(json!({ "scope": "read" }))).await;
+
+    for (registration, token) in [
+        (&registration, "wrong"),
+        (&registration, ""),
+        (
+            &registration,
+            other["registration_access_token"].as_str().unwrap(),
+        ),
+        (&json!({ "client_id": "unknown" }), token(&registration)),
+    ] {
+        for method in [Method::GET, Method::DELETE] {
+            let response = configure(&app, method, registration, token, None).await;
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+            assert_eq!(common::body_json(response).await["error"], "invalid_token");
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_update_replaces_metadata() {
+    let app = app();
+    let registration =
+        common::register(&app, common::client_metadata(json!({ "scope": "read" }))).await;
+    let client_id = registration["client_id"].as_str().unwrap();
+    let client_secret = registration["client_secret"].as_str().unwrap();
+
+    // Repeating the client_secret keeps it
+    let response = configure(
+        &app,
+        Method::PUT,
+        &registration,
+        token(&registration),
+        Some(json!({
+            "client_id": client_id,
+            "client_secret": client_secret,
+            "client_name": "Renamed Client",
+            "redirect_uris": ["https://client.example/callback"],
+            "scope": "read write"
+        })),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let updated = common::body_json(response).await;
+    assert_eq!(updated["client_id"], client_id);
+    assert_eq!(updated["client_secret"], client_secret);
+    assert_eq!(updated["client_name"], "Renamed Client");
+    assert_eq!(
+        updated["redirect_uris"],
+        json!(["https://client.example/callback"])
+    );
+    assert_eq!(updated["scope"], "read write");
+    assert_eq!(
+        updated["client_id_issued_at"],
+        registration["client_id_issued_at"]
+    );
+    assert_eq!(
+        updated["registration_access_token"],
+        registration["registration_access_token"]
+    );
+
+    // The previous redirect URI is no longer accepted
+    let response = common::get(
+        &app,
+        &format!(
+            "/authorize?client_id={client_id}&response_type=code&redirect_uri=http://localhost/callback"
+        ),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = configure(&app, Method::GET, &registration, token(&registration), None).await;
+    assert_eq!(common::body_json(response).await, without_secret(&updated));
+}
+
+#[tokio::test]
+async fn test_update_without_secret_rotates_it() {
+    let app = app();
+    let registration =
+        common::register(&app, common::client_metadata(json!({ "scope": "read" }))).await;
+    let client_id = registration["client_id"].as_str().unwrap();
+    let old_secret = registration["client_secret"].as_str().unwrap();
+
+    let response = configure(
+        &app,
+        Method::PUT,
+        &registration,
+        token(&registration),
+        Some(json!({
+            "client_id": client_id,
+            "redirect_uris": ["http://localhost/callback"],
+            "grant_types": ["authorization_code", "client_credentials"]
+        })),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let updated = common::body_json(response).await;
+    let new_secret = updated["client_secret"].as_str().unwrap();
+    assert_ne!(new_secret, old_secret);
+
+    for (secret, status) in [
+        (old_secret, StatusCode::UNAUTHORIZED),
+        (new_secret, StatusCode::OK),
+    ] {
+        let response = common::post_form(
+            &app,
+            "/token",
+            &[
+                ("grant_type", "client_credentials"),
+                ("client_id", client_id),
+                ("client_secret", secret),
+            ],
+        )
+        .await;
+        assert_eq!(response.status(), status);
+    }
+}
+
+#[tokio::test]
+async fn test_invalid_updates_are_rejected() {
+    let app = app();
+    let registration =
+        common::register(&app, common::client_metadata(json!({ "scope": "read" }))).await;
+    let client_id = registration["client_id"].as_str().unwrap();
+
+    for (update, error) in [
+        (
+            json!({ "client_id": "other", "redirect_uris": ["http://localhost/callback"] }),
+            "invalid_client_metadata",
+        ),
+        (
+            json!({
+                "client_id": client_id,
+                "client_secret": "wrong",
+                "redirect_uris": ["http://localhost/callback"]
+            }),
+            "invalid_client_metadata",
+        ),
+        (
+            json!({ "client_id": client_id, "redirect_uris": ["/relative"] }),
+            "invalid_redirect_uri",
+        ),
+    ] {
+        let response = configure(
+            &app,
+            Method::PUT,
+            &registration,
+            token(&registration),
+            Some(update),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(common::body_json(response).await["error"], error);
+    }
+
+    // The registered metadata is unchanged
+    let response = configure(&app, Method::GET, &registration, token(&registration), None).await;
+    assert_eq!(
+        common::body_json(response).await,
+        without_secret(&registration)
+    );
+}
+
+#[tokio::test]
+async fn test_delete_decommissions_client() {
+    let app = app();
+    let registration =
+        common::register(&app, common::client_metadata(json!({ "scope": "read" }))).await;
+    let client_id = registration["client_id"].as_str().unwrap();
+
+    let response = configure(
+        &app,
+        Method::DELETE,
+        &registration,
+        token(&registration),
+        None,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = configure(&app, Method::GET, &registration, token(&registration), None).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = common::get(
+        &app,
+        &format!("/authorize?client_id={client_id}&response_type=code"),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(common::body_json(response).await["error"], "invalid_client");
+}
+
+/// Returns whether introspection by the client of `registration` reports `token` as active.
+async fn is_active(app: &Router, registration: &Value, token: &str) -> bool {
+    let response = common::post_form(
+        app,
+        "/introspect",
+        &[
+            ("token", token),
+            ("client_id", registration["client_id"].as_str().unwrap()),
+            (
+                "client_secret",
+                registration["client_secret"].as_str().unwrap(),
+            ),
+        ],
+    )
+    .await;
+    common::body_json(response).await["active"] == true
+}
+
+#[tokio::test]
+async fn test_delete_revokes_tokens_and_consents() {
+    let state = common::app_state_with(Config {
+        issuer: "https://issuer.example/".to_string(),
+        ..common::test_config()
+    });
+    let app = router_with_state(state.clone());
+    let registration =
+        common::register(&app, common::client_metadata(json!({ "scope": "read" }))).await;
+    let client_id = registration["client_id"].as_str().unwrap();
+    let client_secret = registration["client_secret"].as_str().unwrap();
+
+    // Obtain tokens, consenting to the client on the way
+    let response = common::authorize(
+        &app,
+        &format!("/authorize?client_id={client_id}&response_type=code"),
+    )
+    .await;
+    let code = common::redirect_param(&response, "code").unwrap();
+    let response = common::post_form(
+        &app,
+        "/token",
+        &[
+            ("grant_type", "authorization_code"),
+            ("code", &code),
+            ("client_id", client_id),
+            ("client_secret", client_secret),
+        ],
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let tokens = common::body_json(response).await;
+
+    // A resource server introspects the tokens of the client
+    let resource_server = common::register(
+        &app,
+        json!({ "client_name": "Legacy Service", "grant_types": [] }),
+    )
+    .await;
+    let resource_server_id = resource_server["client_id"].as_str().unwrap();
+    state.lock().unwrap().config.introspection_clients = resource_server_id.to_string();
+    let access_token = tokens["access_token"].as_str().unwrap();
+    let refresh_token = tokens["refresh_token"].as_str().unwrap();
+    assert!(is_active(&app, &resource_server, access_token).await);
+    assert!(is_active(&app, &resource_server, refresh_token).await);
+
+    let response = configure(
+        &app,
+        Method::DELETE,
+        &registration,
+        token(&registration),
+        None,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    assert!(!is_active(&app, &resource_server, access_token).await);
+    assert!(!is_active(&app, &resource_server, refresh_token).await);
+
+    // Resource servers learn about the revoked access token as well
+    let jti = common::jwt_claims(access_token)["jti"].clone();
+    let response = common::revoked_tokens(
+        &app,
+        resource_server_id,
+        resource_server["client_secret"].as_str().unwrap(),
+    )
+    .await;
+    let revoked = common::body_json(response).await;
+    assert!(
+        revoked["revoked"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|token| token["jti"] == jti)
+    );
+
+    let consent = state
+        .lock()
+        .unwrap()
+        .storage
+        .get_consent(common::USER_ID, client_id)
+        .unwrap();
+    assert_eq!(consent, None);
+}
@@ -0,0 +1,130 @@
+mod common;
+
+use authorization_server::router::router_with_state;
+use axum::http::StatusCode;
+use serde_json::{Value, json};
+
+/// Returns the metadata of a client that only uses the client credentials grant for `scope`.
+fn service(scope: &str) -> Value {
+    json!({
+        "client_name": "Backend Job",
+        "grant_types": ["client_credentials"],
+        "redirect_uris": [],
+        "scope": scope
+    })
+}
+
+#[tokio::test]
+async fn test_client_credentials_grant() {
+    let app = router_with_state(common::app_state());
+    let (client_id, client_secret) = common::register_client(&app, service("read write")).await;
+
+    let response = common::post_form(
+        &app,
+        "/token",
+        &[
+            ("grant_type", "client_credentials"),
+            ("client_id", &client_id),
+            ("client_secret", &client_secret),
+        ],
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let token_response = common::body_json(response).await;
+    assert_eq!(token_response["token_type"], "Bearer");
+    assert!(token_response.get("refresh_token").is_none());
+
+    let claims = common::jwt_claims(token_response["access_token"].as_str().unwrap());
+    assert_eq!(claims["sub"], client_id);
+    assert_eq!(claims["scope"], "read write");
+}
+
+#[tokio::test]
+async fn test_client_credentials_grant_with_narrowed_scope() {
+    let app = router_with_state(common::app_state());
+    let (client_id, client_secret) = common::register_client(&app, service("read write")).await;
+
+    let response = common::post_form(
+        &app,
+        "/token",
+        &[
+            ("grant_type", "client_credentials"),
+            ("client_id", &client_id),
+            ("client_secret", &client_secret),
+            ("scope", "write"),
+        ],
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let token_response = common::body_json(response).await;
+    let claims = common::jwt_claims(token_response["access_token"].as_str().unwrap());
+    assert_eq!(claims["scope"], "write");
+}
+
+#[tokio::test]
+async fn test_client_credentials_grant_rejects_unregistered_scope() {
+    let app = router_with_state(common::app_state());
+    let (client_id, client_secret) = common::register_client(&app, service("read")).await;
+
+    let response = common::post_form(
+        &app,
+        "/token",
+        &[
+            ("grant_type", "client_credentials"),
+            ("client_id", &client_id),
+            ("client_secret", &client_secret),
+            ("scope", "read admin"),
+        ],
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(common::body_json(response).await["error"], "invalid_scope");
+}
+
+#[tokio::test]
+async fn test_client_credentials_grant_requires_authentication() {
+    let app = router_with_state(common::app_state());
+    let (client_id, _) = common::register_client(&app, service("read")).await;
+
+    let response = common::post_form(
+        &app,
+        "/token",
+        &[
+            ("grant_type", "client_credentials"),
+            ("client_id", &client_id),
+            ("client_secret", "wrong-secret"),
+        ],
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(common::body_json(response).await["error"], "invalid_client");
+
+    // Public clients have no credentials to authenticate with
+    let registration = common::register(
+        &app,
+        json!({
+            "client_name": "Public Client",
+            "redirect_uris": ["http://localhost/callback"],
+            "token_endpoint_auth_method": "none"
+        }),
+    )
+    .await;
+    let public_client_id = registration["client_id"].as_str().unwrap();
+
+    let response = common::post_form(
+        &app,
+        "/token",
+        &[
+            ("grant_type", "client_credentials"),
+            ("client_id", public_client_id),
+        ],
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        common::body_json(response).await["error"],
+        "unauthorized_client"
+    );
+}
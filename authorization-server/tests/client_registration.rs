@@ -1,23 +1,24 @@
-use authorization_server::{AppState, SharedAppState, register::register_client};
+mod common;
+
+use authorization_server::{
+    AppState, SharedAppState,
+    config::{Config, RegistrationPolicy},
+    register::register_client,
+    router::router_with_state,
+};
 use axum::{
     Router,
     body::Body,
-    http::{Request, StatusCode},
-};
-use serde_json::Value;
-use std::{
-    collections::HashMap,
-    sync::{Arc, Mutex},
+    http::{Request, StatusCode, header},
 };
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex};
 use tower::util::ServiceExt;
 
 #[tokio::test]
 async fn test_client_registration() {
     // Create the shared client registry
-    let client_registry: SharedAppState = Arc::new(Mutex::new(AppState {
-        authorization_state: HashMap::new(),
-        client_registry: HashMap::new(),
-    }));
+    let client_registry: SharedAppState = Arc::new(Mutex::new(AppState::default()));
 
     // Build the application
     let app = Router::new()
@@ -40,7 +41,7 @@ async fn test_client_registration() {
         .unwrap();
 
     let response = app.clone().oneshot(registration_request).await.unwrap();
-    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.status(), StatusCode::CREATED);
 
     let body = axum::body::to_bytes(response.into_body(), usize::MAX)
         .await
@@ -50,3 +51,476 @@ async fn test_client_registration() {
     assert!(registration_response["client_id"].as_str().is_some());
     assert!(registration_response["client_secret"].as_str().is_some());
 }
+
+/// Sends a registration request for `metadata` to the full application.
+async fn register(metadata: Value) -> (StatusCode, Value) {
+    register_with(&router_with_state(common::app_state()), None, metadata).await
+}
+
+/// Sends a registration request for `metadata` to `app`, with `token` as bearer token.
+async fn register_with(app: &Router, token: Option<&str>, metadata: Value) -> (StatusCode, Value) {
+    let mut builder = Request::builder()
+        .method("POST")
+        .uri("/register")
+        .header("Content-Type", "application/json");
+    if let Some(token) = token {
+        builder = builder.header(header::AUTHORIZATION, format!("Bearer {token}"));
+    }
+    let request = builder.body(Body::from(metadata.to_string())).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    (response.status(), common::body_json(response).await)
+}
+
+#[tokio::test]
+async fn test_registered_metadata_is_echoed() {
+    let (status, registration) = register(json!({
+        "client_name": "Photo Printer",
+        "redirect_uris": ["https://printer.example/callback"],
+        "grant_types": ["authorization_code", "refresh_token", "client_credentials"],
+        "response_types": ["code"],
+        "token_endpoint_auth_method": "client_secret_post",
+        "scope": "read write",
+        "client_uri": "https://printer.example/",
+        "logo_uri": "https://printer.example/logo.png",
+        "contacts": ["admin@printer.example"],
+        "jwks_uri": "https://printer.example/jwks.json"
+    }))
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    assert!(registration["client_id"].is_string());
+    assert!(registration["client_secret"].is_string());
+    assert!(registration["client_id_issued_at"].as_u64().unwrap() > 0);
+    assert_eq!(registration["client_secret_expires_at"], 0);
+    assert_eq!(registration["client_name"], "Photo Printer");
+    assert_eq!(
+        registration["redirect_uris"],
+        json!(["https://printer.example/callback"])
+    );
+    assert_eq!(
+        registration["grant_types"],
+        json!(["authorization_code", "refresh_token", "client_credentials"])
+    );
+    assert_eq!(registration["response_types"], json!(["code"]));
+    assert_eq!(
+        registration["token_endpoint_auth_method"],
+        "client_secret_post"
+    );
+    assert_eq!(registration["scope"], "read write");
+    assert_eq!(registration["client_uri"], "https://printer.example/");
+    assert_eq!(registration["logo_uri"], "https://printer.example/logo.png");
+    assert_eq!(registration["contacts"], json!(["admin@printer.example"]));
+    assert_eq!(
+        registration["jwks_uri"],
+        "https://printer.example/jwks.json"
+    );
+}
+
+#[tokio::test]
+async fn test_omitted_metadata_gets_defaults() {
+    let (status, registration) = register(json!({
+        "redirect_uris": ["http://localhost/callback"],
+        "token_endpoint_auth_method": "none"
+    }))
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    assert!(registration.get("client_secret").is_none());
+    assert!(registration.get("client_secret_expires_at").is_none());
+    assert!(registration.get("client_name").is_none());
+    assert_eq!(
+        registration["grant_types"],
+        json!(["authorization_code", "refresh_token"])
+    );
+    assert_eq!(registration["response_types"], json!(["code"]));
+    assert_eq!(registration["token_endpoint_auth_method"], "none");
+    assert_eq!(registration["scope"], "read");
+}
+
+#[tokio::test]
+async fn test_invalid_metadata_is_rejected() {
+    for (metadata, error) in [
+        (
+            json!({ "redirect_uris": ["/callback"] }),
+            "invalid_redirect_uri",
+        ),
+        (
+            json!({ "redirect_uris": ["https://client.example/cb#fragment"] }),
+            "invalid_redirect_uri",
+        ),
+        (json!({ "redirect_uris": [] }), "invalid_redirect_uri"),
+        (
+            json!({ "grant_types": ["password"] }),
+            "invalid_client_metadata",
+        ),
+        (
+            json!({ "grant_types": ["client_credentials"], "response_types": ["code"] }),
+            "invalid_client_metadata",
+        ),
+        (
+            json!({ "redirect_uris": ["http://localhost/callback"], "response_types": ["token"] }),
+            "invalid_client_metadata",
+        ),
+        (
+            json!({ "grant_types": ["client_credentials"], "token_endpoint_auth_method": "none" }),
+            "invalid_client_metadata",
+        ),
+        (
+            json!({ "grant_types": [], "token_endpoint_auth_method": "private_key_jwt" }),
+            "invalid_client_metadata",
+        ),
+        (
+            json!({ "grant_types": [], "scope": "read admin" }),
+            "invalid_client_metadata",
+        ),
+        (
+            json!({ "grant_types": [], "client_uri": "javascript:alert(1)" }),
+            "invalid_client_metadata",
+        ),
+        (
+            json!({ "grant_types": [], "logo_uri": "not a url" }),
+            "invalid_client_metadata",
+        ),
+        (
+            json!({ "grant_types": [], "jwks_uri": "ftp://client.example/jwks" }),
+            "invalid_client_metadata",
+        ),
+        (
+            json!({ "grant_types": [], "contacts": [" "] }),
+            "invalid_client_metadata",
+        ),
+    ] {
+        let (status, body) = register(metadata.clone()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{metadata}");
+        assert_eq!(body["error"], error, "{metadata}");
+        assert!(body["error_description"].is_string());
+    }
+}
+
+#[tokio::test]
+async fn test_registered_grant_types_are_enforced() {
+    let app = router_with_state(common::app_state());
+
+    // A client without the client_credentials grant cannot use it
+    let registration = common::register(
+        &app,
+        json!({ "redirect_uris": ["http://localhost/callback"] }),
+    )
+    .await;
+    let response = common::post_form(
+        &app,
+        "/token",
+        &[
+            ("grant_type", "client_credentials"),
+            ("client_id", registration["client_id"].as_str().unwrap()),
+            (
+                "client_secret",
+                registration["client_secret"].as_str().unwrap(),
+            ),
+        ],
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        common::body_json(response).await["error"],
+        "unauthorized_client"
+    );
+
+    // A client without the refresh token grant gets no refresh tokens
+    let registration = common::register(
+        &app,
+        json!({
+            "redirect_uris": ["http://localhost/callback"],
+            "grant_types": ["authorization_code"]
+        }),
+    )
+    .await;
+    let client_id = registration["client_id"].as_str().unwrap();
+    let response = common::authorize(
+        &app,
+        &format!("/authorize?client_id={client_id}&response_type=code"),
+    )
+    .await;
+    let code = common::redirect_param(&response, "code").unwrap();
+    let response = common::post_form(
+        &app,
+        "/token",
+        &[
+            ("grant_type", "authorization_code"),
+            ("code", &code),
+            ("client_id", client_id),
+            (
+                "client_secret",
+                registration["client_secret"].as_str().unwrap(),
+            ),
+        ],
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let tokens = common::body_json(response).await;
+    assert!(tokens["access_token"].is_string());
+    assert!(tokens.get("refresh_token").is_none());
+
+    // A client without the code response type cannot use /authorize
+    let registration = common::register(
+        &app,
+        json!({
+            "redirect_uris": ["http://localhost/callback"],
+            "grant_types": ["client_credentials"],
+            "response_types": []
+        }),
+    )
+    .await;
+    let client_id = registration["client_id"].as_str().unwrap();
+    let response = common::get(
+        &app,
+        &format!("/authorize?client_id={client_id}&response_type=code"),
+    )
+    .await;
+    assert_eq!(
+        common::redirect_param(&response, "error").unwrap(),
+        "unauthorized_client"
+    );
+}
+
+const ADMIN_TOKEN: &str = "test-admin-token";
+
+fn app_with_policy(registration_policy: RegistrationPolicy) -> Router {
+    router_with_state(common::app_state_with(Config {
+        admin_token: Some(ADMIN_TOKEN.to_string()),
+        registration_policy,
+        ..common::test_config()
+    }))
+}
+
+/// Issues an initial access token with the given limits through the admin endpoint.
+async fn initial_access_token(app: &Router, limits: Value) -> String {
+    let request = Request::builder()
+        .method("POST")
+        .uri("/admin/initial-access-tokens")
+        .header(header::AUTHORIZATION, format!("Bearer {ADMIN_TOKEN}"))
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(limits.to_string()))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    common::body_json(response).await["initial_access_token"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+fn web_client() -> Value {
+    json!({ "redirect_uris": ["http://localhost/callback"] })
+}
+
+#[tokio::test]
+async fn test_open_policy_registers_anyone() {
+    let app = app_with_policy(RegistrationPolicy::Open);
+
+    let (status, _) = register_with(&app, None, web_client()).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = register_with(&app, Some("anything"), web_client()).await;
+    assert_eq!(status, StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn test_initial_access_token_policy_requires_a_token() {
+    let app = app_with_policy(RegistrationPolicy::InitialAccessToken);
+
+    for token in [None, Some("forged"), Some(ADMIN_TOKEN)] {
+        let (status, error) = register_with(&app, token, web_client()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(error["error"], "invalid_token");
+    }
+
+    // An unlimited token may be used for several registrations
+    let token = initial_access_token(&app, json!({})).await;
+    for _ in 0..2 {
+        let (status, registration) = register_with(&app, Some(&token), web_client()).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert!(registration["client_id"].is_string());
+    }
+}
+
+#[tokio::test]
+async fn test_initial_access_token_limits_scopes_and_grant_types() {
+    let app = app_with_policy(RegistrationPolicy::InitialAccessToken);
+    let token = initial_access_token(
+        &app,
+        json!({ "scope": "read", "grant_types": ["client_credentials"] }),
+    )
+    .await;
+
+    for metadata in [
+        json!({ "grant_types": ["client_credentials"], "scope": "read write" }),
+        json!({ "redirect_uris": ["http://localhost/callback"], "scope": "read" }),
+    ] {
+        let (status, error) = register_with(&app, Some(&token), metadata).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error["error"], "invalid_client_metadata");
+    }
+
+    let (status, registration) = register_with(
+        &app,
+        Some(&token),
+        json!({ "grant_types": ["client_credentials"], "scope": "read" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // The limits also apply to updates of the client
+    let client_id = registration["client_id"].as_str().unwrap();
+    let request = Request::builder()
+        .method("PUT")
+        .uri(format!("/register/{client_id}"))
+        .header(
+            header::AUTHORIZATION,
+            format!(
+                "Bearer {}",
+                registration["registration_access_token"].as_str().unwrap()
+            ),
+        )
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(
+            json!({
+                "client_id": client_id,
+                "grant_types": ["client_credentials"],
+                "scope": "read write"
+            })
+            .to_string(),
+        ))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        common::body_json(response).await["error"],
+        "invalid_client_metadata"
+    );
+}
+
+#[tokio::test]
+async fn test_initial_access_tokens_are_issued_by_admins_only() {
+    let app = app_with_policy(RegistrationPolicy::InitialAccessToken);
+
+    let create = |token: &str, limits: Value| {
+        Request::builder()
+            .method("POST")
+            .uri("/admin/initial-access-tokens")
+            .header(header::AUTHORIZATION, format!("Bearer {token}"))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(limits.to_string()))
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(create("wrong", json!({})))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    for limits in [
+        json!({ "scope": "admin" }),
+        json!({ "grant_types": ["password"] }),
+        json!({ "max_uses": 0 }),
+    ] {
+        let response = app
+            .clone()
+            .oneshot(create(ADMIN_TOKEN, limits))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
+
+#[tokio::test]
+async fn test_initial_access_token_is_limited_in_uses() {
+    let app = app_with_policy(RegistrationPolicy::InitialAccessToken);
+    let token = initial_access_token(&app, json!({ "max_uses": 2 })).await;
+
+    for _ in 0..2 {
+        let (status, _) = register_with(&app, Some(&token), web_client()).await;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+    let (status, error) = register_with(&app, Some(&token), web_client()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(error["error"], "invalid_token");
+
+    // A failed registration does not use the token up
+    let token = initial_access_token(&app, json!({ "max_uses": 1 })).await;
+    let (status, _) = register_with(&app, Some(&token), json!({ "redirect_uris": [] })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = register_with(&app, Some(&token), web_client()).await;
+    assert_eq!(status, StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn test_initial_access_token_expires() {
+    let app = router_with_state(common::app_state_with(Config {
+        admin_token: Some(ADMIN_TOKEN.to_string()),
+        registration_policy: RegistrationPolicy::InitialAccessToken,
+        initial_access_token_lifetime: 0,
+        ..common::test_config()
+    }));
+    let token = initial_access_token(&app, json!({})).await;
+
+    let (status, error) = register_with(&app, Some(&token), web_client()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(error["error"], "invalid_token");
+}
+
+#[tokio::test]
+async fn test_initial_access_tokens_are_revoked_by_admins() {
+    let app = app_with_policy(RegistrationPolicy::InitialAccessToken);
+    let token = initial_access_token(&app, json!({})).await;
+
+    let revoke = |admin_token: &str| {
+        Request::builder()
+            .method("POST")
+            .uri("/admin/initial-access-tokens/revoke")
+            .header(header::AUTHORIZATION, format!("Bearer {admin_token}"))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                json!({ "initial_access_token": token }).to_string(),
+            ))
+            .unwrap()
+    };
+
+    let response = app.clone().oneshot(revoke("wrong")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let (status, _) = register_with(&app, Some(&token), web_client()).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let response = app.clone().oneshot(revoke(ADMIN_TOKEN)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let (status, error) = register_with(&app, Some(&token), web_client()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(error["error"], "invalid_token");
+
+    let response = app.clone().oneshot(revoke(ADMIN_TOKEN)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_admin_policy_requires_the_admin_token() {
+    let app = app_with_policy(RegistrationPolicy::Admin);
+
+    let token = initial_access_token(&app, json!({})).await;
+    for token in [None, Some("forged"), Some(token.as_str())] {
+        let (status, error) = register_with(&app, token, web_client()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(error["error"], "invalid_token");
+    }
+
+    let (status, _) = register_with(&app, Some(ADMIN_TOKEN), web_client()).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // Without an admin token, nobody may register
+    let app = router_with_state(common::app_state_with(Config {
+        registration_policy: RegistrationPolicy::Admin,
+        ..common::test_config()
+    }));
+    let (status, _) = register_with(&app, None, web_client()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
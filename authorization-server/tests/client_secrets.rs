@@ -0,0 +1,235 @@
+mod common;
+
+use authorization_server::{
+    config::Config, register::RegisteredClient, router::router_with_state, secret,
+    token::TokenRequest,
+};
+use axum::{
+    body::Body,
+    http::{Request, StatusCode, header},
+};
+use serde_json::json;
+use tower::util::ServiceExt;
+
+#[tokio::test]
+async fn test_client_secret_is_stored_hashed() {
+    let state = common::app_state();
+    let app = router_with_state(state.clone());
+    let registration = common::register(
+        &app,
+        json!({
+            "redirect_uris": ["http://localhost/callback"],
+            "grant_types": ["client_credentials"]
+        }),
+    )
+    .await;
+    let client_id = registration["client_id"].as_str().unwrap();
+    let client_secret = registration["client_secret"].as_str().unwrap();
+
+    let client = state
+        .lock()
+        .unwrap()
+        .storage
+        .get_client(client_id)
+        .unwrap()
+        .unwrap();
+    let stored = serde_json::to_string(&client).unwrap();
+    assert!(!stored.contains(client_secret));
+    assert!(client.verify_secret(client_secret));
+    assert!(!client.verify_secret(&client_secret[1..]));
+
+    for (secret, status) in [
+        (client_secret, StatusCode::OK),
+        (
+            &client_secret[..client_secret.len() - 1],
+            StatusCode::UNAUTHORIZED,
+        ),
+        (
+            client.client_secret_hash.as_deref().unwrap(),
+            StatusCode::UNAUTHORIZED,
+        ),
+    ] {
+        let response = common::post_form(
+            &app,
+            "/token",
+            &[
+                ("grant_type", "client_credentials"),
+                ("client_id", client_id),
+                ("client_secret", secret),
+            ],
+        )
+        .await;
+        assert_eq!(response.status(), status);
+    }
+}
+
+#[test]
+fn test_secret_hashes_are_salted() {
+    let first = secret::hash("secret");
+    let second = secret::hash("secret");
+    assert_ne!(first, second);
+    assert!(secret::verify("secret", &first));
+    assert!(secret::verify("secret", &second));
+    assert!(!secret::verify("Secret", &first));
+    assert!(!secret::verify("secret", "$sha256$malformed"));
+}
+
+#[test]
+fn test_plaintext_secrets_of_earlier_versions_still_verify() {
+    let client: RegisteredClient = serde_json::from_value(json!({
+        "client_name": "Legacy Client",
+        "client_secret": "legacy-secret",
+        "redirect_uris": ["http://localhost/callback"],
+        "scopes": ["read"]
+    }))
+    .unwrap();
+    assert!(!client.is_public());
+    assert!(client.verify_secret("legacy-secret"));
+    assert!(!client.verify_secret("legacy"));
+}
+
+#[test]
+fn test_token_request_debug_redacts_credentials() {
+    let request = TokenRequest {
+        grant_type: "authorization_code".to_string(),
+        code: Some("the-code".to_string()),
+        redirect_uri: Some("http://localhost/callback".to_string()),
+        refresh_token: Some("the-refresh-token".to_string()),
+        client_id: "the-client".to_string(),
+        client_secret: Some("the-secret".to_string()),
+        code_verifier: Some("the-verifier".to_string()),
+        scope: None,
+        resource: None,
+    };
+
+    let output = format!("{request:?}");
+    assert!(output.contains("the-client"));
+    assert!(output.contains("http://localhost/callback"));
+    for credential in [
+        "the-code",
+        "the-refresh-token",
+        "the-secret",
+        "the-verifier",
+    ] {
+        assert!(!output.contains(credential), "{output}");
+    }
+}
+
+#[tokio::test]
+async fn test_registration_access_token_is_stored_hashed() {
+    let state = common::app_state();
+    let app = router_with_state(state.clone());
+    let registration = common::register(
+        &app,
+        json!({ "redirect_uris": ["http://localhost/callback"] }),
+    )
+    .await;
+    let client_id = registration["client_id"].as_str().unwrap();
+    let token = registration["registration_access_token"].as_str().unwrap();
+
+    let client = state
+        .lock()
+        .unwrap()
+        .storage
+        .get_client(client_id)
+        .unwrap()
+        .unwrap();
+    let stored = serde_json::to_string(&client).unwrap();
+    assert!(!stored.contains(token));
+
+    // Neither the credentials nor their hashes are printed
+    let output = format!("{client:?}");
+    assert!(output.contains("http://localhost/callback"));
+    for hash in [
+        &client.client_secret_hash,
+        &client.registration_access_token_hash,
+    ] {
+        assert!(!output.contains(hash.as_deref().unwrap()), "{output}");
+    }
+
+    let request = Request::builder()
+        .uri(format!("/register/{client_id}"))
+        .header(header::AUTHORIZATION, format!("Bearer {token}"))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        common::body_json(response).await["registration_access_token"],
+        token
+    );
+}
+
+#[tokio::test]
+async fn test_plaintext_registration_access_tokens_of_earlier_versions_still_verify() {
+    let state = common::app_state();
+    let app = router_with_state(state.clone());
+    let client: RegisteredClient = serde_json::from_value(json!({
+        "client_name": "Legacy Client",
+        "client_secret": "legacy-secret",
+        "redirect_uris": ["http://localhost/callback"],
+        "scopes": ["read"],
+        "registration_access_token": "legacy-token"
+    }))
+    .unwrap();
+    state
+        .lock()
+        .unwrap()
+        .storage
+        .put_client("legacy", client)
+        .unwrap();
+
+    for (token, status) in [
+        ("legacy-token", StatusCode::OK),
+        ("legacy", StatusCode::UNAUTHORIZED),
+    ] {
+        let request = Request::builder()
+            .uri("/register/legacy")
+            .header(header::AUTHORIZATION, format!("Bearer {token}"))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), status);
+    }
+}
+
+#[tokio::test]
+async fn test_initial_access_tokens_are_stored_hashed() {
+    let state = common::app_state_with(Config {
+        admin_token: Some("test-admin-token".to_string()),
+        ..common::test_config()
+    });
+    let app = router_with_state(state.clone());
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/admin/initial-access-tokens")
+        .header(header::AUTHORIZATION, "Bearer test-admin-token")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from("{}"))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let token = common::body_json(response).await["initial_access_token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let state = state.lock().unwrap();
+    assert!(
+        state
+            .storage
+            .get_initial_access_token(&token)
+            .unwrap()
+            .is_none()
+    );
+    let hash = secret::lookup_hash(&token);
+    assert_ne!(hash, token);
+    assert!(
+        state
+            .storage
+            .get_initial_access_token(&hash)
+            .unwrap()
+            .is_some()
+    );
+}
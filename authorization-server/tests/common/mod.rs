@@ -0,0 +1,260 @@
+#![allow(dead_code)]
+
+use authorization_server::{AppState, SharedAppState, config::Config, user::User};
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, Response, StatusCode, header},
+};
+use base64::{
+    Engine,
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+};
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex, OnceLock};
+use tower::util::ServiceExt;
+
+/// The ID of the user created in every test state.
+pub const USER_ID: &str = "user-1";
+/// The username of the test user.
+pub const USERNAME: &str = "alice";
+/// The password of the test user.
+pub const PASSWORD: &str = "correct horse battery staple";
+
+/// Returns a configuration pointing at the development keys in the workspace root.
+pub fn test_config() -> Config {
+    Config {
+        signing_key_path: concat!(env!("CARGO_MANIFEST_DIR"), "/../unsafe-private.pem").to_string(),
+        ..Default::default()
+    }
+}
+
+/// Creates a fresh shared state using `test_config`.
+pub fn app_state() -> SharedAppState {
+    app_state_with(test_config())
+}
+
+/// Creates a fresh shared state using the given configuration, with the test user.
+pub fn app_state_with(config: Config) -> SharedAppState {
+    // Hash the password once, hashing is slow on purpose
+    static USER: OnceLock<User> = OnceLock::new();
+    let user = USER.get_or_init(|| User::new(USERNAME, PASSWORD).unwrap());
+
+    let mut state = AppState::from_config(config);
+    state.storage.put_user(USER_ID, user.clone()).unwrap();
+    Arc::new(Mutex::new(state))
+}
+
+/// Reads a response body as JSON.
+pub async fn body_json(response: Response<Body>) -> Value {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+/// Decodes the claims of a JWT without verifying its signature.
+pub fn jwt_claims(token: &str) -> Value {
+    let payload = token.split('.').nth(1).unwrap();
+    serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).unwrap()).unwrap()
+}
+
+/// Extracts a query parameter from the `Location` header of a redirect response.
+pub fn redirect_param(response: &Response<Body>, name: &str) -> Option<String> {
+    let location = response.headers().get("location")?.to_str().ok()?;
+    url::Url::parse(location)
+        .ok()?
+        .query_pairs()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.to_string())
+}
+
+/// Registers a client with the given JSON metadata and returns the registration response.
+pub async fn register(app: &Router, metadata: Value) -> Value {
+    let request = Request::builder()
+        .method("POST")
+        .uri("/register")
+        .header("Content-Type", "application/json")
+        .body(Body::from(metadata.to_string()))
+        .unwrap();
+
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    body_json(response).await
+}
+
+/// Returns the metadata of a confidential client for the authorization code flow at
+/// `http://localhost/callback`, overridden by the fields of `overrides`.
+pub fn client_metadata(overrides: Value) -> Value {
+    let mut metadata = json!({
+        "client_name": "Test Client",
+        "redirect_uris": ["http://localhost/callback"]
+    });
+    metadata
+        .as_object_mut()
+        .unwrap()
+        .extend(overrides.as_object().unwrap().clone());
+    metadata
+}
+
+/// Registers a confidential client with `client_metadata(overrides)` and returns its
+/// `client_id` and `client_secret`.
+pub async fn register_client(app: &Router, overrides: Value) -> (String, String) {
+    let registration = register(app, client_metadata(overrides)).await;
+    (
+        registration["client_id"].as_str().unwrap().to_string(),
+        registration["client_secret"].as_str().unwrap().to_string(),
+    )
+}
+
+/// Exchanges an authorization code at the token endpoint, authenticating as the client, with
+/// the `extra` parameters, and returns the status and the response.
+pub async fn exchange_code(
+    app: &Router,
+    client_id: &str,
+    client_secret: &str,
+    code: &str,
+    extra: &[(&str, &str)],
+) -> (StatusCode, Value) {
+    let mut form = vec![
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("client_id", client_id),
+        ("client_secret", client_secret),
+    ];
+    form.extend_from_slice(extra);
+    let response = post_form(app, "/token", &form).await;
+    (response.status(), body_json(response).await)
+}
+
+/// Runs the authorization code flow for the client, appending `params` to the `/authorize`
+/// query, and returns the token response.
+pub async fn tokens(app: &Router, client_id: &str, client_secret: &str, params: &str) -> Value {
+    let response = authorize(
+        app,
+        &format!("/authorize?client_id={client_id}&response_type=code{params}"),
+    )
+    .await;
+    let code = redirect_param(&response, "code").unwrap();
+
+    let (status, tokens) = exchange_code(app, client_id, client_secret, &code, &[]).await;
+    assert_eq!(status, StatusCode::OK);
+    tokens
+}
+
+/// Registers a confidential client with `client_metadata(overrides)` and runs the authorization
+/// code flow for it, returning its `client_id`, `client_secret` and the token response.
+pub async fn client_with_tokens(
+    app: &Router,
+    overrides: Value,
+    params: &str,
+) -> (String, String, Value) {
+    let (client_id, client_secret) = register_client(app, overrides).await;
+    let tokens = tokens(app, &client_id, &client_secret, params).await;
+    (client_id, client_secret, tokens)
+}
+
+/// Registers a confidential resource server and lists it in `introspection_clients`, so that it
+/// may introspect any token and poll `/revoked`. Returns its `client_id` and `client_secret`.
+pub async fn register_resource_server(state: &SharedAppState, app: &Router) -> (String, String) {
+    let (client_id, client_secret) = register_client(
+        app,
+        json!({ "client_name": "Legacy Service", "grant_types": [] }),
+    )
+    .await;
+    state.lock().unwrap().config.introspection_clients = client_id.clone();
+    (client_id, client_secret)
+}
+
+/// Lists the revoked access tokens at `/revoked`, authenticating as the client with HTTP Basic
+/// authentication.
+pub async fn revoked_tokens(app: &Router, client_id: &str, client_secret: &str) -> Response<Body> {
+    let credentials = STANDARD.encode(format!("{client_id}:{client_secret}"));
+    let request = Request::builder()
+        .uri("/revoked")
+        .header(header::AUTHORIZATION, format!("Basic {credentials}"))
+        .body(Body::empty())
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap()
+}
+
+/// Sends a GET request to `uri`.
+pub async fn get(app: &Router, uri: &str) -> Response<Body> {
+    let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+    app.clone().oneshot(request).await.unwrap()
+}
+
+/// Sends a form-encoded POST request to `uri`.
+pub async fn post_form(app: &Router, uri: &str, form: &[(&str, &str)]) -> Response<Body> {
+    post_form_with_cookie(app, uri, form, None).await
+}
+
+/// Sends a form-encoded POST request to `uri`, with the given `Cookie` header if any.
+pub async fn post_form_with_cookie(
+    app: &Router,
+    uri: &str,
+    form: &[(&str, &str)],
+    cookie: Option<&str>,
+) -> Response<Body> {
+    let body = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(form)
+        .finish();
+    let mut request = Request::builder()
+        .method("POST")
+        .uri(uri)
+        .header("Content-Type", "application/x-www-form-urlencoded");
+    if let Some(cookie) = cookie {
+        request = request.header(header::COOKIE, cookie);
+    }
+    app.clone()
+        .oneshot(request.body(Body::from(body)).unwrap())
+        .await
+        .unwrap()
+}
+
+/// Opens the authorization request `uri` like a browser would, and returns the CSRF cookie it
+/// sets as a `name=value` pair together with the anti-CSRF token of its form, or `None` if the
+/// request shows no form.
+pub async fn csrf(app: &Router, uri: &str) -> Option<(String, String)> {
+    let response = get(app, uri).await;
+    let cookie = response
+        .headers()
+        .get_all(header::SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok()?.split(';').next())
+        .find(|cookie| cookie.starts_with("csrf="))?
+        .to_string();
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let token = String::from_utf8(body.to_vec())
+        .unwrap()
+        .split(r#"name="csrf_token" value=""#)
+        .nth(1)?
+        .split('"')
+        .next()?
+        .to_string();
+    Some((cookie, token))
+}
+
+/// Logs in as the test user on the login form of the authorization request `uri`, allowing
+/// the client on the consent screen at the same time.
+pub async fn authorize(app: &Router, uri: &str) -> Response<Body> {
+    let url = url::Url::parse(&format!("http://localhost{uri}")).unwrap();
+    let params: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    let csrf = csrf(app, uri).await;
+
+    let mut form: Vec<(&str, &str)> = params
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .collect();
+    form.push(("username", USERNAME));
+    form.push(("password", PASSWORD));
+    form.push(("consent", "allow"));
+    if let Some((_, token)) = &csrf {
+        form.push(("csrf_token", token));
+    }
+    let cookie = csrf.as_ref().map(|(cookie, _)| cookie.as_str());
+    post_form_with_cookie(app, url.path(), &form, cookie).await
+}
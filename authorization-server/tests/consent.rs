@@ -0,0 +1,304 @@
+mod common;
+
+use authorization_server::{SharedAppState, router::router_with_state};
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, Response, StatusCode, header},
+};
+use serde_json::{Value, json};
+use tower::util::ServiceExt;
+
+/// Returns the metadata of a client for `read write`, whose name the consent screen shows.
+fn metadata() -> Value {
+    json!({ "client_name": "Photo Printer", "scope": "read write" })
+}
+
+/// Returns the parameters of an authorization request for `client_id`.
+fn request<'a>(client_id: &'a str, extra: &[(&'a str, &'a str)]) -> Vec<(&'a str, &'a str)> {
+    let mut params = vec![
+        ("client_id", client_id),
+        ("response_type", "code"),
+        ("state", "xyz"),
+    ];
+    params.extend_from_slice(extra);
+    params
+}
+
+/// Returns the CSRF cookie and the anti-CSRF token of a login form for `client_id`.
+async fn csrf(app: &Router, client_id: &str) -> (String, String) {
+    let query = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(request(client_id, &[]))
+        .finish();
+    common::csrf(app, &format!("/authorize?{query}"))
+        .await
+        .unwrap()
+}
+
+/// Returns the anti-CSRF token carried by the CSRF cookie in `cookie`, which the forms repeat.
+fn csrf_token(cookie: &str) -> &str {
+    cookie
+        .split("; ")
+        .find_map(|cookie| cookie.strip_prefix("csrf="))
+        .unwrap_or_default()
+}
+
+/// Logs in as the test user without deciding on consent, and returns the response and the
+/// CSRF and session cookies.
+async fn log_in(app: &Router, client_id: &str, extra: &[(&str, &str)]) -> (Response<Body>, String) {
+    let (csrf_cookie, csrf_token) = csrf(app, client_id).await;
+    let mut form = request(client_id, extra);
+    form.push(("username", common::USERNAME));
+    form.push(("password", common::PASSWORD));
+    form.push(("csrf_token", &csrf_token));
+    let response =
+        common::post_form_with_cookie(app, "/authorize", &form, Some(&csrf_cookie)).await;
+
+    let session_cookie = response.headers()[header::SET_COOKIE]
+        .to_str()
+        .unwrap()
+        .split(';')
+        .next()
+        .unwrap()
+        .to_string();
+    (response, format!("{csrf_cookie}; {session_cookie}"))
+}
+
+/// Sends an authorization request for `client_id` with the session cookie.
+async fn authorize(
+    app: &Router,
+    cookie: Option<&str>,
+    client_id: &str,
+    extra: &[(&str, &str)],
+) -> Response<Body> {
+    let query = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(request(client_id, extra))
+        .finish();
+    let mut builder = Request::builder().uri(format!("/authorize?{query}"));
+    if let Some(cookie) = cookie {
+        builder = builder.header(header::COOKIE, cookie);
+    }
+    app.clone()
+        .oneshot(builder.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
+
+/// Submits the consent screen for `client_id` with the user's `decision`.
+async fn decide(
+    app: &Router,
+    cookie: &str,
+    client_id: &str,
+    extra: &[(&str, &str)],
+    decision: &str,
+) -> Response<Body> {
+    let mut form = request(client_id, extra);
+    form.push(("consent", decision));
+    form.push(("csrf_token", csrf_token(cookie)));
+    common::post_form_with_cookie(app, "/authorize", &form, Some(cookie)).await
+}
+
+async fn body_text(response: Response<Body>) -> String {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+fn consented_scopes(state: &SharedAppState, client_id: &str) -> Option<Vec<String>> {
+    state
+        .lock()
+        .unwrap()
+        .storage
+        .get_consent(common::USER_ID, client_id)
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_consent_screen_lists_client_and_scopes() {
+    let app = router_with_state(common::app_state());
+    let (client_id, _) = common::register_client(&app, metadata()).await;
+
+    let (response, _) = log_in(&app, &client_id, &[("scope", "read")]).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let page = body_text(response).await;
+    assert!(page.contains("Photo Printer wants to access your account"));
+    assert!(page.contains("<code>read</code>"));
+    assert!(!page.contains("<code>write</code>"));
+    assert!(page.contains(r#"name="consent" value="allow""#));
+    assert!(page.contains(r#"name="consent" value="deny""#));
+}
+
+#[tokio::test]
+async fn test_granted_scopes_are_remembered() {
+    let state = common::app_state();
+    let app = router_with_state(state.clone());
+    let (client_id, _) = common::register_client(&app, metadata()).await;
+    let read = [("scope", "read")];
+
+    let (_, cookie) = log_in(&app, &client_id, &read).await;
+    let response = decide(&app, &cookie, &client_id, &read, "allow").await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert!(common::redirect_param(&response, "code").is_some());
+    assert_eq!(common::redirect_param(&response, "state").unwrap(), "xyz");
+    assert_eq!(
+        consented_scopes(&state, &client_id),
+        Some(vec!["read".to_string()])
+    );
+
+    // Repeat requests for granted scopes skip the consent screen
+    let response = authorize(&app, Some(&cookie), &client_id, &read).await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert!(common::redirect_param(&response, "code").is_some());
+
+    // Requests for more scopes ask again, and the new grant adds to the previous one
+    let response = authorize(&app, Some(&cookie), &client_id, &[]).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = decide(&app, &cookie, &client_id, &[], "allow").await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert_eq!(
+        consented_scopes(&state, &client_id),
+        Some(vec!["read".to_string(), "write".to_string()])
+    );
+}
+
+#[tokio::test]
+async fn test_prompt_consent_asks_again() {
+    let app = router_with_state(common::app_state());
+    let (client_id, _) = common::register_client(&app, metadata()).await;
+
+    let (_, cookie) = log_in(&app, &client_id, &[]).await;
+    decide(&app, &cookie, &client_id, &[], "allow").await;
+
+    let prompt = [("prompt", "consent")];
+    let response = authorize(&app, Some(&cookie), &client_id, &prompt).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(
+        body_text(response)
+            .await
+            .contains(r#"name="prompt" value="consent""#)
+    );
+
+    let response = decide(&app, &cookie, &client_id, &prompt, "allow").await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert!(common::redirect_param(&response, "code").is_some());
+}
+
+#[tokio::test]
+async fn test_declined_consent_redirects_with_access_denied() {
+    let state = common::app_state();
+    let app = router_with_state(state.clone());
+    let (client_id, _) = common::register_client(&app, metadata()).await;
+
+    let (_, cookie) = log_in(&app, &client_id, &[]).await;
+    let response = decide(&app, &cookie, &client_id, &[], "deny").await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert_eq!(
+        common::redirect_param(&response, "error").unwrap(),
+        "access_denied"
+    );
+    assert_eq!(common::redirect_param(&response, "state").unwrap(), "xyz");
+    assert!(common::redirect_param(&response, "code").is_none());
+    assert_eq!(consented_scopes(&state, &client_id), None);
+}
+
+#[tokio::test]
+async fn test_prompt_none_never_shows_a_page() {
+    let app = router_with_state(common::app_state());
+    let (client_id, _) = common::register_client(&app, metadata()).await;
+    let prompt = [("prompt", "none")];
+
+    let response = authorize(&app, None, &client_id, &prompt).await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert_eq!(
+        common::redirect_param(&response, "error").unwrap(),
+        "login_required"
+    );
+
+    let (_, cookie) = log_in(&app, &client_id, &[("scope", "read")]).await;
+    let response = authorize(&app, Some(&cookie), &client_id, &prompt).await;
+    assert_eq!(
+        common::redirect_param(&response, "error").unwrap(),
+        "consent_required"
+    );
+
+    decide(&app, &cookie, &client_id, &[], "allow").await;
+    let response = authorize(&app, Some(&cookie), &client_id, &prompt).await;
+    assert!(common::redirect_param(&response, "code").is_some());
+
+    let response = authorize(
+        &app,
+        Some(&cookie),
+        &client_id,
+        &[("prompt", "none consent")],
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert_eq!(
+        common::redirect_param(&response, "error").unwrap(),
+        "invalid_request"
+    );
+}
+
+#[tokio::test]
+async fn test_consent_is_per_client() {
+    let app = router_with_state(common::app_state());
+    let (client_id, _) = common::register_client(&app, metadata()).await;
+    let (other_client_id, _) = common::register_client(&app, metadata()).await;
+
+    let (_, cookie) = log_in(&app, &client_id, &[]).await;
+    decide(&app, &cookie, &client_id, &[], "allow").await;
+
+    let response = authorize(&app, Some(&cookie), &other_client_id, &[]).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(
+        body_text(response)
+            .await
+            .contains("wants to access your account")
+    );
+}
+
+#[tokio::test]
+async fn test_consent_requires_a_session() {
+    let app = router_with_state(common::app_state());
+    let (client_id, _) = common::register_client(&app, metadata()).await;
+
+    let (csrf_cookie, _) = csrf(&app, &client_id).await;
+    let cookie = format!("{csrf_cookie}; session=forged");
+    let response = decide(&app, &cookie, &client_id, &[], "allow").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(body_text(response).await.contains(r#"name="password""#));
+}
+
+#[tokio::test]
+async fn test_consent_requires_the_anti_csrf_token() {
+    let state = common::app_state();
+    let app = router_with_state(state.clone());
+    let (client_id, _) = common::register_client(&app, metadata()).await;
+    let (_, cookie) = log_in(&app, &client_id, &[]).await;
+
+    // The consent screen carries the token of the CSRF cookie
+    let response = authorize(&app, Some(&cookie), &client_id, &[]).await;
+    assert!(body_text(response).await.contains(&format!(
+        r#"<input type="hidden" name="csrf_token" value="{}">"#,
+        csrf_token(&cookie)
+    )));
+
+    // A form posted by another site carries the cookies, but not the token
+    let session_cookie = cookie.split("; ").nth(1).unwrap();
+    for (cookie, token) in [
+        (cookie.as_str(), None),
+        (cookie.as_str(), Some("forged")),
+        (session_cookie, Some(csrf_token(&cookie))),
+    ] {
+        let mut form = request(&client_id, &[]);
+        form.push(("consent", "allow"));
+        if let Some(token) = token {
+            form.push(("csrf_token", token));
+        }
+        let response = common::post_form_with_cookie(&app, "/authorize", &form, Some(cookie)).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(common::body_json(response).await["error"], "access_denied");
+    }
+    assert_eq!(consented_scopes(&state, &client_id), None);
+}
@@ -0,0 +1,201 @@
+mod common;
+
+use authorization_server::router::router_with_state;
+use axum::{Router, http::StatusCode};
+use jsonwebtoken::{EncodingKey, Header, encode};
+use serde_json::{Value, json};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Introspects `token` as the given resource server.
+async fn introspect(
+    app: &Router,
+    (client_id, client_secret): &(String, String),
+    token: &str,
+    hint: Option<&str>,
+) -> (StatusCode, Value) {
+    let mut form = vec![
+        ("token", token),
+        ("client_id", client_id.as_str()),
+        ("client_secret", client_secret.as_str()),
+    ];
+    if let Some(hint) = hint {
+        form.push(("token_type_hint", hint));
+    }
+    let response = common::post_form(app, "/introspect", &form).await;
+    let status = response.status();
+    (status, common::body_json(response).await)
+}
+
+#[tokio::test]
+async fn test_active_access_token() {
+    let state = common::app_state();
+    let app = router_with_state(state.clone());
+    let caller = common::register_resource_server(&state, &app).await;
+    let (client_id, _, tokens) =
+        common::client_with_tokens(&app, json!({ "scope": "read write" }), "&scope=read").await;
+
+    let (status, introspection) = introspect(
+        &app,
+        &caller,
+        tokens["access_token"].as_str().unwrap(),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(introspection["active"], true);
+    assert_eq!(introspection["scope"], "read");
+    assert_eq!(introspection["client_id"], client_id);
+    assert_eq!(introspection["sub"], common::USER_ID);
+    assert_eq!(introspection["token_type"], "Bearer");
+    let iat = introspection["iat"].as_u64().unwrap();
+    assert_eq!(introspection["exp"].as_u64().unwrap(), iat + 3600);
+}
+
+#[tokio::test]
+async fn test_refresh_token_becomes_inactive_once_rotated() {
+    let state = common::app_state();
+    let app = router_with_state(state.clone());
+    let caller = common::register_resource_server(&state, &app).await;
+    let (client_id, client_secret, tokens) =
+        common::client_with_tokens(&app, json!({ "scope": "read write" }), "&scope=read").await;
+    let refresh_token = tokens["refresh_token"].as_str().unwrap();
+
+    let (_, introspection) = introspect(&app, &caller, refresh_token, Some("refresh_token")).await;
+    assert_eq!(introspection["active"], true);
+    assert_eq!(introspection["token_type"], "refresh_token");
+    assert_eq!(introspection["client_id"], client_id);
+
+    let response = common::post_form(
+        &app,
+        "/token",
+        &[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+            ("client_id", &client_id),
+            ("client_secret", &client_secret),
+        ],
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let rotated = common::body_json(response).await;
+
+    let (_, introspection) = introspect(&app, &caller, refresh_token, None).await;
+    assert_eq!(introspection, json!({ "active": false }));
+
+    // Reusing the rotated token revokes its successor as well
+    let response = common::post_form(
+        &app,
+        "/token",
+        &[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+            ("client_id", &client_id),
+            ("client_secret", &client_secret),
+        ],
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let successor = rotated["refresh_token"].as_str().unwrap();
+    let (_, introspection) = introspect(&app, &caller, successor, Some("refresh_token")).await;
+    assert_eq!(introspection, json!({ "active": false }));
+}
+
+#[tokio::test]
+async fn test_expired_or_unknown_tokens_are_inactive() {
+    let state = common::app_state();
+    let app = router_with_state(state.clone());
+    let caller = common::register_resource_server(&state, &app).await;
+
+    let (_, introspection) = introspect(&app, &caller, "not-a-token", None).await;
+    assert_eq!(introspection, json!({ "active": false }));
+
+    // Sign an expired token with the server's own key
+    let jwks = common::body_json(common::get(&app, "/jwks.json").await).await;
+    let mut header = Header::new(jsonwebtoken::Algorithm::RS256);
+    header.kid = jwks["keys"][0]["kid"].as_str().map(String::from);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let private_key = std::fs::read(common::test_config().signing_key_path).unwrap();
+    let expired = encode(
+        &header,
+        &json!({
+            "sub": "someone",
+            "client_id": "someone",
+            "iat": now - 7200,
+            "exp": now - 3600,
+            "scope": "read"
+        }),
+        &EncodingKey::from_rsa_pem(&private_key).unwrap(),
+    )
+    .unwrap();
+
+    let (_, introspection) = introspect(&app, &caller, &expired, None).await;
+    assert_eq!(introspection, json!({ "active": false }));
+}
+
+#[tokio::test]
+async fn test_introspection_requires_confidential_client() {
+    let state = common::app_state();
+    let app = router_with_state(state.clone());
+    let (_, _, tokens) =
+        common::client_with_tokens(&app, json!({ "scope": "read write" }), "&scope=read").await;
+    let token = tokens["access_token"].as_str().unwrap();
+
+    let (client_id, _) = common::register_resource_server(&state, &app).await;
+    let (status, error) =
+        introspect(&app, &(client_id, "wrong-secret".to_string()), token, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(error["error"], "invalid_client");
+
+    let registration = common::register(
+        &app,
+        json!({
+            "client_name": "Mobile App",
+            "redirect_uris": ["http://localhost/callback"],
+            "token_endpoint_auth_method": "none"
+        }),
+    )
+    .await;
+    let public_client_id = registration["client_id"].as_str().unwrap();
+    let response = common::post_form(
+        &app,
+        "/introspect",
+        &[("token", token), ("client_id", public_client_id)],
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_unrelated_client_cannot_introspect_tokens_of_others() {
+    let app = router_with_state(common::app_state());
+    let (client_id, client_secret, tokens) =
+        common::client_with_tokens(&app, json!({ "scope": "read write" }), "&scope=read").await;
+    let other = common::register_client(
+        &app,
+        json!({ "client_name": "Other Service", "grant_types": [] }),
+    )
+    .await;
+    let owner = (client_id.clone(), client_secret);
+
+    for (token, hint) in [
+        (tokens["access_token"].as_str().unwrap(), None),
+        (
+            tokens["refresh_token"].as_str().unwrap(),
+            Some("refresh_token"),
+        ),
+    ] {
+        // A confidential client that is not a configured resource server learns nothing
+        let (status, introspection) = introspect(&app, &other, token, hint).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(introspection, json!({ "active": false }));
+
+        // The client the token was issued to may still introspect it
+        let (_, introspection) = introspect(&app, &owner, token, hint).await;
+        assert_eq!(introspection["active"], true);
+        assert_eq!(introspection["client_id"], client_id);
+    }
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use authorization_server::{config::Config, router::router_with_state};
+use axum::http::{StatusCode, header};
+use base64::{
+    Engine,
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+};
+use jsonwebtoken::{
+    Algorithm,
+    jwk::{AlgorithmParameters, JwkSet, PublicKeyUse},
+};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// Fetches `/jwks.json` from an app built with `config`, returning the response headers and body.
+async fn fetch_jwks(config: Config) -> (axum::http::HeaderMap, Value) {
+    let app = router_with_state(common::app_state_with(config));
+    let response = common::get(&app, "/jwks.json").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let headers = response.headers().clone();
+    (headers, common::body_json(response).await)
+}
+
+#[tokio::test]
+async fn test_jwks_round_trips_through_jwk_set() {
+    let (_, body) = fetch_jwks(common::test_config()).await;
+    let jwk_set: JwkSet = serde_json::from_value(body.clone()).unwrap();
+
+    assert_eq!(jwk_set.keys.len(), 1);
+    let jwk = &jwk_set.keys[0];
+    assert_eq!(jwk.common.public_key_use, Some(PublicKeyUse::Signature));
+    assert_eq!(jwk.common.algorithm, Some(Algorithm::RS256));
+    assert!(jwk.common.x509_chain.is_none());
+    assert!(jsonwebtoken::DecodingKey::from_jwk(jwk).is_ok());
+
+    // Nothing is lost or renamed when the set is parsed by a strict JOSE library
+    assert_eq!(serde_json::to_value(&jwk_set).unwrap(), body);
+    assert_eq!(body["keys"][0]["use"], "sig");
+    assert!(body["keys"][0].get("use_").is_none());
+}
+
+#[tokio::test]
+async fn test_jwks_kid_is_rfc7638_thumbprint() {
+    let (_, body) = fetch_jwks(common::test_config()).await;
+    let jwk_set: JwkSet = serde_json::from_value(body).unwrap();
+    let jwk = &jwk_set.keys[0];
+
+    let AlgorithmParameters::RSA(rsa) = &jwk.algorithm else {
+        panic!("expected an RSA key");
+    };
+    let canonical = format!(r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#, rsa.e, rsa.n);
+    let thumbprint = URL_SAFE_NO_PAD.encode(Sha256::digest(canonical.as_bytes()));
+    assert_eq!(jwk.common.key_id.as_deref(), Some(thumbprint.as_str()));
+}
+
+#[tokio::test]
+async fn test_jwks_is_cacheable() {
+    let config = Config {
+        jwks_max_age: 120,
+        ..common::test_config()
+    };
+    let (headers, _) = fetch_jwks(config).await;
+    assert_eq!(headers[header::CACHE_CONTROL], "public, max-age=120");
+}
+
+#[tokio::test]
+async fn test_jwks_publishes_certificate_chain() {
+    let config = Config {
+        signing_key_path: concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/signing-key-with-certificate.pem"
+        )
+        .to_string(),
+        ..common::test_config()
+    };
+    let (_, body) = fetch_jwks(config).await;
+    let jwk_set: JwkSet = serde_json::from_value(body).unwrap();
+    let jwk = &jwk_set.keys[0];
+
+    let chain = jwk.common.x509_chain.as_ref().unwrap();
+    assert_eq!(chain.len(), 1);
+    let leaf = STANDARD.decode(&chain[0]).unwrap();
+    assert_eq!(
+        jwk.common.x509_sha256_fingerprint.as_deref(),
+        Some(URL_SAFE_NO_PAD.encode(Sha256::digest(&leaf)).as_str())
+    );
+
+    // The certificate does not change the key, so neither does its thumbprint
+    let (_, default) = fetch_jwks(common::test_config()).await;
+    assert_eq!(
+        jwk.common.key_id.as_deref(),
+        default["keys"][0]["kid"].as_str()
+    );
+}
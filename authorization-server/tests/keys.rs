@@ -0,0 +1,218 @@
+mod common;
+
+use authorization_server::{
+    config::Config,
+    keys::{self, KeyManager, SigningKey},
+    router::router_with_state,
+};
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode},
+};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, decode_header};
+use serde_json::{Value, json};
+use std::path::Path;
+use tower::util::ServiceExt;
+
+const ADMIN_TOKEN: &str = "test-admin-token";
+
+/// Registers a service client and returns an access token issued to it.
+async fn access_token(app: &Router) -> String {
+    let registration = common::register(
+        app,
+        json!({
+            "client_name": "Backend Job",
+            "grant_types": ["client_credentials"],
+            "scope": "read"
+        }),
+    )
+    .await;
+
+    let response = common::post_form(
+        app,
+        "/token",
+        &[
+            ("grant_type", "client_credentials"),
+            ("client_id", registration["client_id"].as_str().unwrap()),
+            (
+                "client_secret",
+                registration["client_secret"].as_str().unwrap(),
+            ),
+        ],
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    common::body_json(response).await["access_token"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+/// Fetches the published JWK set.
+async fn jwks(app: &Router) -> Vec<Value> {
+    let response = common::get(app, "/jwks.json").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    common::body_json(response).await["keys"]
+        .as_array()
+        .unwrap()
+        .clone()
+}
+
+/// Requests a key rotation, optionally presenting `token`.
+async fn rotate(app: &Router, token: Option<&str>) -> axum::http::Response<Body> {
+    let mut request = Request::builder().method("POST").uri("/admin/keys/rotate");
+    if let Some(token) = token {
+        request = request.header("Authorization", format!("Bearer {token}"));
+    }
+    app.clone()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
+
+/// Verifies `token` against the published key matching its `kid`.
+fn verify(token: &str, keys: &[Value]) -> bool {
+    let kid = decode_header(token).unwrap().kid.unwrap();
+    let Some(key) = keys.iter().find(|key| key["kid"] == kid) else {
+        return false;
+    };
+    let decoding_key =
+        DecodingKey::from_rsa_components(key["n"].as_str().unwrap(), key["e"].as_str().unwrap())
+            .unwrap();
+    decode::<Value>(token, &decoding_key, &Validation::new(Algorithm::RS256)).is_ok()
+}
+
+fn admin_config() -> Config {
+    Config {
+        admin_token: Some(ADMIN_TOKEN.to_string()),
+        ..common::test_config()
+    }
+}
+
+/// Returns an admin configuration backed by the SQLite database at `path`.
+fn sqlite_config(path: &Path) -> Config {
+    Config {
+        sqlite_path: Some(path.to_string_lossy().into_owned()),
+        ..admin_config()
+    }
+}
+
+#[tokio::test]
+async fn test_access_token_header_carries_published_kid() {
+    let app = router_with_state(common::app_state());
+
+    let token = access_token(&app).await;
+    let keys = jwks(&app).await;
+
+    assert_eq!(keys.len(), 1);
+    assert_eq!(
+        decode_header(&token).unwrap().kid.as_deref(),
+        keys[0]["kid"].as_str()
+    );
+    assert!(verify(&token, &keys));
+}
+
+#[tokio::test]
+async fn test_rotation_keeps_previous_key_published() {
+    let app = router_with_state(common::app_state_with(admin_config()));
+    let old_token = access_token(&app).await;
+
+    let response = rotate(&app, Some(ADMIN_TOKEN)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let rotation = common::body_json(response).await;
+
+    // The new key is published first, followed by the retired key
+    let keys = jwks(&app).await;
+    assert_eq!(keys.len(), 2);
+    assert_eq!(keys[0]["kid"], rotation["kid"]);
+    assert_eq!(rotation["published"].as_array().unwrap().len(), 2);
+
+    // New tokens are signed with the new key, while old tokens still verify
+    let new_token = access_token(&app).await;
+    assert_eq!(
+        decode_header(&new_token).unwrap().kid.as_deref(),
+        rotation["kid"].as_str()
+    );
+    assert!(verify(&new_token, &keys));
+    assert!(verify(&old_token, &keys));
+}
+
+#[tokio::test]
+async fn test_rotation_requires_admin_token() {
+    let app = router_with_state(common::app_state_with(admin_config()));
+    assert_eq!(rotate(&app, None).await.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        rotate(&app, Some("wrong-token")).await.status(),
+        StatusCode::UNAUTHORIZED
+    );
+
+    // Without a configured admin token the endpoint is disabled
+    let app = router_with_state(common::app_state());
+    assert_eq!(
+        rotate(&app, Some(ADMIN_TOKEN)).await.status(),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[test]
+fn test_retired_keys_are_pruned_after_retention() {
+    let path = common::test_config().signing_key_path;
+    let mut keys = KeyManager::new(SigningKey::load(&path).unwrap(), Vec::new(), 0);
+
+    let mut replacement = SigningKey::load(&path).unwrap();
+    replacement.kid = "replacement".to_string();
+    keys.rotate(replacement);
+
+    let published: Vec<_> = keys.published().map(|key| key.kid.as_str()).collect();
+    assert_eq!(published, vec!["replacement"]);
+}
+
+#[tokio::test]
+async fn test_rotated_key_survives_restart_and_reaches_replicas() {
+    let path =
+        std::env::temp_dir().join(format!("authorization-server-{}.db", uuid::Uuid::new_v4()));
+    let app = router_with_state(common::app_state_with(sqlite_config(&path)));
+    let replica_state = common::app_state_with(sqlite_config(&path));
+    let replica = router_with_state(replica_state.clone());
+    let old_token = access_token(&app).await;
+
+    let response = rotate(&app, Some(ADMIN_TOKEN)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let kid = common::body_json(response).await["kid"].clone();
+
+    // A restarted server signs with the rotated key and still publishes the previous one
+    let restarted = router_with_state(common::app_state_with(sqlite_config(&path)));
+    let keys = jwks(&restarted).await;
+    assert_eq!(keys.len(), 2);
+    assert_eq!(keys[0]["kid"], kid);
+    let new_token = access_token(&restarted).await;
+    assert_eq!(
+        decode_header(&new_token).unwrap().kid.as_deref(),
+        kid.as_str()
+    );
+    assert!(verify(&new_token, &keys));
+    assert!(verify(&old_token, &keys));
+
+    // A replica started before the rotation picks up the key on its next sync
+    assert_eq!(jwks(&replica).await.len(), 1);
+    keys::sync(&mut replica_state.lock().unwrap()).unwrap();
+    assert_eq!(jwks(&replica).await, keys);
+    let replica_token = access_token(&replica).await;
+    assert_eq!(
+        decode_header(&replica_token).unwrap().kid.as_deref(),
+        kid.as_str()
+    );
+
+    // Rotating again on the replica retires the key rotated in by the first server
+    let response = rotate(&replica, Some(ADMIN_TOKEN)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let next_kid = common::body_json(response).await["kid"].clone();
+    let restarted = router_with_state(common::app_state_with(sqlite_config(&path)));
+    let keys = jwks(&restarted).await;
+    assert_eq!(keys.len(), 3);
+    assert_eq!(keys[0]["kid"], next_kid);
+    assert!(verify(&replica_token, &keys));
+
+    std::fs::remove_file(path).ok();
+}
@@ -0,0 +1,271 @@
+mod common;
+
+use authorization_server::{config::Config, router::router_with_state};
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, Response, StatusCode, header},
+};
+use serde_json::{Value, json};
+use tower::util::ServiceExt;
+
+const ADMIN_TOKEN: &str = "test-admin-token";
+
+fn app() -> Router {
+    router_with_state(common::app_state_with(Config {
+        admin_token: Some(ADMIN_TOKEN.to_string()),
+        ..common::test_config()
+    }))
+}
+
+/// Returns the metadata of a client whose name needs escaping in HTML.
+fn metadata() -> Value {
+    json!({ "client_name": "Test <Client>", "scope": "read" })
+}
+
+fn authorize_uri(client_id: &str) -> String {
+    format!("/authorize?client_id={client_id}&response_type=code&state=%22%3E%3Cscript%3E")
+}
+
+/// Submits the login form of the authorization request for `client_id`, allowing the client.
+async fn log_in(app: &Router, client_id: &str, username: &str, password: &str) -> Response<Body> {
+    let csrf = common::csrf(app, &authorize_uri(client_id)).await;
+    let mut form = vec![
+        ("client_id", client_id),
+        ("response_type", "code"),
+        ("username", username),
+        ("password", password),
+        ("consent", "allow"),
+    ];
+    if let Some((_, token)) = &csrf {
+        form.push(("csrf_token", token));
+    }
+    let cookie = csrf.as_ref().map(|(cookie, _)| cookie.as_str());
+    common::post_form_with_cookie(app, "/authorize", &form, cookie).await
+}
+
+/// Sends an authorization request for `client_id` with the given `Cookie` header.
+async fn authorize_with_cookie(app: &Router, client_id: &str, cookie: &str) -> Response<Body> {
+    let request = Request::builder()
+        .uri(authorize_uri(client_id))
+        .header(header::COOKIE, cookie)
+        .body(Body::empty())
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap()
+}
+
+async fn body_text(response: Response<Body>) -> String {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+/// Returns the `name=value` pair of the session cookie set by a response.
+fn session_cookie(response: &Response<Body>) -> String {
+    let set_cookie = response
+        .headers()
+        .get(header::SET_COOKIE)
+        .unwrap()
+        .to_str()
+        .unwrap();
+    assert!(set_cookie.contains("HttpOnly"));
+    assert!(set_cookie.contains("SameSite=Lax"));
+    set_cookie.split(';').next().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_authorize_shows_login_form() {
+    let app = app();
+    let (client_id, _) = common::register_client(&app, metadata()).await;
+
+    let response = common::get(&app, &authorize_uri(&client_id)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CACHE_CONTROL], "no-store");
+
+    // The browser receives a CSRF cookie, whose token the form repeats, but no session
+    let set_cookie = response.headers()[header::SET_COOKIE].to_str().unwrap();
+    assert!(set_cookie.contains("HttpOnly"));
+    assert!(set_cookie.contains("SameSite=Lax"));
+    let csrf_token = set_cookie
+        .split(';')
+        .next()
+        .unwrap()
+        .strip_prefix("csrf=")
+        .unwrap()
+        .to_string();
+    assert_eq!(
+        response
+            .headers()
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .count(),
+        1
+    );
+
+    let page = body_text(response).await;
+    assert!(page.contains(r#"<form method="post" action="/authorize">"#));
+    assert!(page.contains(&format!(
+        r#"<input type="hidden" name="csrf_token" value="{csrf_token}">"#
+    )));
+    assert!(page.contains(&format!(
+        r#"<input type="hidden" name="client_id" value="{client_id}">"#
+    )));
+    // Request parameters and client metadata are escaped
+    assert!(page.contains("Test &lt;Client&gt;"));
+    assert!(page.contains(r#"name="state" value="&quot;&gt;&lt;script&gt;""#));
+    assert!(!page.contains("<script>"));
+}
+
+#[tokio::test]
+async fn test_invalid_requests_are_rejected_before_login() {
+    let app = app();
+
+    let response = common::get(&app, "/authorize?client_id=unknown&response_type=code").await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = log_in(&app, "unknown", common::USERNAME, common::PASSWORD).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(common::body_json(response).await["error"], "invalid_client");
+}
+
+#[tokio::test]
+async fn test_invalid_credentials_show_the_form_again() {
+    let app = app();
+    let (client_id, _) = common::register_client(&app, metadata()).await;
+
+    for (username, password) in [
+        (common::USERNAME, "wrong password"),
+        ("mallory", common::PASSWORD),
+    ] {
+        let response = log_in(&app, &client_id, username, password).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(response.headers().get(header::SET_COOKIE).is_none());
+        assert!(
+            body_text(response)
+                .await
+                .contains("Invalid username or password.")
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_login_requires_the_anti_csrf_token() {
+    let app = app();
+    let (client_id, _) = common::register_client(&app, metadata()).await;
+    let (cookie, token) = common::csrf(&app, &authorize_uri(&client_id))
+        .await
+        .unwrap();
+
+    // A login form posted by another site lacks the cookie or the token
+    for (cookie, token) in [
+        (None, Some(token.as_str())),
+        (Some(cookie.as_str()), None),
+        (Some(cookie.as_str()), Some("forged")),
+    ] {
+        let mut form = vec![
+            ("client_id", client_id.as_str()),
+            ("response_type", "code"),
+            ("username", common::USERNAME),
+            ("password", common::PASSWORD),
+            ("consent", "allow"),
+        ];
+        if let Some(token) = token {
+            form.push(("csrf_token", token));
+        }
+        let response = common::post_form_with_cookie(&app, "/authorize", &form, cookie).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(response.headers().get(header::SET_COOKIE).is_none());
+        assert_eq!(common::body_json(response).await["error"], "access_denied");
+    }
+}
+
+#[tokio::test]
+async fn test_tokens_are_issued_to_the_logged_in_user() {
+    let app = app();
+    let (client_id, client_secret) = common::register_client(&app, metadata()).await;
+
+    let response = log_in(&app, &client_id, common::USERNAME, common::PASSWORD).await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    session_cookie(&response);
+    let code = common::redirect_param(&response, "code").unwrap();
+
+    let (status, tokens) =
+        common::exchange_code(&app, &client_id, &client_secret, &code, &[]).await;
+    assert_eq!(status, StatusCode::OK);
+    let claims = common::jwt_claims(tokens["access_token"].as_str().unwrap());
+    assert_eq!(claims["sub"], common::USER_ID);
+    assert_eq!(claims["client_id"], client_id);
+}
+
+#[tokio::test]
+async fn test_session_cookie_skips_login() {
+    let app = app();
+    let (client_id, client_secret) = common::register_client(&app, metadata()).await;
+
+    let response = log_in(&app, &client_id, common::USERNAME, common::PASSWORD).await;
+    let cookie = session_cookie(&response);
+
+    let response = authorize_with_cookie(&app, &client_id, &format!("theme=dark; {cookie}")).await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    let code = common::redirect_param(&response, "code").unwrap();
+
+    let (status, tokens) =
+        common::exchange_code(&app, &client_id, &client_secret, &code, &[]).await;
+    assert_eq!(status, StatusCode::OK);
+    let claims = common::jwt_claims(tokens["access_token"].as_str().unwrap());
+    assert_eq!(claims["sub"], common::USER_ID);
+
+    // An unknown session is treated as no session
+    let response = authorize_with_cookie(&app, &client_id, "session=forged").await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_admin_creates_users() {
+    let app = app();
+    let (client_id, _) = common::register_client(&app, metadata()).await;
+
+    let create_user = |token: &str| {
+        Request::builder()
+            .method("POST")
+            .uri("/admin/users")
+            .header(header::AUTHORIZATION, format!("Bearer {token}"))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                json!({ "username": "bob", "password": "hunter2" }).to_string(),
+            ))
+            .unwrap()
+    };
+
+    let response = app.clone().oneshot(create_user("wrong")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = app.clone().oneshot(create_user(ADMIN_TOKEN)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let user_id = common::body_json(response).await["user_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let response = app.clone().oneshot(create_user(ADMIN_TOKEN)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = log_in(&app, &client_id, "bob", "hunter2").await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert_ne!(user_id, common::USER_ID);
+}
+
+#[tokio::test]
+async fn test_passwords_are_stored_hashed() {
+    let state = common::app_state();
+    let user = state
+        .lock()
+        .unwrap()
+        .storage
+        .get_user(common::USER_ID)
+        .unwrap()
+        .unwrap();
+    assert!(user.password_hash.starts_with("$argon2id$"));
+    assert!(!user.password_hash.contains(common::PASSWORD));
+}
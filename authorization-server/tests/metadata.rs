@@ -0,0 +1,92 @@
+mod common;
+
+use authorization_server::{config::Config, router::router_with_state};
+use axum::http::StatusCode;
+use serde_json::{Value, json};
+
+async fn fetch_metadata(config: Config) -> Value {
+    let app = router_with_state(common::app_state_with(config));
+    let response = common::get(&app, "/.well-known/oauth-authorization-server").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    common::body_json(response).await
+}
+
+#[tokio::test]
+async fn test_metadata_advertises_endpoints_and_capabilities() {
+    let metadata = fetch_metadata(common::test_config()).await;
+
+    assert_eq!(metadata["issuer"], "http://localhost:3033");
+    assert_eq!(
+        metadata["authorization_endpoint"],
+        "http://localhost:3033/authorize"
+    );
+    assert_eq!(metadata["token_endpoint"], "http://localhost:3033/token");
+    assert_eq!(metadata["jwks_uri"], "http://localhost:3033/jwks.json");
+    assert_eq!(
+        metadata["registration_endpoint"],
+        "http://localhost:3033/register"
+    );
+    assert_eq!(
+        metadata["introspection_endpoint"],
+        "http://localhost:3033/introspect"
+    );
+    assert_eq!(
+        metadata["scopes_supported"],
+        json!(["openid", "read", "write"])
+    );
+    assert_eq!(metadata["response_types_supported"], json!(["code"]));
+    assert_eq!(
+        metadata["grant_types_supported"],
+        json!(["authorization_code", "refresh_token", "client_credentials"])
+    );
+    assert_eq!(
+        metadata["token_endpoint_auth_methods_supported"],
+        json!(["client_secret_post", "none"])
+    );
+    assert_eq!(
+        metadata["code_challenge_methods_supported"],
+        json!(["S256", "plain"])
+    );
+}
+
+#[tokio::test]
+async fn test_metadata_follows_configuration() {
+    let config = Config {
+        issuer: "https://auth.example.com/".to_string(),
+        supported_scopes: "profile email".to_string(),
+        ..common::test_config()
+    };
+    let metadata = fetch_metadata(config).await;
+
+    // RFC 8414, Section 2: the issuer has no trailing slash, and endpoints are built from it
+    assert_eq!(metadata["issuer"], "https://auth.example.com");
+    assert_eq!(metadata["token_endpoint"], "https://auth.example.com/token");
+    assert_eq!(metadata["scopes_supported"], json!(["profile", "email"]));
+}
+
+#[tokio::test]
+async fn test_openid_configuration_is_served() {
+    let app = router_with_state(common::app_state());
+    let response = common::get(&app, "/.well-known/openid-configuration").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let metadata = common::body_json(response).await;
+
+    // OpenID Connect Discovery 1.0, Section 3: the required provider metadata
+    assert_eq!(metadata["issuer"], "http://localhost:3033");
+    assert_eq!(
+        metadata["userinfo_endpoint"],
+        "http://localhost:3033/userinfo"
+    );
+    assert_eq!(metadata["jwks_uri"], "http://localhost:3033/jwks.json");
+    assert_eq!(metadata["subject_types_supported"], json!(["public"]));
+    assert_eq!(
+        metadata["id_token_signing_alg_values_supported"],
+        json!(["RS256"])
+    );
+    assert!(
+        metadata["scopes_supported"]
+            .as_array()
+            .unwrap()
+            .contains(&json!("openid"))
+    );
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use authorization_server::router::router_with_state;
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, Response, StatusCode, header},
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tower::util::ServiceExt;
+
+async fn userinfo(app: &Router, access_token: Option<&str>) -> Response<Body> {
+    let mut request = Request::builder().uri("/userinfo");
+    if let Some(access_token) = access_token {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {access_token}"));
+    }
+    app.clone()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_id_token_is_issued_for_openid_scope() {
+    let app = router_with_state(common::app_state());
+    let (client_id, client_secret) =
+        common::register_client(&app, json!({ "scope": "openid read" })).await;
+
+    let tokens = common::tokens(
+        &app,
+        &client_id,
+        &client_secret,
+        "&scope=openid%20read&nonce=n-0S6_WzA2Mj",
+    )
+    .await;
+    let access_token = tokens["access_token"].as_str().unwrap();
+    let id_token = tokens["id_token"].as_str().unwrap();
+
+    let claims = common::jwt_claims(id_token);
+    assert_eq!(claims["iss"], "http://localhost:3033");
+    assert_eq!(claims["aud"], client_id);
+    assert_eq!(claims["nonce"], "n-0S6_WzA2Mj");
+    assert_eq!(claims["sub"], common::USER_ID);
+    assert!(claims["auth_time"].as_u64().unwrap() <= claims["iat"].as_u64().unwrap());
+    assert!(claims["exp"].as_u64().unwrap() > claims["iat"].as_u64().unwrap());
+
+    // OIDC Core, Section 3.1.3.6: at_hash is the left half of the access token's SHA-256 hash
+    let hash = Sha256::digest(access_token.as_bytes());
+    assert_eq!(claims["at_hash"], URL_SAFE_NO_PAD.encode(&hash[..16]));
+
+    // The ID token is signed with the published key
+    let header = jsonwebtoken::decode_header(id_token).unwrap();
+    let jwks = common::body_json(common::get(&app, "/jwks.json").await).await;
+    assert_eq!(jwks["keys"][0]["kid"], header.kid.unwrap());
+}
+
+#[tokio::test]
+async fn test_id_token_is_not_issued_without_openid_scope() {
+    let app = router_with_state(common::app_state());
+    let (client_id, client_secret) =
+        common::register_client(&app, json!({ "scope": "openid read" })).await;
+
+    let tokens = common::tokens(&app, &client_id, &client_secret, "&scope=read").await;
+    assert!(tokens.get("id_token").is_none());
+}
+
+#[tokio::test]
+async fn test_userinfo_returns_the_subject() {
+    let app = router_with_state(common::app_state());
+    let (client_id, client_secret) =
+        common::register_client(&app, json!({ "scope": "openid read" })).await;
+    let tokens = common::tokens(&app, &client_id, &client_secret, "&scope=openid").await;
+    let access_token = tokens["access_token"].as_str().unwrap();
+
+    let response = userinfo(&app, Some(access_token)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let userinfo = common::body_json(response).await;
+    assert_eq!(
+        userinfo["sub"],
+        common::jwt_claims(tokens["id_token"].as_str().unwrap())["sub"]
+    );
+}
+
+#[tokio::test]
+async fn test_userinfo_requires_a_valid_openid_access_token() {
+    let app = router_with_state(common::app_state());
+    let (client_id, client_secret) =
+        common::register_client(&app, json!({ "scope": "openid read" })).await;
+
+    let response = userinfo(&app, None).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = userinfo(&app, Some("not-a-jwt")).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        response.headers()[header::WWW_AUTHENTICATE],
+        r#"Bearer error="invalid_token""#
+    );
+
+    let read_tokens = common::tokens(&app, &client_id, &client_secret, "&scope=read").await;
+    let response = userinfo(&app, read_tokens["access_token"].as_str()).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(
+        response.headers()[header::WWW_AUTHENTICATE],
+        r#"Bearer error="insufficient_scope""#
+    );
+
+    // Revoked access tokens are rejected
+    let openid_tokens = common::tokens(&app, &client_id, &client_secret, "&scope=openid").await;
+    let access_token = openid_tokens["access_token"].as_str().unwrap();
+    let response = common::post_form(
+        &app,
+        "/revoke",
+        &[
+            ("token", access_token),
+            ("client_id", &client_id),
+            ("client_secret", &client_secret),
+        ],
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = userinfo(&app, Some(access_token)).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,203 @@
+mod common;
+
+use authorization_server::{config::Config, router::router_with_state};
+use axum::http::StatusCode;
+use serde_json::json;
+
+// CODE_CHALLENGE = BASE64URL(SHA256(CODE_VERIFIER))
+const CODE_VERIFIER: &str = "dBjftJeZ4CVP-mJ92K9qgdmbPwS4GCnQYh7oX1pHoOk";
+const CODE_CHALLENGE: &str = "5yc_jTHjyIPLfLUdjJ6RVmctPU_M7b7XzugBLPqm2fw";
+
+async fn register_public_client(app: &axum::Router) -> String {
+    let registration = common::register(
+        app,
+        json!({
+            "client_name": "Public Client",
+            "redirect_uris": ["http://localhost/callback"],
+            "token_endpoint_auth_method": "none"
+        }),
+    )
+    .await;
+    assert!(registration.get("client_secret").is_none());
+    registration["client_id"].as_str().unwrap().to_string()
+}
+
+async fn authorize(
+    app: &axum::Router,
+    client_id: &str,
+    pkce: &str,
+) -> axum::http::Response<axum::body::Body> {
+    common::authorize(
+        app,
+        &format!(
+            "/authorize?client_id={client_id}&response_type=code&redirect_uri=http://localhost/callback{pkce}"
+        ),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_s256_code_verifier_is_accepted() {
+    let app = router_with_state(common::app_state());
+    let client_id = register_public_client(&app).await;
+
+    let response = authorize(
+        &app,
+        &client_id,
+        &format!("&code_challenge={CODE_CHALLENGE}&code_challenge_method=S256"),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    let code = common::redirect_param(&response, "code").unwrap();
+
+    let response = common::post_form(
+        &app,
+        "/token",
+        &[
+            ("grant_type", "authorization_code"),
+            ("code", &code),
+            ("client_id", &client_id),
+            ("redirect_uri", "http://localhost/callback"),
+            ("code_verifier", CODE_VERIFIER),
+        ],
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let token_response = common::body_json(response).await;
+    assert!(token_response["access_token"].as_str().is_some());
+}
+
+#[tokio::test]
+async fn test_plain_code_verifier_is_accepted() {
+    let app = router_with_state(common::app_state());
+    let client_id = register_public_client(&app).await;
+
+    let response = authorize(
+        &app,
+        &client_id,
+        &format!("&code_challenge={CODE_VERIFIER}"),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    let code = common::redirect_param(&response, "code").unwrap();
+
+    let response = common::post_form(
+        &app,
+        "/token",
+        &[
+            ("grant_type", "authorization_code"),
+            ("code", &code),
+            ("client_id", &client_id),
+            ("redirect_uri", "http://localhost/callback"),
+            ("code_verifier", CODE_VERIFIER),
+        ],
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_wrong_code_verifier_is_rejected() {
+    let app = router_with_state(common::app_state());
+    let client_id = register_public_client(&app).await;
+
+    for form in [
+        vec![("code_verifier", "x".repeat(43))],
+        vec![("code_verifier", CODE_CHALLENGE.to_string())],
+        vec![],
+    ] {
+        let response = authorize(
+            &app,
+            &client_id,
+            &format!("&code_challenge={CODE_CHALLENGE}&code_challenge_method=S256"),
+        )
+        .await;
+        let code = common::redirect_param(&response, "code").unwrap();
+
+        let mut request = vec![
+            ("grant_type", "authorization_code".to_string()),
+            ("code", code.clone()),
+            ("client_id", client_id.clone()),
+            ("redirect_uri", "http://localhost/callback".to_string()),
+        ];
+        request.extend(form);
+        let request: Vec<(&str, &str)> = request.iter().map(|(k, v)| (*k, v.as_str())).collect();
+
+        let response = common::post_form(&app, "/token", &request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(common::body_json(response).await["error"], "invalid_grant");
+    }
+}
+
+#[tokio::test]
+async fn test_failed_verification_does_not_burn_code() {
+    let app = router_with_state(common::app_state());
+    let client_id = register_public_client(&app).await;
+
+    let response = authorize(
+        &app,
+        &client_id,
+        &format!("&code_challenge={CODE_CHALLENGE}&code_challenge_method=S256"),
+    )
+    .await;
+    let code = common::redirect_param(&response, "code").unwrap();
+
+    let exchange = |code_verifier: Option<&'static str>| {
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code.as_str()),
+            ("client_id", client_id.as_str()),
+            ("redirect_uri", "http://localhost/callback"),
+        ];
+        form.extend(code_verifier.map(|code_verifier| ("code_verifier", code_verifier)));
+        form
+    };
+
+    let response = common::post_form(&app, "/token", &exchange(None)).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = common::post_form(&app, "/token", &exchange(Some(CODE_VERIFIER))).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_public_client_without_pkce_is_rejected_by_policy() {
+    let app = router_with_state(common::app_state());
+    let client_id = register_public_client(&app).await;
+
+    let response = authorize(&app, &client_id, "").await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert_eq!(
+        common::redirect_param(&response, "error").unwrap(),
+        "invalid_request"
+    );
+
+    // The policy can be relaxed for deployments that still need it
+    let app = router_with_state(common::app_state_with(Config {
+        require_pkce_for_public_clients: false,
+        ..common::test_config()
+    }));
+    let client_id = register_public_client(&app).await;
+
+    let response = authorize(&app, &client_id, "").await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+}
+
+#[tokio::test]
+async fn test_invalid_pkce_parameters_are_rejected() {
+    let app = router_with_state(common::app_state());
+    let client_id = register_public_client(&app).await;
+
+    for pkce in [
+        format!("&code_challenge={CODE_CHALLENGE}&code_challenge_method=S512"),
+        "&code_challenge=too-short&code_challenge_method=S256".to_string(),
+        "&code_challenge_method=S256".to_string(),
+    ] {
+        let response = authorize(&app, &client_id, &pkce).await;
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            common::redirect_param(&response, "error").unwrap(),
+            "invalid_request"
+        );
+    }
+}
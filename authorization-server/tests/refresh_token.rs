@@ -0,0 +1,139 @@
+mod common;
+
+use authorization_server::{router::router_with_state, token::RefreshToken};
+use axum::{Router, http::StatusCode};
+use serde_json::{Value, json};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+async fn refresh(
+    app: &Router,
+    client_id: &str,
+    client_secret: &str,
+    refresh_token: &str,
+) -> (StatusCode, Value) {
+    let response = common::post_form(
+        app,
+        "/token",
+        &[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+            ("client_id", client_id),
+            ("client_secret", client_secret),
+        ],
+    )
+    .await;
+    (response.status(), common::body_json(response).await)
+}
+
+#[tokio::test]
+async fn test_refresh_token_is_rotated() {
+    let app = router_with_state(common::app_state());
+    let (client_id, client_secret, tokens) = common::client_with_tokens(&app, json!({}), "").await;
+    let refresh_token = tokens["refresh_token"].as_str().unwrap();
+
+    let (status, refreshed) = refresh(&app, &client_id, &client_secret, refresh_token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(refreshed["access_token"].as_str().is_some());
+    assert_eq!(refreshed["token_type"], "Bearer");
+
+    let rotated = refreshed["refresh_token"].as_str().unwrap();
+    assert_ne!(rotated, refresh_token);
+
+    let (status, _) = refresh(&app, &client_id, &client_secret, rotated).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_refresh_token_reuse_revokes_family() {
+    let app = router_with_state(common::app_state());
+    let (client_id, client_secret, tokens) = common::client_with_tokens(&app, json!({}), "").await;
+    let refresh_token = tokens["refresh_token"].as_str().unwrap();
+
+    let (_, refreshed) = refresh(&app, &client_id, &client_secret, refresh_token).await;
+    let rotated = refreshed["refresh_token"].as_str().unwrap();
+
+    // Replaying the already rotated token is rejected...
+    let (status, error) = refresh(&app, &client_id, &client_secret, refresh_token).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["error"], "invalid_grant");
+
+    // ...and revokes the token that replaced it
+    let (status, error) = refresh(&app, &client_id, &client_secret, rotated).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["error"], "invalid_grant");
+}
+
+#[tokio::test]
+async fn test_refresh_token_is_bound_to_client() {
+    let app = router_with_state(common::app_state());
+    let (_, _, tokens) = common::client_with_tokens(&app, json!({}), "").await;
+    let (other_client_id, other_client_secret, _) =
+        common::client_with_tokens(&app, json!({}), "").await;
+    let refresh_token = tokens["refresh_token"].as_str().unwrap();
+
+    let (status, error) =
+        refresh(&app, &other_client_id, &other_client_secret, refresh_token).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["error"], "invalid_grant");
+
+    let (status, error) = refresh(&app, &other_client_id, "wrong-secret", refresh_token).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(error["error"], "invalid_client");
+}
+
+/// Builds a used refresh token of `family_id` that expires `expires_in` seconds from now.
+fn refresh_token(family_id: &str, expires_in: i64) -> RefreshToken {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    RefreshToken {
+        client_id: "client".to_string(),
+        sub: "user".to_string(),
+        scope: "read".to_string(),
+        family_id: family_id.to_string(),
+        resource: None,
+        issued_at: now,
+        expires_at: now.saturating_add_signed(expires_in),
+        rotated: expires_in < 0,
+        revoked: false,
+    }
+}
+
+#[tokio::test]
+async fn test_expired_refresh_token_families_are_purged() {
+    let state = common::app_state();
+    {
+        let mut state = state.lock().unwrap();
+        for (token, family_id, expires_in) in [
+            ("expired-1", "expired", -100),
+            ("expired-2", "expired", -10),
+            ("rotated", "live", -100),
+            ("current", "live", 100),
+        ] {
+            state
+                .storage
+                .put_refresh_token(token, refresh_token(family_id, expires_in))
+                .unwrap();
+        }
+    }
+
+    // Issuing a refresh token purges the families that have expired
+    let app = router_with_state(state.clone());
+    common::client_with_tokens(&app, json!({}), "").await;
+
+    let state = state.lock().unwrap();
+    for (token, kept) in [
+        ("expired-1", false),
+        ("expired-2", false),
+        // Rotated tokens stay until their family expires, so that reuse is still detected
+        ("rotated", true),
+        ("current", true),
+    ] {
+        assert_eq!(
+            state.storage.get_refresh_token(token).unwrap().is_some(),
+            kept,
+            "{token}"
+        );
+    }
+}
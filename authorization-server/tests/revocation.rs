@@ -0,0 +1,236 @@
+mod common;
+
+use authorization_server::{
+    config::Config,
+    router::router_with_state,
+    token::{self, AccessToken},
+};
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode, header},
+};
+use serde_json::{Value, json};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tower::util::ServiceExt;
+
+const ADMIN_TOKEN: &str = "test-admin-token";
+
+async fn revoke(app: &Router, client_id: &str, client_secret: &str, token: &str) -> StatusCode {
+    common::post_form(
+        app,
+        "/revoke",
+        &[
+            ("token", token),
+            ("client_id", client_id),
+            ("client_secret", client_secret),
+        ],
+    )
+    .await
+    .status()
+}
+
+async fn introspect(app: &Router, client_id: &str, client_secret: &str, token: &str) -> Value {
+    let response = common::post_form(
+        app,
+        "/introspect",
+        &[
+            ("token", token),
+            ("client_id", client_id),
+            ("client_secret", client_secret),
+        ],
+    )
+    .await;
+    common::body_json(response).await
+}
+
+/// Returns the `jti`s listed at `/revoked` to the given resource server.
+async fn revoked_jtis(app: &Router, (client_id, client_secret): &(String, String)) -> Vec<String> {
+    let response = common::revoked_tokens(app, client_id, client_secret).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let revoked = common::body_json(response).await;
+    revoked["revoked"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|token| token["jti"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_revoked_access_token_is_denylisted() {
+    let state = common::app_state();
+    let app = router_with_state(state.clone());
+    let resource_server = common::register_resource_server(&state, &app).await;
+    let (client_id, client_secret) = common::register_client(&app, json!({})).await;
+    let tokens = common::tokens(&app, &client_id, &client_secret, "").await;
+    let access_token = tokens["access_token"].as_str().unwrap();
+    let jti = common::jwt_claims(access_token)["jti"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    assert!(revoked_jtis(&app, &resource_server).await.is_empty());
+    assert_eq!(
+        revoke(&app, &client_id, &client_secret, access_token).await,
+        StatusCode::OK
+    );
+
+    assert_eq!(revoked_jtis(&app, &resource_server).await, vec![jti]);
+    assert_eq!(
+        introspect(&app, &client_id, &client_secret, access_token).await,
+        json!({ "active": false })
+    );
+
+    // Revoking an access token leaves the refresh token usable
+    let refresh_token = tokens["refresh_token"].as_str().unwrap();
+    assert_eq!(
+        introspect(&app, &client_id, &client_secret, refresh_token).await["active"],
+        true
+    );
+}
+
+#[tokio::test]
+async fn test_revoked_refresh_token_revokes_its_family() {
+    let state = common::app_state();
+    let app = router_with_state(state.clone());
+    let resource_server = common::register_resource_server(&state, &app).await;
+    let (client_id, client_secret) = common::register_client(&app, json!({})).await;
+    let tokens = common::tokens(&app, &client_id, &client_secret, "").await;
+    let refresh_token = tokens["refresh_token"].as_str().unwrap();
+    let access_token = tokens["access_token"].as_str().unwrap();
+
+    let response = common::post_form(
+        &app,
+        "/revoke",
+        &[
+            ("token", refresh_token),
+            ("token_type_hint", "refresh_token"),
+            ("client_id", &client_id),
+            ("client_secret", &client_secret),
+        ],
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = common::post_form(
+        &app,
+        "/token",
+        &[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+            ("client_id", &client_id),
+            ("client_secret", &client_secret),
+        ],
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // The access token issued alongside the refresh token is revoked as well
+    assert_eq!(
+        introspect(&app, &client_id, &client_secret, access_token).await,
+        json!({ "active": false })
+    );
+    assert_eq!(revoked_jtis(&app, &resource_server).await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_clients_can_only_revoke_their_own_tokens() {
+    let state = common::app_state();
+    let app = router_with_state(state.clone());
+    let resource_server = common::register_resource_server(&state, &app).await;
+    let (client_id, client_secret) = common::register_client(&app, json!({})).await;
+    let (other_client_id, other_client_secret) = common::register_client(&app, json!({})).await;
+    let tokens = common::tokens(&app, &client_id, &client_secret, "").await;
+    let access_token = tokens["access_token"].as_str().unwrap();
+
+    assert_eq!(
+        revoke(&app, &other_client_id, &other_client_secret, access_token).await,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        revoke(&app, &client_id, "wrong-secret", access_token).await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert!(revoked_jtis(&app, &resource_server).await.is_empty());
+
+    // Unknown tokens are ignored (RFC 7009, Section 2.2)
+    assert_eq!(
+        revoke(&app, &client_id, &client_secret, "unknown-token").await,
+        StatusCode::OK
+    );
+}
+
+#[tokio::test]
+async fn test_revoked_tokens_are_only_listed_to_resource_servers_and_admins() {
+    let state = common::app_state_with(Config {
+        admin_token: Some(ADMIN_TOKEN.to_string()),
+        ..common::test_config()
+    });
+    let app = router_with_state(state.clone());
+    let (resource_server_id, resource_server_secret) =
+        common::register_resource_server(&state, &app).await;
+    let (client_id, client_secret) = common::register_client(&app, json!({})).await;
+
+    let response = common::get(&app, "/revoked").await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(common::body_json(response).await["error"], "invalid_client");
+
+    let response = common::revoked_tokens(&app, &resource_server_id, "wrong-secret").await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Other clients have no business knowing which tokens were revoked
+    let response = common::revoked_tokens(&app, &client_id, &client_secret).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(
+        common::body_json(response).await["error"],
+        "unauthorized_client"
+    );
+
+    let response = common::revoked_tokens(&app, &resource_server_id, &resource_server_secret).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    for (admin_token, status) in [
+        ("wrong", StatusCode::UNAUTHORIZED),
+        (ADMIN_TOKEN, StatusCode::OK),
+    ] {
+        let request = Request::builder()
+            .uri("/revoked")
+            .header(header::AUTHORIZATION, format!("Bearer {admin_token}"))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), status);
+    }
+}
+
+#[tokio::test]
+async fn test_expired_access_tokens_are_purged_periodically() {
+    let state = common::app_state();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    for (jti, expires_at) in [("expired", now - 1), ("active", now + 3600)] {
+        let access_token = AccessToken {
+            client_id: "client".to_string(),
+            sub: "user".to_string(),
+            family_id: None,
+            expires_at,
+            revoked: true,
+        };
+        state
+            .lock()
+            .unwrap()
+            .storage
+            .put_access_token(jti, access_token)
+            .unwrap();
+    }
+
+    token::spawn_access_token_purge(state.clone(), Duration::from_millis(10));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let state = state.lock().unwrap();
+    assert!(state.storage.get_access_token("expired").unwrap().is_none());
+    assert!(state.storage.get_access_token("active").unwrap().is_some());
+}
@@ -0,0 +1,178 @@
+mod common;
+
+use authorization_server::router::router_with_state;
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode},
+};
+use serde_json::{Value, json};
+use tower::util::ServiceExt;
+
+async fn register_client(app: &Router, scope: &str) -> (String, String) {
+    let registration = common::register(
+        app,
+        json!({
+            "client_name": "Test Client",
+            "redirect_uris": ["http://localhost/callback"],
+            "scope": scope
+        }),
+    )
+    .await;
+    (
+        registration["client_id"].as_str().unwrap().to_string(),
+        registration["client_secret"].as_str().unwrap().to_string(),
+    )
+}
+
+/// Runs the authorization code flow with the given `scope` query and returns the token response.
+async fn token_for_scope(app: &Router, client_id: &str, client_secret: &str, scope: &str) -> Value {
+    let response = common::authorize(
+        app,
+        &format!("/authorize?client_id={client_id}&response_type=code{scope}"),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    let code = common::redirect_param(&response, "code").unwrap();
+
+    let response = common::post_form(
+        app,
+        "/token",
+        &[
+            ("grant_type", "authorization_code"),
+            ("code", &code),
+            ("client_id", client_id),
+            ("client_secret", client_secret),
+        ],
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    common::body_json(response).await
+}
+
+#[tokio::test]
+async fn test_requested_scope_is_embedded_in_access_token() {
+    let app = router_with_state(common::app_state());
+    let (client_id, client_secret) = register_client(&app, "read write").await;
+
+    let token_response = token_for_scope(&app, &client_id, &client_secret, "&scope=write").await;
+    assert!(token_response.get("scope").is_none());
+
+    let claims = common::jwt_claims(token_response["access_token"].as_str().unwrap());
+    assert_eq!(claims["scope"], "write");
+}
+
+#[tokio::test]
+async fn test_narrowed_scope_is_echoed_in_token_response() {
+    let app = router_with_state(common::app_state());
+    let (client_id, client_secret) = register_client(&app, "read").await;
+
+    let token_response =
+        token_for_scope(&app, &client_id, &client_secret, "&scope=read%20write").await;
+    assert_eq!(token_response["scope"], "read");
+
+    let claims = common::jwt_claims(token_response["access_token"].as_str().unwrap());
+    assert_eq!(claims["scope"], "read");
+
+    // Without a requested scope, every registered scope is granted and echoed
+    let token_response = token_for_scope(&app, &client_id, &client_secret, "").await;
+    assert_eq!(token_response["scope"], "read");
+}
+
+#[tokio::test]
+async fn test_unallowed_scope_is_rejected_at_authorize() {
+    let app = router_with_state(common::app_state());
+    let (client_id, _) = register_client(&app, "read").await;
+
+    let response = common::get(
+        &app,
+        &format!("/authorize?client_id={client_id}&response_type=code&scope=write"),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert_eq!(
+        common::redirect_param(&response, "error").unwrap(),
+        "invalid_scope"
+    );
+}
+
+#[tokio::test]
+async fn test_unsupported_scope_is_rejected_at_register() {
+    let app = router_with_state(common::app_state());
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/register")
+        .header("Content-Type", "application/json")
+        .body(Body::from(
+            json!({
+                "client_name": "Test Client",
+                "redirect_uris": ["http://localhost/callback"],
+                "scope": "read admin"
+            })
+            .to_string(),
+        ))
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_refresh_token_grant_can_narrow_scope() {
+    let app = router_with_state(common::app_state());
+    let (client_id, client_secret) = register_client(&app, "read write").await;
+    let token_response = token_for_scope(&app, &client_id, &client_secret, "").await;
+    let refresh_token = token_response["refresh_token"].as_str().unwrap();
+
+    let response = common::post_form(
+        &app,
+        "/token",
+        &[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+            ("client_id", &client_id),
+            ("client_secret", &client_secret),
+            ("scope", "write admin"),
+        ],
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(common::body_json(response).await["error"], "invalid_scope");
+
+    let response = common::post_form(
+        &app,
+        "/token",
+        &[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+            ("client_id", &client_id),
+            ("client_secret", &client_secret),
+            ("scope", "write"),
+        ],
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let refreshed = common::body_json(response).await;
+    let claims = common::jwt_claims(refreshed["access_token"].as_str().unwrap());
+    assert_eq!(claims["scope"], "write");
+
+    // The rotated refresh token keeps the originally granted scope
+    let response = common::post_form(
+        &app,
+        "/token",
+        &[
+            ("grant_type", "refresh_token"),
+            (
+                "refresh_token",
+                refreshed["refresh_token"].as_str().unwrap(),
+            ),
+            ("client_id", &client_id),
+            ("client_secret", &client_secret),
+        ],
+    )
+    .await;
+    let refreshed = common::body_json(response).await;
+    let claims = common::jwt_claims(refreshed["access_token"].as_str().unwrap());
+    assert_eq!(claims["scope"], "read write");
+}
@@ -0,0 +1,337 @@
+mod common;
+
+use authorization_server::{
+    authorize::AuthorizationCode,
+    config::Config,
+    router::router_with_state,
+    storage::{AuthorizationCodeStore, MemoryStorage, RefreshTokenStore, SqliteStorage, Storage},
+    token::{AccessToken, RefreshToken},
+};
+use axum::{Router, http::StatusCode};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+/// Returns the path of a fresh SQLite database in the temporary directory.
+fn database_path() -> PathBuf {
+    std::env::temp_dir().join(format!("authorization-server-{}.db", uuid::Uuid::new_v4()))
+}
+
+/// Builds an app backed by the SQLite database at `path`, as a freshly started server would.
+fn sqlite_app(path: &Path) -> Router {
+    let config = Config {
+        sqlite_path: Some(path.to_string_lossy().into_owned()),
+        ..common::test_config()
+    };
+    router_with_state(common::app_state_with(config))
+}
+
+#[tokio::test]
+async fn test_sqlite_storage_survives_restart() {
+    let path = database_path();
+
+    // Register a client and obtain an authorization code from the first instance
+    let app = sqlite_app(&path);
+    let registration = common::register(
+        &app,
+        json!({
+            "client_name": "Test Client",
+            "redirect_uris": ["http://localhost/callback"]
+        }),
+    )
+    .await;
+    let client_id = registration["client_id"].as_str().unwrap().to_string();
+    let client_secret = registration["client_secret"].as_str().unwrap().to_string();
+
+    let response = common::authorize(
+        &app,
+        &format!("/authorize?client_id={client_id}&response_type=code"),
+    )
+    .await;
+    let code = common::redirect_param(&response, "code").unwrap();
+    drop(app);
+
+    // Redeem the code against a second instance sharing the database
+    let app = sqlite_app(&path);
+    let response = common::post_form(
+        &app,
+        "/token",
+        &[
+            ("grant_type", "authorization_code"),
+            ("code", &code),
+            ("client_id", &client_id),
+            ("client_secret", &client_secret),
+        ],
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let tokens = common::body_json(response).await;
+    let refresh_token = tokens["refresh_token"].as_str().unwrap().to_string();
+    drop(app);
+
+    // Rotate the refresh token on a third instance, then detect its reuse on a fourth
+    let refresh = [
+        ("grant_type", "refresh_token"),
+        ("refresh_token", refresh_token.as_str()),
+        ("client_id", client_id.as_str()),
+        ("client_secret", client_secret.as_str()),
+    ];
+    let response = common::post_form(&sqlite_app(&path), "/token", &refresh).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = common::post_form(&sqlite_app(&path), "/token", &refresh).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(common::body_json(response).await["error"], "invalid_grant");
+
+    std::fs::remove_file(&path).ok();
+}
+
+#[tokio::test]
+async fn test_sqlite_storage_rejects_code_replay() {
+    let path = database_path();
+    let app = sqlite_app(&path);
+
+    let registration = common::register(
+        &app,
+        json!({
+            "client_name": "Test Client",
+            "redirect_uris": ["http://localhost/callback"]
+        }),
+    )
+    .await;
+    let client_id = registration["client_id"].as_str().unwrap();
+    let client_secret = registration["client_secret"].as_str().unwrap();
+
+    let response = common::authorize(
+        &app,
+        &format!("/authorize?client_id={client_id}&response_type=code"),
+    )
+    .await;
+    let code = common::redirect_param(&response, "code").unwrap();
+
+    let exchange = [
+        ("grant_type", "authorization_code"),
+        ("code", code.as_str()),
+        ("client_id", client_id),
+        ("client_secret", client_secret),
+    ];
+    let response = common::post_form(&app, "/token", &exchange).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = common::post_form(&sqlite_app(&path), "/token", &exchange).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    std::fs::remove_file(&path).ok();
+}
+
+#[tokio::test]
+async fn test_sqlite_storage_deletes_clients() {
+    let path = database_path();
+    let app = sqlite_app(&path);
+
+    let registration = common::register(
+        &app,
+        json!({
+            "client_name": "Test Client",
+            "redirect_uris": ["http://localhost/callback"]
+        }),
+    )
+    .await;
+    let client_id = registration["client_id"].as_str().unwrap();
+    let token = registration["registration_access_token"].as_str().unwrap();
+
+    let delete = |app: Router| {
+        let request = axum::http::Request::builder()
+            .method("DELETE")
+            .uri(format!("/register/{client_id}"))
+            .header("Authorization", format!("Bearer {token}"))
+            .body(axum::body::Body::empty())
+            .unwrap();
+        tower::util::ServiceExt::oneshot(app, request)
+    };
+    let response = delete(sqlite_app(&path)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    // The client is gone for every instance sharing the database
+    let response = delete(app).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    std::fs::remove_file(&path).ok();
+}
+
+/// Stores a refresh token of `family_id` that expires `expires_in` seconds from now.
+fn put_refresh_token(storage: &mut dyn Storage, token: &str, family_id: &str, expires_in: i64) {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    storage
+        .put_refresh_token(
+            token,
+            RefreshToken {
+                client_id: "client".to_string(),
+                sub: "user".to_string(),
+                scope: "read".to_string(),
+                family_id: family_id.to_string(),
+                resource: None,
+                issued_at: now,
+                expires_at: now.saturating_add_signed(expires_in),
+                rotated: expires_in < 0,
+                revoked: false,
+            },
+        )
+        .unwrap();
+}
+
+#[test]
+fn test_expired_refresh_token_families_are_purged() {
+    let path = database_path();
+    let backends: [Box<dyn Storage>; 2] = [
+        Box::new(MemoryStorage::default()),
+        Box::new(SqliteStorage::open(&path).unwrap()),
+    ];
+
+    for mut storage in backends {
+        put_refresh_token(storage.as_mut(), "expired-1", "expired", -100);
+        put_refresh_token(storage.as_mut(), "expired-2", "expired", -10);
+        put_refresh_token(storage.as_mut(), "rotated", "live", -100);
+        put_refresh_token(storage.as_mut(), "current", "live", 100);
+
+        storage.remove_expired_refresh_tokens().unwrap();
+
+        for (token, kept) in [
+            ("expired-1", false),
+            ("expired-2", false),
+            // Rotated tokens stay until their family expires, so that reuse is still detected
+            ("rotated", true),
+            ("current", true),
+        ] {
+            assert_eq!(
+                storage.get_refresh_token(token).unwrap().is_some(),
+                kept,
+                "{token}"
+            );
+        }
+    }
+
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn test_tokens_and_consents_of_a_client_are_revoked() {
+    let path = database_path();
+    let backends: [Box<dyn Storage>; 2] = [
+        Box::new(MemoryStorage::default()),
+        Box::new(SqliteStorage::open(&path).unwrap()),
+    ];
+
+    for mut storage in backends {
+        for client_id in ["client", "other"] {
+            let refresh_token = RefreshToken {
+                client_id: client_id.to_string(),
+                sub: "user".to_string(),
+                scope: "read".to_string(),
+                family_id: client_id.to_string(),
+                resource: None,
+                issued_at: 0,
+                expires_at: u64::MAX / 2,
+                rotated: false,
+                revoked: false,
+            };
+            storage.put_refresh_token(client_id, refresh_token).unwrap();
+            let access_token = AccessToken {
+                client_id: client_id.to_string(),
+                sub: "user".to_string(),
+                family_id: None,
+                expires_at: u64::MAX / 2,
+                revoked: false,
+            };
+            storage.put_access_token(client_id, access_token).unwrap();
+            storage
+                .put_consent("user", client_id, vec!["read".to_string()])
+                .unwrap();
+        }
+
+        storage.revoke_client_refresh_tokens("client").unwrap();
+        storage.revoke_client_access_tokens("client").unwrap();
+        storage.remove_client_consents("client").unwrap();
+
+        for (client_id, revoked) in [("client", true), ("other", false)] {
+            let refresh_token = storage.get_refresh_token(client_id).unwrap().unwrap();
+            assert_eq!(refresh_token.revoked, revoked, "{client_id}");
+            let access_token = storage.get_access_token(client_id).unwrap().unwrap();
+            assert_eq!(access_token.revoked, revoked, "{client_id}");
+            let consent = storage.get_consent("user", client_id).unwrap();
+            assert_eq!(consent.is_none(), revoked, "{client_id}");
+        }
+        let revoked: Vec<_> = storage
+            .revoked_access_tokens()
+            .unwrap()
+            .into_iter()
+            .map(|(jti, _)| jti)
+            .collect();
+        assert_eq!(revoked, vec!["client".to_string()]);
+    }
+
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn test_sqlite_redeems_codes_and_rotates_refresh_tokens_once_across_instances() {
+    let path = database_path();
+    let mut storage = SqliteStorage::open(&path).unwrap();
+    storage
+        .put_code(
+            "code",
+            AuthorizationCode {
+                client_id: "client".to_string(),
+                sub: "user".to_string(),
+                auth_time: 0,
+                issued_at: 0,
+                redirect_uri: None,
+                requested_scope: None,
+                scopes: vec!["read".to_string()],
+                state: None,
+                code_challenge: None,
+                redeemed: false,
+                refresh_token_family: None,
+                resource: None,
+                nonce: None,
+            },
+        )
+        .unwrap();
+    put_refresh_token(&mut storage, "token", "family", 100);
+
+    // Replicas sharing the database race to redeem the same code and rotate the same token
+    let replicas: Vec<_> = (0..4)
+        .map(|replica| {
+            let mut storage = SqliteStorage::open(&path).unwrap();
+            std::thread::spawn(move || {
+                (
+                    storage
+                        .redeem_code("code", &format!("family-{replica}"))
+                        .unwrap(),
+                    storage.rotate_refresh_token("token").unwrap(),
+                )
+            })
+        })
+        .collect();
+    let results: Vec<(bool, bool)> = replicas
+        .into_iter()
+        .map(|replica| replica.join().unwrap())
+        .collect();
+
+    assert_eq!(results.iter().filter(|(redeemed, _)| *redeemed).count(), 1);
+    assert_eq!(results.iter().filter(|(_, rotated)| *rotated).count(), 1);
+
+    let code = storage.get_code("code").unwrap().unwrap();
+    assert!(code.redeemed);
+    assert!(code.refresh_token_family.unwrap().starts_with("family-"));
+    assert!(storage.get_refresh_token("token").unwrap().unwrap().rotated);
+
+    // Revoked tokens cannot be rotated either
+    put_refresh_token(&mut storage, "revoked", "other", 100);
+    storage.revoke_refresh_token_family("other").unwrap();
+    assert!(!storage.rotate_refresh_token("revoked").unwrap());
+
+    std::fs::remove_file(&path).ok();
+}
@@ -2,6 +2,10 @@ use reqwest::{Client, redirect::Policy};
 use serde::{Deserialize, Serialize};
 use url::Url;
 
+/// The demo user the client logs in as.
+const USERNAME: &str = "demo";
+const PASSWORD: &str = "demo-password";
+
 #[tokio::main]
 async fn main() {
     // Create an HTTP client with redirect following disabled
@@ -10,41 +14,76 @@ async fn main() {
         .build()
         .unwrap();
 
+    // Step 0: Discover the Authorization Server endpoints
+    let metadata = discover(&client).await.unwrap();
+
     // Step 1: Register the client
-    let registration_response = register_client(&client).await.unwrap();
+    let registration_response = register_client(&client, &metadata).await.unwrap();
     let client_id = registration_response.client_id;
     let client_secret = registration_response.client_secret;
 
     println!("Registered Client ID: {}", client_id);
     println!("Registered Client Secret: {}", client_secret);
 
-    // Step 2: Authorize the client
-    let authorization_code = authorize_client(&client, &client_id).await.unwrap();
+    // Step 2: Create the demo user (requires the Authorization Server's ADMIN_TOKEN)
+    let admin_token =
+        std::env::var("ADMIN_TOKEN").unwrap_or_else(|_| "unsafe-admin-token".to_string());
+    create_demo_user(&client, &admin_token).await.unwrap();
+
+    // Step 3: Log in as the demo user to authorize the client
+    let authorization_code = authorize_client(&client, &metadata, &client_id)
+        .await
+        .unwrap();
     println!("Authorization Code: {}", authorization_code);
 
-    // Step 3: Exchange the authorization code for an access token
-    let access_token =
-        exchange_code_for_token(&client, &client_id, &client_secret, &authorization_code)
-            .await
-            .unwrap();
+    // Step 4: Exchange the authorization code for an access token
+    let access_token = exchange_code_for_token(
+        &client,
+        &metadata,
+        &client_id,
+        &client_secret,
+        &authorization_code,
+    )
+    .await
+    .unwrap();
     println!("Access Token: {}", access_token);
 
-    // Step 4: Access a protected resource
+    // Step 5: Access a protected resource
     let resource = access_protected_resource(&client, &access_token)
         .await
         .unwrap();
     println!("Protected Resource: {}", resource);
 }
 
+/// The subset of the Authorization Server Metadata (RFC 8414) used by this client.
+#[derive(Deserialize)]
+struct AuthorizationServerMetadata {
+    authorization_endpoint: String,
+    token_endpoint: String,
+    registration_endpoint: String,
+}
+
+async fn discover(client: &Client) -> Result<AuthorizationServerMetadata, reqwest::Error> {
+    client
+        .get("http://localhost:3033/.well-known/oauth-authorization-server")
+        .send()
+        .await?
+        .json::<AuthorizationServerMetadata>()
+        .await
+}
+
 #[derive(Serialize, Deserialize)]
 struct ClientRegistrationResponse {
     client_id: String,
     client_secret: String,
 }
 
-async fn register_client(client: &Client) -> Result<ClientRegistrationResponse, reqwest::Error> {
+async fn register_client(
+    client: &Client,
+    metadata: &AuthorizationServerMetadata,
+) -> Result<ClientRegistrationResponse, reqwest::Error> {
     let response = client
-        .post("http://localhost:3033/register")
+        .post(&metadata.registration_endpoint)
         .json(&serde_json::json!({
             "client_name": "Test Client",
             "redirect_uris": ["http://localhost/callback"]
@@ -57,15 +96,76 @@ async fn register_client(client: &Client) -> Result<ClientRegistrationResponse,
     Ok(response)
 }
 
-async fn authorize_client(client: &Client, client_id: &str) -> Result<String, reqwest::Error> {
+async fn create_demo_user(client: &Client, admin_token: &str) -> Result<(), reqwest::Error> {
     let response = client
-        .get("http://localhost:3033/authorize")
-        .query(&[
-            ("client_id", client_id),
-            ("response_type", "code"),
-            ("state", "xyz"),
-            ("redirect_uri", "http://localhost/callback"),
-        ])
+        .post("http://localhost:3033/admin/users")
+        .bearer_auth(admin_token)
+        .json(&serde_json::json!({
+            "username": USERNAME,
+            "password": PASSWORD
+        }))
+        .send()
+        .await?;
+
+    // The user already exists if the client ran before
+    if response.status() != reqwest::StatusCode::CONFLICT {
+        response.error_for_status()?;
+    }
+    Ok(())
+}
+
+async fn authorize_client(
+    client: &Client,
+    metadata: &AuthorizationServerMetadata,
+    client_id: &str,
+) -> Result<String, reqwest::Error> {
+    let params = [
+        ("client_id", client_id),
+        ("response_type", "code"),
+        ("state", "xyz"),
+        ("redirect_uri", "http://localhost/callback"),
+    ];
+
+    // Without a session, the Authorization Server answers with its login form
+    let response = client
+        .get(&metadata.authorization_endpoint)
+        .query(&params)
+        .send()
+        .await?;
+    println!("Login Form Status Code: {}", response.status());
+
+    // The form must be submitted with the CSRF cookie and the anti-CSRF token it carries
+    let csrf_cookie = response
+        .headers()
+        .get_all(reqwest::header::SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok()?.split(';').next())
+        .find(|cookie| cookie.starts_with("csrf="))
+        .unwrap_or_default()
+        .to_string();
+    let login_form = response.text().await?;
+    let csrf_token = login_form
+        .split(r#"name="csrf_token" value=""#)
+        .nth(1)
+        .and_then(|rest| rest.split('"').next())
+        .unwrap_or_default();
+
+    // Submit the login form like a browser would, allowing the client on the consent screen
+    let response = client
+        .post(&metadata.authorization_endpoint)
+        .header(reqwest::header::COOKIE, csrf_cookie)
+        .form(
+            &[
+                params.as_slice(),
+                &[
+                    ("username", USERNAME),
+                    ("password", PASSWORD),
+                    ("consent", "allow"),
+                    ("csrf_token", csrf_token),
+                ],
+            ]
+            .concat(),
+        )
         .send()
         .await?;
 
@@ -98,17 +198,19 @@ struct TokenResponse {
 
 async fn exchange_code_for_token(
     client: &Client,
+    metadata: &AuthorizationServerMetadata,
     client_id: &str,
     client_secret: &str,
     code: &str,
 ) -> Result<String, reqwest::Error> {
     let response = client
-        .post("http://localhost:3033/token")
+        .post(&metadata.token_endpoint)
         .form(&[
             ("grant_type", "authorization_code"),
             ("code", code),
             ("client_id", client_id),
             ("client_secret", client_secret),
+            ("redirect_uri", "http://localhost/callback"),
         ])
         .send()
         .await?
@@ -0,0 +1,238 @@
+use axum::extract::Query;
+use axum::routing::get;
+use axum::{Router, response::IntoResponse};
+use reqwest::{Client, header, redirect::Policy};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::env;
+use tokio::net::TcpListener;
+use tokio::task;
+use tokio::time::{Duration, sleep};
+
+static INIT: std::sync::Once = std::sync::Once::new();
+
+const ADMIN_TOKEN: &str = "integration-test-admin-token";
+const USERNAME: &str = "alice";
+const PASSWORD: &str = "correct horse battery staple";
+
+/// Returns the value of the query parameter `name` of `url`.
+fn query_param(url: &url::Url, name: &str) -> String {
+    url.query_pairs()
+        .find(|(key, _)| key == name)
+        .unwrap()
+        .1
+        .to_string()
+}
+
+#[tokio::test]
+async fn test_complete_authorization_code_flow() {
+    // Initialize tracing (only once)
+    INIT.call_once(|| {
+        tracing_subscriber::fmt::init();
+    });
+
+    // Step 1: Reserve the addresses of the servers
+    // Bind every server to a random port first, so that they can be configured with each other's URLs
+    let auth_listener = TcpListener::bind("localhost:0").await.unwrap();
+    let auth_server_url = format!("http://{}", auth_listener.local_addr().unwrap());
+    let resource_listener = TcpListener::bind("localhost:0").await.unwrap();
+    let resource_server_url = format!("http://{}", resource_listener.local_addr().unwrap());
+    let redirect_listener = TcpListener::bind("localhost:0").await.unwrap();
+    let redirect_server_url = format!("http://{}", redirect_listener.local_addr().unwrap());
+
+    unsafe {
+        // Configure both servers: tokens are issued by the Authorization Server for the
+        // Resource Server
+        env::set_var("ISSUER", &auth_server_url);
+        env::set_var(
+            "SIGNING_KEY_PATH",
+            concat!(env!("CARGO_MANIFEST_DIR"), "/../unsafe-private.pem"),
+        );
+        env::set_var("ADMIN_TOKEN", ADMIN_TOKEN);
+        env::set_var("RESOURCES", &resource_server_url);
+        env::set_var("DEFAULT_RESOURCE", &resource_server_url);
+        env::set_var("AUTHORIZATION_SERVER_URL", &auth_server_url);
+        env::set_var("AUDIENCE", &resource_server_url);
+    }
+
+    // Step 2: Start the Authorization Server
+    let auth_server = authorization_server::router::router();
+    let auth_server_handle = task::spawn(async move {
+        axum::serve(auth_listener, auth_server).await.unwrap();
+    });
+
+    // Step 3: Start the Resource Server
+    let resource_server = resource_server::router::router();
+    let resource_server_handle = task::spawn(async move {
+        axum::serve(resource_listener, resource_server)
+            .await
+            .unwrap();
+    });
+
+    // Step 4: Start the Mock Redirect URI Server
+    let redirect_server = Router::new().route(
+        "/callback",
+        get(|query: Query<HashMap<String, String>>| async move {
+            println!("Callback received with query: {:?}", query);
+            "Callback handled".into_response()
+        }),
+    );
+    let redirect_server_handle = task::spawn(async move {
+        axum::serve(redirect_listener, redirect_server)
+            .await
+            .unwrap();
+    });
+
+    // Wait for the servers to start
+    sleep(Duration::from_secs(1)).await;
+
+    // Step 5: Simulate the Client
+    // Create an HTTP client with redirect following disabled
+    let client = Client::builder().redirect(Policy::none()).build().unwrap();
+
+    // Step 6: Create the user who authorizes the client
+    let user_response = client
+        .post(format!("{auth_server_url}/admin/users"))
+        .bearer_auth(ADMIN_TOKEN)
+        .json(&json!({ "username": USERNAME, "password": PASSWORD }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(user_response.status(), 200);
+    let user_id = user_response.json::<Value>().await.unwrap()["user_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    // Step 7: Register the client
+    // Send a registration request to the Authorization Server
+    let redirect_uri = format!("{redirect_server_url}/callback");
+    let registration_response = client
+        .post(format!("{auth_server_url}/register"))
+        .json(&json!({
+            "client_name": "Test Client",
+            "redirect_uris": [redirect_uri]
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(registration_response.status(), 201);
+
+    let registration_response_json: Value = registration_response.json().await.unwrap();
+    let client_id = registration_response_json["client_id"].as_str().unwrap();
+    let client_secret = registration_response_json["client_secret"]
+        .as_str()
+        .unwrap();
+
+    // Step 8: Simulate an /authorize request
+    // Without a session, the Authorization Server shows the login form and sets the CSRF cookie
+    let authorization_request = [
+        ("client_id", client_id),
+        ("response_type", "code"),
+        ("state", "xyz"),
+        ("redirect_uri", redirect_uri.as_str()),
+    ];
+    let login_form_response = client
+        .get(format!("{auth_server_url}/authorize"))
+        .query(&authorization_request)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(login_form_response.status(), 200);
+
+    let csrf_cookie = login_form_response
+        .headers()
+        .get_all(header::SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok()?.split(';').next())
+        .find(|cookie| cookie.starts_with("csrf="))
+        .unwrap()
+        .to_string();
+    let login_form = login_form_response.text().await.unwrap();
+    let csrf_token = login_form
+        .split(r#"name="csrf_token" value=""#)
+        .nth(1)
+        .and_then(|rest| rest.split('"').next())
+        .unwrap()
+        .to_string();
+
+    // Step 9: Log in and consent
+    // Submit the login form, allowing the client on the consent screen at the same time
+    let mut form = authorization_request.to_vec();
+    form.extend([
+        ("username", USERNAME),
+        ("password", PASSWORD),
+        ("consent", "allow"),
+        ("csrf_token", csrf_token.as_str()),
+    ]);
+    let authorize_response = client
+        .post(format!("{auth_server_url}/authorize"))
+        .header(header::COOKIE, csrf_cookie)
+        .form(&form)
+        .send()
+        .await
+        .unwrap();
+
+    // Verify the 303 See Other response
+    assert_eq!(authorize_response.status(), 303);
+
+    let redirect_location = authorize_response
+        .headers()
+        .get("location")
+        .unwrap()
+        .to_str()
+        .unwrap();
+    let redirect_location = url::Url::parse(redirect_location).unwrap();
+    let code = query_param(&redirect_location, "code");
+    assert_eq!(query_param(&redirect_location, "state"), "xyz");
+
+    // Step 10: Simulate a /token request
+    // Exchange the authorization code for an access token
+    let token_response = client
+        .post(format!("{auth_server_url}/token"))
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code.as_str()),
+            ("client_id", client_id),
+            ("client_secret", client_secret),
+            ("redirect_uri", redirect_uri.as_str()),
+        ])
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(token_response.status(), 200);
+
+    let token_response_json: Value = token_response.json().await.unwrap();
+    let access_token = token_response_json["access_token"].as_str().unwrap();
+    assert_eq!(token_response_json["token_type"], "Bearer");
+    assert_eq!(token_response_json["expires_in"], 3600);
+
+    // Step 11: Simulate a request to the protected resource
+    // The Resource Server discovers and fetches the Authorization Server's keys on its own
+    let resource_response = client
+        .get(format!("{resource_server_url}/resource"))
+        .bearer_auth(access_token)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(resource_response.status(), 200);
+
+    let resource_response_json: Value = resource_response.json().await.unwrap();
+    assert_eq!(
+        resource_response_json["message"],
+        format!("Access granted to user: {}", user_id)
+    );
+
+    println!("Integration test passed!");
+
+    // Step 12: Stop the servers
+    // Abort all server tasks
+    auth_server_handle.abort();
+    resource_server_handle.abort();
+    redirect_server_handle.abort();
+}
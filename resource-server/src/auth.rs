@@ -0,0 +1,273 @@
+/// The `auth` module validates bearer access tokens for the Resource Server.
+/// It provides the `Authenticated` extractor, which exposes the validated claims to handlers,
+/// and the `RequireScopeLayer`, which lets routes declare the scopes they require.
+use crate::{AppState, jwks::decoding_key, revocation::is_revoked};
+use axum::{
+    extract::{FromRequestParts, Request},
+    http::{HeaderValue, StatusCode, header, request::Parts},
+    response::{IntoResponse, Response},
+};
+use jsonwebtoken::{Validation, decode, decode_header};
+use serde::Deserialize;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower::{Layer, Service};
+
+/// Represents the claims of a validated access token.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Claims {
+    /// The subject of the token.
+    pub sub: String,
+    /// The expiration time of the token (UNIX timestamp).
+    pub exp: u64,
+    /// The space-delimited scope granted to the token.
+    #[serde(default)]
+    pub scope: String,
+    /// The unique identifier of the token, used to check whether it was revoked.
+    #[serde(default)]
+    pub jti: Option<String>,
+    /// The client ID the token was issued to.
+    #[serde(default)]
+    pub client_id: Option<String>,
+    /// The time the token was issued (UNIX timestamp).
+    #[serde(default)]
+    pub iat: Option<u64>,
+}
+
+impl Claims {
+    /// Returns the scope tokens granted to the token.
+    pub fn scopes(&self) -> impl Iterator<Item = &str> {
+        self.scope.split_whitespace()
+    }
+
+    /// Checks whether every scope in `required` was granted to the token.
+    pub fn has_scopes<S: AsRef<str>>(&self, required: &[S]) -> bool {
+        required
+            .iter()
+            .all(|required| self.scopes().any(|scope| scope == required.as_ref()))
+    }
+}
+
+/// Represents the reasons a request can fail authentication or authorization.
+#[derive(Debug)]
+pub enum AuthError {
+    /// The request carries no bearer token.
+    MissingToken,
+    /// The bearer token is malformed, expired, revoked or has an invalid signature.
+    InvalidToken,
+    /// The token is valid but lacks the scopes required by the route.
+    InsufficientScope(String),
+    /// The key needed to validate the token could not be obtained.
+    KeyUnavailable(StatusCode),
+}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        // RFC 6750, Section 3: challenge the client with the reason of the failure
+        let (status, challenge) = match self {
+            AuthError::MissingToken => (StatusCode::UNAUTHORIZED, "Bearer".to_string()),
+            AuthError::InvalidToken => (
+                StatusCode::UNAUTHORIZED,
+                r#"Bearer error="invalid_token""#.to_string(),
+            ),
+            AuthError::InsufficientScope(scope) => (
+                StatusCode::FORBIDDEN,
+                format!(r#"Bearer error="insufficient_scope", scope="{scope}""#),
+            ),
+            AuthError::KeyUnavailable(status) => return status.into_response(),
+        };
+
+        let mut response = status.into_response();
+        if let Ok(value) = HeaderValue::from_str(&challenge) {
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, value);
+        }
+        response
+    }
+}
+
+/// Validates the bearer token of a request and returns its claims.
+///
+/// Claims that were already validated for this request (e.g. by `RequireScopeLayer`) are reused.
+pub async fn authenticate(state: &AppState, parts: &mut Parts) -> Result<Claims, AuthError> {
+    if let Some(claims) = parts.extensions.get::<Claims>() {
+        return Ok(claims.clone());
+    }
+
+    // Extract the token from the Authorization header
+    let token = parts
+        .headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| {
+            tracing::warn!("Authorization header missing or invalid");
+            AuthError::MissingToken
+        })?;
+
+    let header = decode_header(token).map_err(|err| {
+        tracing::warn!("Malformed JWT header: {}", err);
+        AuthError::InvalidToken
+    })?;
+    // RFC 9068, Section 4: in strict mode, only explicitly typed access tokens are accepted
+    if state.strict_mode && !is_jwt_access_token_type(header.typ.as_deref()) {
+        tracing::warn!("Rejecting token of type: {:?}", header.typ);
+        return Err(AuthError::InvalidToken);
+    }
+
+    let public_key = decoding_key(state, &header).await?;
+
+    // Validate the JWT with the algorithm the selected key allows
+    let mut validation = Validation::new(header.alg);
+    validation.validate_exp = true; // Ensure token expiration is validated
+    validation.validate_nbf = true;
+    validation.leeway = state.leeway;
+    // Only accept tokens issued by the trusted issuer for this Resource Server
+    validation.set_issuer(&[&state.issuer]);
+    validation.set_audience(&[&state.audience]);
+    if state.strict_mode {
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+    } else {
+        validation.set_required_spec_claims(&["exp", "iss", "aud"]);
+    }
+
+    let claims = decode::<Claims>(token, &public_key, &validation)
+        .map_err(|err| {
+            tracing::warn!("JWT validation failed: {}", err);
+            AuthError::InvalidToken
+        })?
+        .claims;
+
+    if state.strict_mode
+        && (claims.client_id.is_none() || claims.iat.is_none() || claims.jti.is_none())
+    {
+        tracing::warn!("Token lacks claims required by RFC 9068");
+        return Err(AuthError::InvalidToken);
+    }
+
+    if let Some(jti) = &claims.jti
+        && is_revoked(state, jti)
+    {
+        tracing::warn!("Revoked token presented: {}", jti);
+        return Err(AuthError::InvalidToken);
+    }
+
+    tracing::info!("JWT validated successfully for user: {}", claims.sub);
+
+    parts.extensions.insert(claims.clone());
+    Ok(claims)
+}
+
+/// Checks whether `typ` identifies an RFC 9068 access token (`at+jwt`, optionally with the
+/// `application/` prefix).
+fn is_jwt_access_token_type(typ: Option<&str>) -> bool {
+    typ.is_some_and(|typ| {
+        let typ = typ.to_ascii_lowercase();
+        typ == "at+jwt" || typ == "application/at+jwt"
+    })
+}
+
+/// An extractor that validates the bearer token of a request and exposes its claims.
+#[derive(Clone, Debug)]
+pub struct Authenticated(pub Claims);
+
+impl FromRequestParts<Arc<AppState>> for Authenticated {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        authenticate(state, parts).await.map(Authenticated)
+    }
+}
+
+/// A layer that rejects requests whose access token lacks any of the required scopes.
+///
+/// Apply it with `route_layer` so that each route declares the scopes it requires.
+#[derive(Clone)]
+pub struct RequireScopeLayer {
+    state: Arc<AppState>,
+    scopes: Arc<[String]>,
+}
+
+impl RequireScopeLayer {
+    /// Creates a layer requiring every scope in `scopes`.
+    pub fn new<I, S>(state: Arc<AppState>, scopes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            state,
+            scopes: scopes.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl<S> Layer<S> for RequireScopeLayer {
+    type Service = RequireScope<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequireScope {
+            inner,
+            state: self.state.clone(),
+            scopes: self.scopes.clone(),
+        }
+    }
+}
+
+/// The service created by `RequireScopeLayer`.
+#[derive(Clone)]
+pub struct RequireScope<S> {
+    inner: S,
+    state: Arc<AppState>,
+    scopes: Arc<[String]>,
+}
+
+impl<S> Service<Request> for RequireScope<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        // Use the service that was driven to readiness, leaving a fresh clone in its place
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let state = self.state.clone();
+        let scopes = self.scopes.clone();
+
+        Box::pin(async move {
+            let (mut parts, body) = request.into_parts();
+
+            let claims = match authenticate(&state, &mut parts).await {
+                Ok(claims) => claims,
+                Err(err) => return Ok(err.into_response()),
+            };
+
+            if !claims.has_scopes(&scopes) {
+                tracing::warn!(
+                    "Token for user {} lacks required scope: {:?}",
+                    claims.sub,
+                    scopes
+                );
+                return Ok(AuthError::InsufficientScope(scopes.join(" ")).into_response());
+            }
+
+            inner.call(Request::from_parts(parts, body)).await
+        })
+    }
+}
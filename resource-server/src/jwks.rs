@@ -0,0 +1,300 @@
+/// The `jwks` module caches the Authorization Server's JWK set, so that access tokens can be
+/// validated without contacting the Authorization Server on every request. Tokens are verified
+/// with the key named by the `kid` in their header, which may be an RSA, EC or OKP key.
+///
+/// The key set is kept for as long as its `Cache-Control` header allows and refreshed in the
+/// background once it goes stale. Tokens signed with a key the cache does not know trigger an
+/// early refetch, which is rate limited so that forged `kid`s cannot flood the Authorization
+/// Server. When a refresh fails, the last key set that was fetched successfully keeps being used.
+use crate::{AppState, auth::AuthError, protected_resource::jwks_uri};
+use axum::http::StatusCode;
+use jsonwebtoken::{
+    Algorithm, DecodingKey, Header,
+    jwk::{AlgorithmParameters, EllipticCurve, Jwk, KeyOperations, PublicKeyUse},
+};
+use reqwest::header::{self, HeaderMap};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// How long a key set is cached when the response does not specify a `max-age`.
+pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(300);
+
+/// The default minimum time between two refetches triggered by requests.
+pub const DEFAULT_MIN_REFETCH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A key of the JWK set that can verify access tokens.
+#[derive(Clone)]
+struct VerificationKey {
+    /// The key itself.
+    key: DecodingKey,
+    /// The signature algorithms the key may be used with.
+    algorithms: Vec<Algorithm>,
+}
+
+impl VerificationKey {
+    /// Creates a verification key from a JWK, or explains why the JWK cannot verify tokens.
+    fn from_jwk(jwk: &Jwk) -> Result<Self, String> {
+        if let Some(key_use) = &jwk.common.public_key_use
+            && *key_use != PublicKeyUse::Signature
+        {
+            return Err(format!("its use is {key_use:?}"));
+        }
+        if let Some(key_operations) = &jwk.common.key_operations
+            && !key_operations.contains(&KeyOperations::Verify)
+        {
+            return Err("its key_ops do not include verify".to_string());
+        }
+
+        let algorithms = match &jwk.algorithm {
+            AlgorithmParameters::RSA(_) => vec![
+                Algorithm::RS256,
+                Algorithm::RS384,
+                Algorithm::RS512,
+                Algorithm::PS256,
+                Algorithm::PS384,
+                Algorithm::PS512,
+            ],
+            AlgorithmParameters::EllipticCurve(params) => match params.curve {
+                EllipticCurve::P256 => vec![Algorithm::ES256],
+                EllipticCurve::P384 => vec![Algorithm::ES384],
+                _ => return Err(format!("its curve {:?} is not supported", params.curve)),
+            },
+            AlgorithmParameters::OctetKeyPair(params) => match params.curve {
+                EllipticCurve::Ed25519 => vec![Algorithm::EdDSA],
+                _ => return Err(format!("its curve {:?} is not supported", params.curve)),
+            },
+            // Symmetric keys are secrets and have no business in a published key set
+            AlgorithmParameters::OctetKey(_) => {
+                return Err("symmetric keys are not accepted".to_string());
+            }
+        };
+        let algorithms = match jwk.common.algorithm {
+            Some(alg) if algorithms.contains(&alg) => vec![alg],
+            Some(alg) => return Err(format!("its alg {alg:?} does not match its type")),
+            None => algorithms,
+        };
+
+        let key = DecodingKey::from_jwk(jwk).map_err(|err| err.to_string())?;
+        Ok(Self { key, algorithms })
+    }
+}
+
+/// A key set that was fetched successfully.
+#[derive(Clone)]
+struct CachedJwks {
+    /// The keys that can verify access tokens, by `kid`.
+    keys: HashMap<String, VerificationKey>,
+    /// When the key set goes stale, according to its `Cache-Control` header.
+    expires_at: Instant,
+}
+
+/// The cache of the Authorization Server's JWK set.
+pub struct JwksCache {
+    /// The last key set that was fetched successfully.
+    cached: Mutex<Option<CachedJwks>>,
+    /// When the key set was last fetched, successfully or not.
+    last_fetch: Mutex<Option<Instant>>,
+    /// Serializes refetches, so that concurrent requests trigger a single one.
+    refetch: tokio::sync::Mutex<()>,
+    /// The minimum time between two refetches triggered by requests.
+    min_refetch_interval: Duration,
+}
+
+impl JwksCache {
+    /// Creates an empty cache that refetches at most once per `min_refetch_interval` on demand.
+    pub fn new(min_refetch_interval: Duration) -> Self {
+        Self {
+            cached: Mutex::new(None),
+            last_fetch: Mutex::new(None),
+            refetch: tokio::sync::Mutex::new(()),
+            min_refetch_interval,
+        }
+    }
+
+    /// Returns the time until the cached key set goes stale, or `None` if nothing is cached.
+    pub fn expires_in(&self) -> Option<Duration> {
+        self.cached
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|cached| cached.expires_at.saturating_duration_since(Instant::now()))
+    }
+
+    /// Checks whether a refetch was attempted within the minimum refetch interval.
+    fn recently_fetched(&self) -> bool {
+        self.last_fetch
+            .lock()
+            .unwrap()
+            .is_some_and(|last_fetch| last_fetch.elapsed() < self.min_refetch_interval)
+    }
+}
+
+impl Default for JwksCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_MIN_REFETCH_INTERVAL)
+    }
+}
+
+/// Returns the key to verify a token with the given header.
+///
+/// The key is selected by the `kid` of the header, and must allow the header's `alg`. Tokens
+/// without a `kid` are only accepted if the key set holds a single key. The cached key set is
+/// refetched if it is missing, stale, or does not contain the `kid`, unless a refetch was already
+/// attempted within the minimum refetch interval. If the refetch fails, the last good key set is
+/// used.
+pub async fn decoding_key(state: &AppState, header: &Header) -> Result<DecodingKey, AuthError> {
+    let kid = header.kid.as_deref();
+    let needs_refetch = match state.jwks.cached.lock().unwrap().as_ref() {
+        None => true,
+        Some(cached) => {
+            cached.expires_at <= Instant::now()
+                || kid.is_some_and(|kid| !cached.keys.contains_key(kid))
+        }
+    };
+
+    if needs_refetch {
+        let _refetch = state.jwks.refetch.lock().await;
+        if state.jwks.recently_fetched() {
+            tracing::debug!("Not refetching JWKS, it was fetched recently");
+        } else if fetch_jwks(state).await.is_err() {
+            tracing::warn!("Keeping the last good JWKS");
+        }
+    }
+
+    let cached = state.jwks.cached.lock().unwrap().clone().ok_or_else(|| {
+        tracing::error!("No JWKS available");
+        AuthError::KeyUnavailable(StatusCode::BAD_GATEWAY)
+    })?;
+
+    let key = match kid {
+        Some(kid) => cached.keys.get(kid),
+        None if cached.keys.len() == 1 => cached.keys.values().next(),
+        None => None,
+    }
+    .ok_or_else(|| {
+        tracing::warn!("No key in the JWKS matches kid: {:?}", kid);
+        AuthError::InvalidToken
+    })?;
+
+    if !key.algorithms.contains(&header.alg) {
+        tracing::warn!("Key {:?} may not be used with {:?}", kid, header.alg);
+        return Err(AuthError::InvalidToken);
+    }
+    Ok(key.key.clone())
+}
+
+/// Refetches the key set, regardless of the state of the cache.
+///
+/// On failure, the last good key set stays cached.
+pub async fn refresh_jwks(state: &AppState) -> Result<(), StatusCode> {
+    let _refetch = state.jwks.refetch.lock().await;
+    fetch_jwks(state).await
+}
+
+/// Spawns a task that refetches the key set whenever it goes stale.
+///
+/// Refetches happen at most once per minimum refetch interval, so that key sets that must not be
+/// cached do not keep the task busy.
+pub fn spawn_jwks_refresher(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        loop {
+            let delay = state
+                .jwks
+                .expires_in()
+                .unwrap_or_default()
+                .max(state.jwks.min_refetch_interval);
+            tokio::time::sleep(delay).await;
+
+            if refresh_jwks(&state).await.is_err() {
+                tracing::warn!("Keeping the last good JWKS");
+            }
+        }
+    });
+}
+
+/// Fetches the key set from the Authorization Server and caches it.
+async fn fetch_jwks(state: &AppState) -> Result<(), StatusCode> {
+    *state.jwks.last_fetch.lock().unwrap() = Some(Instant::now());
+
+    let jwks_url = jwks_uri(state, &state.http_client).await?;
+    tracing::info!("Fetching JWKS from: {}", jwks_url);
+
+    let response = state
+        .http_client
+        .get(&jwks_url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|err| {
+            tracing::error!("Failed to fetch JWKS: {}", err);
+            StatusCode::BAD_GATEWAY
+        })?;
+    let max_age = max_age(response.headers());
+    let jwks = response.json::<Value>().await.map_err(|err| {
+        tracing::error!("Failed to parse JWKS response: {}", err);
+        StatusCode::BAD_GATEWAY
+    })?;
+
+    // Skip the keys that cannot verify tokens rather than rejecting the whole set
+    let mut keys = HashMap::new();
+    for jwk in jwks["keys"].as_array().cloned().unwrap_or_default() {
+        let jwk = match serde_json::from_value::<Jwk>(jwk) {
+            Ok(jwk) => jwk,
+            Err(err) => {
+                tracing::warn!("Ignoring malformed JWK: {}", err);
+                continue;
+            }
+        };
+        let Some(kid) = jwk.common.key_id.clone() else {
+            tracing::warn!("Ignoring JWK without kid");
+            continue;
+        };
+        match VerificationKey::from_jwk(&jwk) {
+            Ok(key) => {
+                keys.insert(kid, key);
+            }
+            Err(reason) => tracing::warn!("Ignoring JWK {}: {}", kid, reason),
+        }
+    }
+
+    if keys.is_empty() {
+        tracing::error!("JWKS contains no usable keys");
+        return Err(StatusCode::BAD_GATEWAY);
+    }
+
+    tracing::info!("JWKS fetched successfully, caching it for {:?}", max_age);
+    *state.jwks.cached.lock().unwrap() = Some(CachedJwks {
+        keys,
+        expires_at: Instant::now() + max_age,
+    });
+    Ok(())
+}
+
+/// Returns how long a response may be cached according to its `Cache-Control` header.
+fn max_age(headers: &HeaderMap) -> Duration {
+    let Some(cache_control) = headers
+        .get(header::CACHE_CONTROL)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return DEFAULT_MAX_AGE;
+    };
+
+    let mut max_age = DEFAULT_MAX_AGE;
+    for directive in cache_control.split(',').map(str::trim) {
+        let directive = directive.to_ascii_lowercase();
+        if directive == "no-cache" || directive == "no-store" {
+            return Duration::ZERO;
+        }
+        if let Some(seconds) = directive
+            .strip_prefix("max-age=")
+            .and_then(|seconds| seconds.trim_matches('"').parse().ok())
+        {
+            max_age = Duration::from_secs(seconds);
+        }
+    }
+    max_age
+}
@@ -1,13 +1,39 @@
+pub mod auth;
+pub mod jwks;
 pub mod protected_resource;
+pub mod revocation;
 pub mod router;
 
-use jsonwebtoken::DecodingKey;
-use std::sync::Arc;
+use jwks::JwksCache;
+use reqwest::Client;
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
 
 /// Application state for the Resource Server.
 #[derive(Clone)]
 pub struct AppState {
-    pub tokens: Arc<std::sync::Mutex<std::collections::HashMap<String, String>>>,
-    pub public_key: Arc<std::sync::Mutex<Option<DecodingKey>>>,
+    /// The cached JWK set of the Authorization Server.
+    pub jwks: Arc<JwksCache>,
+    /// The HTTP client used to reach the Authorization Server.
+    pub http_client: Client,
     pub authorization_server_url: String, // Add the Authorization Server URL to the state
+    /// The issuer access tokens must be issued by (`iss` claim).
+    pub issuer: String,
+    /// The audience access tokens must be intended for (`aud` claim), i.e. this Resource Server.
+    pub audience: String,
+    /// The clock skew, in seconds, tolerated when validating `exp` and `nbf`.
+    pub leeway: u64,
+    /// Whether only access tokens following the JWT profile of RFC 9068 are accepted.
+    pub strict_mode: bool,
+    /// The URL of the Authorization Server's JWK set, discovered from its metadata unless configured.
+    pub jwks_uri: Arc<Mutex<Option<String>>>,
+    /// The URL of the Authorization Server's list of revoked tokens, discovered unless configured.
+    pub revoked_tokens_uri: Arc<Mutex<Option<String>>>,
+    /// The client ID and secret the Resource Server authenticates with to the Authorization
+    /// Server, if it is registered as a client.
+    pub client_credentials: Option<(String, String)>,
+    /// The `jti` of every revoked access token that has not expired yet.
+    pub revoked_tokens: Arc<Mutex<HashSet<String>>>,
 }
@@ -1,8 +1,5 @@
-use resource_server::{AppState, router::router};
+use resource_server::router::router;
 use tokio::net::TcpListener;
-use tracing_subscriber;
-
-mod protected_resource;
 
 #[tokio::main]
 async fn main() {
@@ -1,145 +1,103 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
-use axum::{extract::State, http::StatusCode, response::Json};
-use dotenvy::dotenv;
-use jsonwebtoken::{DecodingKey, Validation, decode};
+use axum::{http::StatusCode, response::Json};
 use reqwest::Client;
-use serde::{Deserialize, Serialize};
-use tracing;
+use serde::Serialize;
 
-use crate::AppState;
+use crate::{AppState, auth::Authenticated};
 
 #[derive(Serialize)]
 pub struct ProtectedResource {
     pub message: String,
 }
 
-#[derive(Deserialize)]
-struct Claims {
-    sub: String,
-    exp: u64,
-}
-
 /// Handles requests to the `/resource` endpoint.
 ///
-/// Validates the JWT in the `Authorization` header and grants access to the protected resource.
-#[axum_macros::debug_handler]
-pub async fn protected_resource(
-    State(state): State<Arc<AppState>>,
-    headers: axum::http::HeaderMap,
-) -> Result<Json<ProtectedResource>, StatusCode> {
-    dotenv().ok(); // Load environment variables from .env
-
+/// Access requires a valid JWT in the `Authorization` header carrying the `read` scope,
+/// which is enforced by the `RequireScopeLayer` of the route.
+#[axum_macros::debug_handler(state = Arc<AppState>)]
+pub async fn protected_resource(Authenticated(claims): Authenticated) -> Json<ProtectedResource> {
     tracing::info!("Received request for protected resource");
 
-    fetch_public_key_handler(State(state.clone())).await?;
-
-    // Extract the Authorization header
-    let auth_header = headers
-        .get(axum::http::header::AUTHORIZATION)
-        .and_then(|value| value.to_str().ok());
-
-    if let Some(auth_header) = auth_header {
-        if let Some(token) = auth_header.strip_prefix("Bearer ") {
-            tracing::info!("Validating JWT: {token}");
-
-            let public_key = {
-                let key_guard = state.public_key.lock().unwrap();
-                key_guard.clone()
-            };
-
-            if public_key.is_none() {
-                tracing::error!("Public key not available");
-                return Err(StatusCode::INTERNAL_SERVER_ERROR);
-            }
-
-            // Validate the JWT
-            let mut validation = Validation::new(jsonwebtoken::Algorithm::RS256); // Explicitly require RS256
-            validation.validate_exp = true; // Ensure token expiration is validated
-
-            match decode::<Claims>(token, public_key.as_ref().unwrap(), &validation) {
-                Ok(token_data) => {
-                    let claims = token_data.claims;
-
-                    // Check token expiration
-                    let now = std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs();
-                    if claims.exp < now {
-                        tracing::warn!("JWT has expired");
-                        return Err(StatusCode::UNAUTHORIZED);
-                    }
+    Json(ProtectedResource {
+        message: format!("Access granted to user: {}", claims.sub),
+    })
+}
 
-                    tracing::info!("JWT validated successfully for user: {}", claims.sub);
-                    return Ok(Json(ProtectedResource {
-                        message: format!("Access granted to user: {}", claims.sub),
-                    }));
-                }
-                Err(err) => {
-                    tracing::warn!("JWT validation failed: {}", err);
-                    return Err(StatusCode::UNAUTHORIZED);
-                }
-            }
-        }
-    }
+/// Returns the URL of the Authorization Server's JWK set.
+///
+/// Unless configured explicitly, the URL is discovered from the Authorization Server Metadata
+/// (RFC 8414) once and remembered.
+pub async fn jwks_uri(state: &AppState, client: &Client) -> Result<String, StatusCode> {
+    discover_endpoint(state, client, "jwks_uri", &state.jwks_uri).await
+}
 
-    tracing::warn!("Authorization header missing or invalid");
-    Err(StatusCode::UNAUTHORIZED)
+/// Returns the URL of the Authorization Server's list of revoked access tokens.
+///
+/// Unless configured explicitly, the URL is discovered from the Authorization Server Metadata
+/// (RFC 8414) once and remembered.
+pub async fn revoked_tokens_uri(state: &AppState, client: &Client) -> Result<String, StatusCode> {
+    discover_endpoint(
+        state,
+        client,
+        "revoked_tokens_uri",
+        &state.revoked_tokens_uri,
+    )
+    .await
 }
 
-/// Handles requests to the `/fetch-public-key` endpoint.
+/// Returns the endpoint stored in `endpoint`, discovering it from the metadata field `name`
+/// if it is not known yet.
 ///
-/// Fetches the public key from the Authorization Server and updates the shared application state.
-#[axum_macros::debug_handler]
-pub async fn fetch_public_key_handler(
-    State(state): State<Arc<AppState>>,
-) -> Result<StatusCode, StatusCode> {
-    let jwks_url = format!("{}/jwks.json", state.authorization_server_url);
-    tracing::info!("Fetching public key from JWKS endpoint: {}", jwks_url);
+/// Metadata whose `issuer` differs from the configured Authorization Server URL is rejected.
+async fn discover_endpoint(
+    state: &AppState,
+    client: &Client,
+    name: &str,
+    endpoint: &Mutex<Option<String>>,
+) -> Result<String, StatusCode> {
+    if let Some(url) = endpoint.lock().unwrap().clone() {
+        return Ok(url);
+    }
+
+    let issuer = state.authorization_server_url.trim_end_matches('/');
+    let metadata_url = format!("{issuer}/.well-known/oauth-authorization-server");
+    tracing::info!("Discovering {} from metadata: {}", name, metadata_url);
 
-    let client = Client::new();
-    let response = client
-        .get(&jwks_url)
+    let metadata = client
+        .get(&metadata_url)
         .send()
         .await
+        .and_then(|response| response.error_for_status())
         .map_err(|err| {
-            tracing::error!("Failed to fetch JWKS: {}", err);
+            tracing::error!("Failed to fetch Authorization Server metadata: {}", err);
             StatusCode::BAD_GATEWAY
         })?
         .json::<serde_json::Value>()
         .await
         .map_err(|err| {
-            tracing::error!("Failed to parse JWKS response: {}", err);
+            tracing::error!("Failed to parse Authorization Server metadata: {}", err);
             StatusCode::BAD_GATEWAY
         })?;
 
-    tracing::info!("Public key fetched successfully");
-
-    let jwk = response["keys"][0].clone();
-    let n = jwk["n"].as_str().ok_or_else(|| {
-        tracing::error!("Missing 'n' field in JWKS");
-        StatusCode::BAD_GATEWAY
-    })?;
-    let e = jwk["e"].as_str().ok_or_else(|| {
-        tracing::error!("Missing 'e' field in JWKS");
-        StatusCode::BAD_GATEWAY
-    })?;
-
-    tracing::info!("Modulus (n): {}", n);
-    tracing::info!("Exponent (e): {}", e);
-
-    // Use the Base64URL-encoded modulus and exponent to create the DecodingKey
-    let decoding_key = DecodingKey::from_rsa_components(n, e).map_err(|err| {
-        tracing::error!("Failed to create DecodingKey: {}", err);
-        StatusCode::BAD_GATEWAY
-    })?;
-
-    tracing::info!("Decoding key created successfully");
+    // The metadata must describe the server it was fetched from (RFC 8414 section 3.3)
+    if metadata["issuer"].as_str() != Some(issuer) {
+        tracing::error!(
+            "Authorization Server metadata issuer {} does not match {}",
+            metadata["issuer"],
+            issuer
+        );
+        return Err(StatusCode::BAD_GATEWAY);
+    }
 
-    let mut key_guard = state.public_key.lock().unwrap();
-    *key_guard = Some(decoding_key);
+    let url = metadata[name]
+        .as_str()
+        .ok_or_else(|| {
+            tracing::error!("Missing '{}' field in Authorization Server metadata", name);
+            StatusCode::BAD_GATEWAY
+        })?
+        .to_string();
 
-    tracing::info!("Public key fetched and stored successfully");
-    Ok(StatusCode::OK)
+    *endpoint.lock().unwrap() = Some(url.clone());
+    Ok(url)
 }
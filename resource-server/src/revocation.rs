@@ -0,0 +1,75 @@
+/// The `revocation` module keeps the Resource Server's copy of the Authorization Server's list
+/// of revoked access tokens. The list is polled periodically, and tokens whose `jti` is on it
+/// are rejected even though their signature and expiration are valid.
+use crate::{AppState, protected_resource::revoked_tokens_uri};
+use axum::http::StatusCode;
+use serde::Deserialize;
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+/// Represents a revoked access token listed by the Authorization Server.
+#[derive(Deserialize)]
+struct RevokedToken {
+    jti: String,
+}
+
+/// Represents the list of revoked access tokens published by the Authorization Server.
+#[derive(Deserialize)]
+struct RevokedTokens {
+    revoked: Vec<RevokedToken>,
+}
+
+/// Fetches the list of revoked access tokens and replaces the copy held in the state.
+///
+/// The Authorization Server only lists unexpired tokens, so replacing the copy also forgets
+/// tokens that have expired in the meantime. The request is authenticated with the client
+/// credentials of the Resource Server, if configured.
+pub async fn refresh_revoked_tokens(state: &AppState) -> Result<(), StatusCode> {
+    let url = revoked_tokens_uri(state, &state.http_client).await?;
+
+    let mut request = state.http_client.get(&url);
+    if let Some((client_id, client_secret)) = &state.client_credentials {
+        request = request.basic_auth(client_id, Some(client_secret));
+    }
+    let revoked = request
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|err| {
+            tracing::error!("Failed to fetch revoked tokens: {}", err);
+            StatusCode::BAD_GATEWAY
+        })?
+        .json::<RevokedTokens>()
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to parse revoked tokens: {}", err);
+            StatusCode::BAD_GATEWAY
+        })?
+        .revoked
+        .into_iter()
+        .map(|token| token.jti)
+        .collect::<HashSet<_>>();
+
+    tracing::info!("Fetched {} revoked token(s)", revoked.len());
+    *state.revoked_tokens.lock().unwrap() = revoked;
+    Ok(())
+}
+
+/// Checks whether the token with the given `jti` has been revoked.
+pub fn is_revoked(state: &AppState, jti: &str) -> bool {
+    state.revoked_tokens.lock().unwrap().contains(jti)
+}
+
+/// Spawns a task that refreshes the list of revoked access tokens every `interval`.
+///
+/// Failures are logged and the previous list is kept until the next successful poll.
+pub fn spawn_revocation_poller(state: Arc<AppState>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if refresh_revoked_tokens(&state).await.is_err() {
+                tracing::warn!("Keeping the previous list of revoked tokens");
+            }
+        }
+    });
+}
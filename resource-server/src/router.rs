@@ -1,31 +1,84 @@
 use crate::{
     AppState,
-    protected_resource::{fetch_public_key_handler, protected_resource},
+    auth::RequireScopeLayer,
+    jwks::{self, JwksCache, spawn_jwks_refresher},
+    protected_resource::protected_resource,
+    revocation::spawn_revocation_poller,
 };
 use axum::{Router, routing::get};
+use dotenvy::dotenv;
+use reqwest::Client;
 use std::{
-    collections::HashMap,
+    collections::HashSet,
     env,
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
 pub fn router() -> Router {
+    dotenv().ok(); // Load environment variables from .env
+
     // Read the AUTHORIZATION_SERVER_URL environment variable
     let authorization_server_url = env::var("AUTHORIZATION_SERVER_URL")
         .unwrap_or_else(|_| "http://localhost:3033".to_string()); // Default to localhost if not set
+    // Access tokens must be issued by ISSUER for AUDIENCE, allowing CLOCK_SKEW_LEEWAY seconds of skew
+    let issuer = env::var("ISSUER").unwrap_or_else(|_| "http://localhost:3033".to_string());
+    let audience = env::var("AUDIENCE").unwrap_or_else(|_| "http://localhost:3034".to_string());
+    let leeway = env::var("CLOCK_SKEW_LEEWAY")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(60);
+    // STRICT_MODE only accepts RFC 9068 access tokens (`typ: at+jwt`)
+    let strict_mode = env::var("STRICT_MODE")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(false);
+    // JWKS_URI overrides the JWKS endpoint discovered from the Authorization Server Metadata
+    let jwks_uri = env::var("JWKS_URI").ok();
+    let revoked_tokens_uri = env::var("REVOKED_TOKENS_URI").ok();
+    // The revoked tokens are listed to registered resource servers, which authenticate as clients
+    let client_credentials = env::var("CLIENT_ID")
+        .ok()
+        .zip(env::var("CLIENT_SECRET").ok());
+    // How often the list of revoked tokens is polled, in seconds
+    let revocation_poll_interval = env::var("REVOCATION_POLL_INTERVAL")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(30);
+    // The minimum time between two JWKS refetches triggered by tokens with an unknown `kid`
+    let jwks_min_refetch_interval = env::var("JWKS_MIN_REFETCH_INTERVAL")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(jwks::DEFAULT_MIN_REFETCH_INTERVAL);
 
     // Create the shared application state
-    let state = AppState {
-        tokens: Arc::new(Mutex::new(HashMap::new())),
-        public_key: Arc::new(Mutex::new(None)),
+    let state = Arc::new(AppState {
+        jwks: Arc::new(JwksCache::new(jwks_min_refetch_interval)),
+        http_client: Client::new(),
         authorization_server_url,
-    };
+        issuer,
+        audience,
+        leeway,
+        strict_mode,
+        jwks_uri: Arc::new(Mutex::new(jwks_uri)),
+        revoked_tokens_uri: Arc::new(Mutex::new(revoked_tokens_uri)),
+        client_credentials,
+        revoked_tokens: Arc::new(Mutex::new(HashSet::new())),
+    });
 
-    // Build the application
-    let app = Router::new()
-        .route("/resource", get(protected_resource))
-        .route("/fetch-public-key", get(fetch_public_key_handler)) // Add the fetch-public-key route
-        .with_state(Arc::new(state));
+    spawn_jwks_refresher(state.clone());
+    spawn_revocation_poller(state.clone(), Duration::from_secs(revocation_poll_interval));
 
-    app
+    router_with_state(state)
+}
+
+pub fn router_with_state(state: Arc<AppState>) -> Router {
+    // Build the application
+    Router::new()
+        .route(
+            "/resource",
+            get(protected_resource).route_layer(RequireScopeLayer::new(state.clone(), ["read"])),
+        )
+        .with_state(state)
 }
@@ -0,0 +1,102 @@
+mod common;
+
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode, header},
+};
+use resource_server::router::router_with_state;
+use serde_json::{Value, json};
+use tower::util::ServiceExt;
+
+async fn app() -> Router {
+    let authorization_server_url = common::start_authorization_server(common::jwks()).await;
+    router_with_state(common::app_state(authorization_server_url))
+}
+
+async fn get_resource(app: &Router, claims: Value) -> StatusCode {
+    let request = Request::builder()
+        .uri("/resource")
+        .header(
+            header::AUTHORIZATION,
+            format!("Bearer {}", common::sign(claims)),
+        )
+        .body(Body::empty())
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap().status()
+}
+
+/// Returns valid claims with `name` replaced by `value`, or removed if `value` is null.
+fn claims_with(name: &str, value: Value) -> Value {
+    let mut claims = common::claims("user-1", "read");
+    if value.is_null() {
+        claims.as_object_mut().unwrap().remove(name);
+    } else {
+        claims[name] = value;
+    }
+    claims
+}
+
+#[tokio::test]
+async fn test_token_must_be_issued_by_the_configured_issuer() {
+    let app = app().await;
+
+    assert_eq!(
+        get_resource(&app, claims_with("iss", json!(common::ISSUER))).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        get_resource(
+            &app,
+            claims_with("iss", json!("http://other-issuer.example"))
+        )
+        .await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        get_resource(&app, claims_with("iss", Value::Null)).await,
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[tokio::test]
+async fn test_token_must_be_intended_for_the_configured_audience() {
+    let app = app().await;
+
+    assert_eq!(
+        get_resource(&app, claims_with("aud", json!([common::AUDIENCE]))).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        get_resource(&app, claims_with("aud", json!("http://other-api.example"))).await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        get_resource(&app, claims_with("aud", Value::Null)).await,
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[tokio::test]
+async fn test_clock_skew_is_tolerated_within_the_leeway() {
+    let app = app().await;
+    let now = common::now();
+
+    // The configured leeway is 60 seconds
+    assert_eq!(
+        get_resource(&app, claims_with("nbf", json!(now + 30))).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        get_resource(&app, claims_with("nbf", json!(now + 300))).await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        get_resource(&app, claims_with("exp", json!(now - 30))).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        get_resource(&app, claims_with("exp", json!(now - 300))).await,
+        StatusCode::UNAUTHORIZED
+    );
+}
@@ -0,0 +1,288 @@
+#![allow(dead_code)]
+
+use axum::{
+    Json, Router,
+    http::{HeaderMap, StatusCode, header},
+    response::IntoResponse,
+    routing::get,
+};
+use base64::{
+    Engine,
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+};
+use jsonwebtoken::{Algorithm, EncodingKey, Header, encode};
+use reqwest::Client;
+use resource_server::{AppState, jwks::JwksCache};
+use rsa::{PublicKeyParts, pkcs8::DecodePublicKey};
+use serde_json::{Value, json};
+use std::{
+    collections::HashSet,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::net::TcpListener;
+
+const PRIVATE_KEY: &str = include_str!("../../../unsafe-private.pem");
+const PUBLIC_KEY: &str = include_str!("../../../public.pem");
+
+/// The issuer the Resource Server under test trusts.
+pub const ISSUER: &str = "http://issuer.example";
+/// The audience identifying the Resource Server under test.
+pub const AUDIENCE: &str = "http://resource.example";
+/// The client ID the Resource Server under test authenticates with.
+pub const CLIENT_ID: &str = "resource-server";
+/// The client secret the Resource Server under test authenticates with.
+pub const CLIENT_SECRET: &str = "resource-server-secret";
+
+const EC_PRIVATE_KEY: &str = include_str!("../fixtures/ec-p256-private.pem");
+const ED25519_PRIVATE_KEY: &str = include_str!("../fixtures/ed25519-private.pem");
+
+/// Returns the JWK set of the development key pair in the workspace root.
+pub fn jwks() -> Value {
+    json!({ "keys": [rsa_jwk()] })
+}
+
+/// Returns the JWK of the development key pair in the workspace root.
+pub fn rsa_jwk() -> Value {
+    let public_key = rsa::RsaPublicKey::from_public_key_pem(PUBLIC_KEY).unwrap();
+    json!({
+        "kty": "RSA",
+        "kid": "key-id-1",
+        "use": "sig",
+        "alg": "RS256",
+        "n": URL_SAFE_NO_PAD.encode(public_key.n().to_bytes_be()),
+        "e": URL_SAFE_NO_PAD.encode(public_key.e().to_bytes_be()),
+    })
+}
+
+/// Returns the JWK of the P-256 key in `tests/fixtures`.
+pub fn ec_jwk() -> Value {
+    json!({
+        "kty": "EC",
+        "kid": "ec-key",
+        "use": "sig",
+        "alg": "ES256",
+        "crv": "P-256",
+        "x": "r_p34Xks2tB2z8636mATphSKFUmg1scNuP7bpV6LoD8",
+        "y": "bU0l5iB5C8YlZdWsr76CZGUdAq1_UE4Gu-6ycIngaUo",
+    })
+}
+
+/// Returns the JWK of the Ed25519 key in `tests/fixtures`.
+pub fn okp_jwk() -> Value {
+    json!({
+        "kty": "OKP",
+        "kid": "okp-key",
+        "use": "sig",
+        "alg": "EdDSA",
+        "crv": "Ed25519",
+        "x": "bz1Ueu7EfdNVK3koIW8sikOR4f9xY1FaQKObLBBAfuU",
+    })
+}
+
+/// A mock Authorization Server that serves its metadata, a JWK set and a list of revoked tokens,
+/// which it only lists to the client `CLIENT_ID`.
+pub struct MockAuthorizationServer {
+    /// The base URL of the server.
+    pub url: String,
+    /// The number of requests made to `/jwks.json`.
+    pub jwks_requests: Arc<AtomicUsize>,
+    /// Whether `/jwks.json` responds; when `false` it fails with `503 Service Unavailable`.
+    pub available: Arc<AtomicBool>,
+}
+
+impl MockAuthorizationServer {
+    /// Returns the number of requests made to `/jwks.json` so far.
+    pub fn jwks_requests(&self) -> usize {
+        self.jwks_requests.load(Ordering::SeqCst)
+    }
+
+    /// Makes `/jwks.json` succeed or fail.
+    pub fn set_available(&self, available: bool) {
+        self.available.store(available, Ordering::SeqCst);
+    }
+}
+
+/// Starts a mock Authorization Server serving its metadata and `jwks`, and returns its URL.
+pub async fn start_authorization_server(jwks: Value) -> String {
+    start_authorization_server_with_revoked(jwks, &[]).await
+}
+
+/// Starts a mock Authorization Server that also lists the given `jti`s as revoked.
+pub async fn start_authorization_server_with_revoked(jwks: Value, revoked: &[&str]) -> String {
+    start_mock_authorization_server(jwks, revoked, "public, max-age=300")
+        .await
+        .url
+}
+
+/// Starts a mock Authorization Server serving `jwks` with the given `Cache-Control` header.
+pub async fn start_mock_authorization_server(
+    jwks: Value,
+    revoked: &[&str],
+    cache_control: &'static str,
+) -> MockAuthorizationServer {
+    let listener = TcpListener::bind("localhost:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let jwks_requests = Arc::new(AtomicUsize::new(0));
+    let available = Arc::new(AtomicBool::new(true));
+
+    let metadata = json!({
+        "issuer": url,
+        "jwks_uri": format!("{url}/jwks.json"),
+        "revoked_tokens_uri": format!("{url}/revoked"),
+    });
+    let revoked = json!({
+        "revoked": revoked
+            .iter()
+            .map(|jti| json!({ "jti": jti, "exp": now() + 3600 }))
+            .collect::<Vec<_>>()
+    });
+    let app = Router::new()
+        .route(
+            "/.well-known/oauth-authorization-server",
+            get(move || {
+                let metadata = metadata.clone();
+                async move { Json(metadata) }
+            }),
+        )
+        .route(
+            "/jwks.json",
+            get({
+                let jwks_requests = jwks_requests.clone();
+                let available = available.clone();
+                move || {
+                    let jwks = jwks.clone();
+                    jwks_requests.fetch_add(1, Ordering::SeqCst);
+                    let available = available.load(Ordering::SeqCst);
+                    async move {
+                        if !available {
+                            return StatusCode::SERVICE_UNAVAILABLE.into_response();
+                        }
+                        ([(header::CACHE_CONTROL, cache_control)], Json(jwks)).into_response()
+                    }
+                }
+            }),
+        )
+        .route(
+            "/revoked",
+            get(move |headers: HeaderMap| {
+                let revoked = revoked.clone();
+                let credentials = STANDARD.encode(format!("{CLIENT_ID}:{CLIENT_SECRET}"));
+                let authorized = headers
+                    .get(header::AUTHORIZATION)
+                    .is_some_and(|value| *value == format!("Basic {credentials}"));
+                async move {
+                    if !authorized {
+                        return StatusCode::UNAUTHORIZED.into_response();
+                    }
+                    Json(revoked).into_response()
+                }
+            }),
+        );
+
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    MockAuthorizationServer {
+        url,
+        jwks_requests,
+        available,
+    }
+}
+
+/// Creates the Resource Server state for the given Authorization Server URL.
+pub fn app_state(authorization_server_url: String) -> Arc<AppState> {
+    app_state_with_jwks_cache(authorization_server_url, JwksCache::default())
+}
+
+/// Creates the Resource Server state using the given JWKS cache.
+pub fn app_state_with_jwks_cache(
+    authorization_server_url: String,
+    jwks: JwksCache,
+) -> Arc<AppState> {
+    Arc::new(AppState {
+        jwks: Arc::new(jwks),
+        http_client: Client::new(),
+        authorization_server_url,
+        issuer: ISSUER.to_string(),
+        audience: AUDIENCE.to_string(),
+        leeway: 60,
+        strict_mode: false,
+        jwks_uri: Arc::new(Mutex::new(None)),
+        revoked_tokens_uri: Arc::new(Mutex::new(None)),
+        client_credentials: Some((CLIENT_ID.to_string(), CLIENT_SECRET.to_string())),
+        revoked_tokens: Arc::new(Mutex::new(HashSet::new())),
+    })
+}
+
+/// Returns the current time as a UNIX timestamp.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Signs `claims` with the development private key.
+pub fn sign(claims: Value) -> String {
+    sign_with_kid(claims, "key-id-1")
+}
+
+/// Signs `claims` with the development private key, announcing it as `kid`.
+pub fn sign_with_kid(claims: Value, kid: &str) -> String {
+    let key = EncodingKey::from_rsa_pem(PRIVATE_KEY.as_bytes()).unwrap();
+    sign_with_key(claims, Algorithm::RS256, &key, Some(kid))
+}
+
+/// Signs `claims` with the development private key as an RFC 9068 access token.
+pub fn sign_at_jwt(claims: Value) -> String {
+    let mut header = Header::new(Algorithm::RS256);
+    header.typ = Some("at+jwt".to_string());
+    header.kid = Some("key-id-1".to_string());
+    let key = EncodingKey::from_rsa_pem(PRIVATE_KEY.as_bytes()).unwrap();
+    encode(&header, &claims, &key).unwrap()
+}
+
+/// Signs `claims` with the P-256 key in `tests/fixtures`.
+pub fn sign_es256(claims: Value) -> String {
+    let key = EncodingKey::from_ec_pem(EC_PRIVATE_KEY.as_bytes()).unwrap();
+    sign_with_key(claims, Algorithm::ES256, &key, Some("ec-key"))
+}
+
+/// Signs `claims` with the Ed25519 key in `tests/fixtures`.
+pub fn sign_eddsa(claims: Value) -> String {
+    let key = EncodingKey::from_ed_pem(ED25519_PRIVATE_KEY.as_bytes()).unwrap();
+    sign_with_key(claims, Algorithm::EdDSA, &key, Some("okp-key"))
+}
+
+/// Signs `claims` with `key` and `alg`, announcing the key as `kid`.
+pub fn sign_with_key(
+    claims: Value,
+    alg: Algorithm,
+    key: &EncodingKey,
+    kid: Option<&str>,
+) -> String {
+    let mut header = Header::new(alg);
+    header.kid = kid.map(str::to_string);
+    encode(&header, &claims, key).unwrap()
+}
+
+/// Returns a signed access token for `sub` with the given scope.
+pub fn access_token(sub: &str, scope: &str) -> String {
+    sign(claims(sub, scope))
+}
+
+/// Returns valid claims of an access token for `sub` with the given scope.
+pub fn claims(sub: &str, scope: &str) -> Value {
+    json!({
+        "iss": ISSUER,
+        "aud": AUDIENCE,
+        "sub": sub,
+        "client_id": "client-1",
+        "jti": uuid::Uuid::new_v4().to_string(),
+        "iat": now(),
+        "exp": now() + 3600,
+        "scope": scope,
+    })
+}
@@ -0,0 +1,242 @@
+mod common;
+
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode, header},
+};
+use resource_server::{
+    jwks::{JwksCache, refresh_jwks},
+    router::router_with_state,
+};
+use serde_json::json;
+use std::time::Duration;
+use tower::util::ServiceExt;
+
+async fn get_resource(app: &Router, token: &str) -> StatusCode {
+    let request = Request::builder()
+        .uri("/resource")
+        .header(header::AUTHORIZATION, format!("Bearer {token}"))
+        .body(Body::empty())
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap().status()
+}
+
+fn token_with_kid(kid: &str) -> String {
+    common::sign_with_kid(common::claims("user-1", "read"), kid)
+}
+
+#[tokio::test]
+async fn test_jwks_is_cached_for_its_max_age() {
+    let server =
+        common::start_mock_authorization_server(common::jwks(), &[], "public, max-age=300").await;
+    let state = common::app_state(server.url.clone());
+    let app = router_with_state(state.clone());
+    let token = common::access_token("user-1", "read");
+
+    assert_eq!(get_resource(&app, &token).await, StatusCode::OK);
+    assert_eq!(get_resource(&app, &token).await, StatusCode::OK);
+    assert_eq!(server.jwks_requests(), 1);
+
+    let expires_in = state.jwks.expires_in().unwrap();
+    assert!(expires_in > Duration::from_secs(290) && expires_in <= Duration::from_secs(300));
+}
+
+#[tokio::test]
+async fn test_uncacheable_jwks_is_refetched() {
+    let server = common::start_mock_authorization_server(common::jwks(), &[], "no-store").await;
+    let state =
+        common::app_state_with_jwks_cache(server.url.clone(), JwksCache::new(Duration::ZERO));
+    let app = router_with_state(state);
+    let token = common::access_token("user-1", "read");
+
+    assert_eq!(get_resource(&app, &token).await, StatusCode::OK);
+    assert_eq!(get_resource(&app, &token).await, StatusCode::OK);
+    assert_eq!(server.jwks_requests(), 2);
+}
+
+#[tokio::test]
+async fn test_unknown_kid_refetches_are_rate_limited() {
+    let server =
+        common::start_mock_authorization_server(common::jwks(), &[], "public, max-age=300").await;
+    let state = common::app_state_with_jwks_cache(
+        server.url.clone(),
+        JwksCache::new(Duration::from_secs(3600)),
+    );
+    let app = router_with_state(state);
+
+    assert_eq!(
+        get_resource(&app, &token_with_kid("key-id-1")).await,
+        StatusCode::OK
+    );
+    for _ in 0..3 {
+        assert_eq!(
+            get_resource(&app, &token_with_kid("unknown-key")).await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+    assert_eq!(server.jwks_requests(), 1);
+}
+
+#[tokio::test]
+async fn test_unknown_kid_triggers_a_refetch() {
+    let server =
+        common::start_mock_authorization_server(common::jwks(), &[], "public, max-age=300").await;
+    let state =
+        common::app_state_with_jwks_cache(server.url.clone(), JwksCache::new(Duration::ZERO));
+    let app = router_with_state(state);
+
+    assert_eq!(
+        get_resource(&app, &token_with_kid("key-id-1")).await,
+        StatusCode::OK
+    );
+    get_resource(&app, &token_with_kid("unknown-key")).await;
+    assert_eq!(server.jwks_requests(), 2);
+}
+
+#[tokio::test]
+async fn test_last_good_jwks_is_used_while_the_authorization_server_is_down() {
+    let server = common::start_mock_authorization_server(common::jwks(), &[], "no-cache").await;
+    let state =
+        common::app_state_with_jwks_cache(server.url.clone(), JwksCache::new(Duration::ZERO));
+    let app = router_with_state(state.clone());
+    let token = common::access_token("user-1", "read");
+
+    assert_eq!(get_resource(&app, &token).await, StatusCode::OK);
+
+    server.set_available(false);
+    assert_eq!(refresh_jwks(&state).await, Err(StatusCode::BAD_GATEWAY));
+    assert_eq!(get_resource(&app, &token).await, StatusCode::OK);
+    assert_eq!(server.jwks_requests(), 3);
+}
+
+#[tokio::test]
+async fn test_unavailable_jwks_without_a_cached_copy_is_a_bad_gateway() {
+    let server =
+        common::start_mock_authorization_server(common::jwks(), &[], "public, max-age=300").await;
+    server.set_available(false);
+    let app = router_with_state(common::app_state(server.url.clone()));
+
+    let token = common::access_token("user-1", "read");
+    assert_eq!(get_resource(&app, &token).await, StatusCode::BAD_GATEWAY);
+
+    // Failed fetches are rate limited as well
+    assert_eq!(get_resource(&app, &token).await, StatusCode::BAD_GATEWAY);
+    assert_eq!(server.jwks_requests(), 1);
+}
+
+#[tokio::test]
+async fn test_metadata_of_another_issuer_is_rejected() {
+    let server =
+        common::start_mock_authorization_server(common::jwks(), &[], "public, max-age=300").await;
+    // The same server under another name, which its metadata does not claim as issuer
+    let port = server.url.rsplit(':').next().unwrap();
+    let app = router_with_state(common::app_state(format!("http://localhost:{port}")));
+
+    let token = common::access_token("user-1", "read");
+    assert_eq!(get_resource(&app, &token).await, StatusCode::BAD_GATEWAY);
+    assert_eq!(server.jwks_requests(), 0);
+}
+
+#[tokio::test]
+async fn test_jwks_refetches_cannot_be_triggered_without_a_token() {
+    let server =
+        common::start_mock_authorization_server(common::jwks(), &[], "public, max-age=300").await;
+    let app = router_with_state(common::app_state(server.url.clone()));
+
+    let request = Request::builder()
+        .uri("/fetch-public-key")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(server.jwks_requests(), 0);
+}
+
+fn claims() -> serde_json::Value {
+    common::claims("user-1", "read")
+}
+
+async fn app_with_keys(keys: Vec<serde_json::Value>) -> Router {
+    let url = common::start_authorization_server(json!({ "keys": keys })).await;
+    router_with_state(common::app_state(url))
+}
+
+#[tokio::test]
+async fn test_key_is_selected_by_kid() {
+    // The key signing the RSA tokens is not the first one of the set
+    let app = app_with_keys(vec![common::ec_jwk(), common::okp_jwk(), common::rsa_jwk()]).await;
+
+    let token = common::sign_with_kid(claims(), "key-id-1");
+    assert_eq!(get_resource(&app, &token).await, StatusCode::OK);
+    assert_eq!(
+        get_resource(&app, &common::sign_es256(claims())).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        get_resource(&app, &common::sign_eddsa(claims())).await,
+        StatusCode::OK
+    );
+
+    // A token claiming to be signed by another key of the set is rejected
+    let token = common::sign_with_kid(claims(), "ec-key");
+    assert_eq!(get_resource(&app, &token).await, StatusCode::UNAUTHORIZED);
+    let token = common::sign_with_kid(claims(), "unknown-key");
+    assert_eq!(get_resource(&app, &token).await, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_token_without_kid_requires_a_single_key() {
+    let key = jsonwebtoken::EncodingKey::from_rsa_pem(include_bytes!("../../unsafe-private.pem"))
+        .unwrap();
+    let token = common::sign_with_key(claims(), jsonwebtoken::Algorithm::RS256, &key, None);
+
+    let app = app_with_keys(vec![common::rsa_jwk()]).await;
+    assert_eq!(get_resource(&app, &token).await, StatusCode::OK);
+
+    let app = app_with_keys(vec![common::rsa_jwk(), common::ec_jwk()]).await;
+    assert_eq!(get_resource(&app, &token).await, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_keys_not_meant_for_signatures_are_ignored() {
+    let mut encryption_key = common::rsa_jwk();
+    encryption_key["use"] = json!("enc");
+    let app = app_with_keys(vec![encryption_key, common::ec_jwk()]).await;
+
+    let token = common::access_token("user-1", "read");
+    assert_eq!(get_resource(&app, &token).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        get_resource(&app, &common::sign_es256(claims())).await,
+        StatusCode::OK
+    );
+}
+
+#[tokio::test]
+async fn test_token_alg_must_match_key_alg() {
+    let mut rs384_key = common::rsa_jwk();
+    rs384_key["alg"] = json!("RS384");
+    let app = app_with_keys(vec![rs384_key]).await;
+
+    let token = common::access_token("user-1", "read");
+    assert_eq!(get_resource(&app, &token).await, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_symmetric_keys_are_ignored() {
+    let secret = json!({ "kty": "oct", "kid": "hmac-key", "k": "c2VjcmV0" });
+    let app = app_with_keys(vec![secret, common::rsa_jwk()]).await;
+
+    let key = jsonwebtoken::EncodingKey::from_secret(b"secret");
+    let token = common::sign_with_key(
+        claims(),
+        jsonwebtoken::Algorithm::HS256,
+        &key,
+        Some("hmac-key"),
+    );
+    assert_eq!(get_resource(&app, &token).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        get_resource(&app, &common::access_token("user-1", "read")).await,
+        StatusCode::OK
+    );
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode, header},
+};
+use resource_server::{AppState, revocation::refresh_revoked_tokens, router::router_with_state};
+use serde_json::json;
+use tower::util::ServiceExt;
+
+#[tokio::test]
+async fn test_revoked_token_is_rejected() {
+    let authorization_server_url =
+        common::start_authorization_server_with_revoked(common::jwks(), &["revoked-jti"]).await;
+    let state = common::app_state(authorization_server_url);
+    let app = router_with_state(state.clone());
+
+    let token = |jti: &str| {
+        let mut claims = common::claims("user-1", "read");
+        claims["jti"] = json!(jti);
+        common::sign(claims)
+    };
+    let get_resource = |token: String| {
+        let request = Request::builder()
+            .uri("/resource")
+            .header(header::AUTHORIZATION, format!("Bearer {token}"))
+            .body(Body::empty())
+            .unwrap();
+        app.clone().oneshot(request)
+    };
+
+    // Until the denylist is polled, the token is accepted on its signature alone
+    let response = get_resource(token("revoked-jti")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    refresh_revoked_tokens(&state).await.unwrap();
+
+    let response = get_resource(token("revoked-jti")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        response.headers()[header::WWW_AUTHENTICATE],
+        r#"Bearer error="invalid_token""#
+    );
+
+    let response = get_resource(token("active-jti")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_revoked_tokens_are_fetched_with_the_client_credentials() {
+    let authorization_server_url =
+        common::start_authorization_server_with_revoked(common::jwks(), &["revoked-jti"]).await;
+    let state = common::app_state(authorization_server_url);
+
+    // The Authorization Server only lists revoked tokens to authenticated resource servers
+    let anonymous = AppState {
+        client_credentials: None,
+        ..(*state).clone()
+    };
+    assert_eq!(
+        refresh_revoked_tokens(&anonymous).await,
+        Err(StatusCode::BAD_GATEWAY)
+    );
+    assert!(anonymous.revoked_tokens.lock().unwrap().is_empty());
+
+    refresh_revoked_tokens(&state).await.unwrap();
+    assert!(state.revoked_tokens.lock().unwrap().contains("revoked-jti"));
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode, header},
+    response::Response,
+};
+use resource_server::router::router_with_state;
+use tower::util::ServiceExt;
+
+async fn app() -> Router {
+    let authorization_server_url = common::start_authorization_server(common::jwks()).await;
+    router_with_state(common::app_state(authorization_server_url))
+}
+
+async fn get_resource(app: &Router, authorization: Option<String>) -> Response {
+    let mut request = Request::builder().uri("/resource");
+    if let Some(authorization) = authorization {
+        request = request.header(header::AUTHORIZATION, authorization);
+    }
+    app.clone()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
+
+fn www_authenticate(response: &Response) -> &str {
+    response
+        .headers()
+        .get(header::WWW_AUTHENTICATE)
+        .unwrap()
+        .to_str()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_token_with_required_scope_is_granted_access() {
+    let app = app().await;
+    let token = common::access_token("user-1", "read write");
+
+    let response = get_resource(&app, Some(format!("Bearer {token}"))).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["message"], "Access granted to user: user-1");
+}
+
+#[tokio::test]
+async fn test_token_without_required_scope_is_forbidden() {
+    let app = app().await;
+    let token = common::access_token("user-1", "write");
+
+    let response = get_resource(&app, Some(format!("Bearer {token}"))).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(
+        www_authenticate(&response),
+        r#"Bearer error="insufficient_scope", scope="read""#
+    );
+}
+
+#[tokio::test]
+async fn test_missing_or_invalid_token_is_unauthorized() {
+    let app = app().await;
+
+    let response = get_resource(&app, None).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(www_authenticate(&response), "Bearer");
+
+    let response = get_resource(&app, Some("Bearer not-a-jwt".to_string())).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        www_authenticate(&response),
+        r#"Bearer error="invalid_token""#
+    );
+
+    let mut claims = common::claims("user-1", "read");
+    claims["exp"] = (common::now() - 3600).into();
+    let expired = common::sign(claims);
+    let response = get_resource(&app, Some(format!("Bearer {expired}"))).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
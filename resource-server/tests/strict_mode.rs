@@ -0,0 +1,73 @@
+mod common;
+
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode, header},
+};
+use resource_server::{AppState, router::router_with_state};
+use std::sync::Arc;
+use tower::util::ServiceExt;
+
+async fn app(strict_mode: bool) -> Router {
+    let authorization_server_url = common::start_authorization_server(common::jwks()).await;
+    let state = common::app_state(authorization_server_url);
+    router_with_state(Arc::new(AppState {
+        strict_mode,
+        ..(*state).clone()
+    }))
+}
+
+async fn get_resource(app: &Router, token: &str) -> StatusCode {
+    let request = Request::builder()
+        .uri("/resource")
+        .header(header::AUTHORIZATION, format!("Bearer {token}"))
+        .body(Body::empty())
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap().status()
+}
+
+#[tokio::test]
+async fn test_strict_mode_requires_at_jwt_type() {
+    let app = app(true).await;
+    let claims = common::claims("user-1", "read");
+
+    assert_eq!(
+        get_resource(&app, &common::sign_at_jwt(claims.clone())).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        get_resource(&app, &common::sign(claims)).await,
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[tokio::test]
+async fn test_strict_mode_requires_rfc9068_claims() {
+    let app = app(true).await;
+
+    for claim in ["sub", "client_id", "iat", "jti"] {
+        let mut claims = common::claims("user-1", "read");
+        claims.as_object_mut().unwrap().remove(claim);
+        assert_eq!(
+            get_resource(&app, &common::sign_at_jwt(claims)).await,
+            StatusCode::UNAUTHORIZED,
+            "token without {claim} was accepted"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_lenient_mode_accepts_untyped_tokens() {
+    let app = app(false).await;
+    let claims = common::claims("user-1", "read");
+
+    assert_eq!(
+        get_resource(&app, &common::sign(claims.clone())).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        get_resource(&app, &common::sign_at_jwt(claims)).await,
+        StatusCode::OK
+    );
+}